@@ -0,0 +1,84 @@
+//! Times version resolution against a large listing, end to end.
+//!
+//! Run with `cargo bench --bench resolve`.
+
+use std::{
+    fs,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+const RUNS: u32 = 20;
+const PLATFORMS: &[&str] = &[
+    "linux-x86_64",
+    "linux-aarch64",
+    "macos-x86_64",
+    "macos-aarch64",
+];
+const BUILD_TYPES: &[&str] = &["cli", "fpm", "micro"];
+
+/// A listing in the upstream format with a few thousand releases per
+/// platform and build type, far more than upstream serves today.
+fn listing() -> (String, usize) {
+    let mut items = Vec::new();
+    for minor in 0..=4 {
+        for patch in 0..1000 {
+            for build_type in BUILD_TYPES {
+                for platform in PLATFORMS {
+                    let name = format!(
+                        "php-8.{}.{}-{}-{}.tar.gz",
+                        minor, patch, build_type, platform
+                    );
+                    items.push(format!(
+                        r#"{{"is_dir":false,"full_path":"/static-php-cli/bulk/{name}","name":"{name}","size":1,"last_modified":"2025-01-15 10:30:00","download_count":"","is_parent":false}}"#
+                    ));
+                }
+            }
+        }
+    }
+    (format!("[{}]", items.join(",")), items.len())
+}
+
+fn time(label: &str, cache_home: &std::path::Path, args: &[&str]) {
+    let mut total = Duration::ZERO;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let status = Command::new(env!("CARGO_BIN_EXE_spc-utils"))
+            .env("XDG_CACHE_HOME", cache_home)
+            .args(args)
+            .output()
+            .expect("spc-utils runs")
+            .status;
+        total += start.elapsed();
+        assert!(status.success(), "{} failed", label);
+    }
+    println!("{:<28} {:>8.2?} per run", label, total / RUNS);
+}
+
+fn main() {
+    let cache_home = tempfile::tempdir().unwrap();
+    let cache_dir = cache_home.path().join("spc-utils");
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join(".version"), env!("CARGO_PKG_VERSION")).unwrap();
+
+    let (json, entries) = listing();
+    fs::write(cache_dir.join("bulk.json"), json).unwrap();
+    println!("Listing with {} entries, {} runs each", entries, RUNS);
+
+    let platform = ["-C", "bulk", "-O", "linux", "-A", "x86_64"];
+    time(
+        "latest",
+        cache_home.path(),
+        &[&["latest"], &platform[..]].concat(),
+    );
+    time(
+        "latest -V 8.2",
+        cache_home.path(),
+        &[&["latest", "-V", "8.2"], &platform[..]].concat(),
+    );
+    time(
+        "list --json",
+        cache_home.path(),
+        &[&["list", "--json"], &platform[..]].concat(),
+    );
+}
@@ -0,0 +1,89 @@
+//! Records build metadata shown by `spc-utils --version` and `spc-utils version`,
+//! and compresses the bundled listing snapshot into the binary.
+
+use std::{fs, io::Write, path::Path, process::Command};
+
+/// Categories a snapshot can hold, named like their cache files.
+const BUNDLED_CATEGORIES: [&str; 6] = [
+    "bulk",
+    "common",
+    "minimal",
+    "win-min",
+    "win-max",
+    "frankenphp",
+];
+
+fn main() {
+    let commit = git(&["rev-parse", "--short=12", "HEAD"]).unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=SPC_UTILS_GIT_COMMIT={}", commit);
+    println!(
+        "cargo:rustc-env=SPC_UTILS_BUILD_DATE={}",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+    println!(
+        "cargo:rustc-env=SPC_UTILS_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+
+    bundle_listings();
+
+    // Rebuild the metadata when the checked-out commit changes.
+    println!("cargo:rerun-if-changed=build.rs");
+    if let Some(head) = git(&["rev-parse", "--git-path", "HEAD"]) {
+        println!("cargo:rerun-if-changed={}", head);
+    }
+    if let Some(reference) = git(&["symbolic-ref", "-q", "HEAD"])
+        && let Some(path) = git(&["rev-parse", "--git-path", &reference])
+    {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+}
+
+/// Gzips `bundled/<category>.json` and `bundled/snapshot-date` into one JSON
+/// document, `{"date": ..., "listings": {<category>: [...]}}`. Without a
+/// snapshot the document has no listings and the fallback is absent, which
+/// is warned about so that a release isn't cut without one.
+fn bundle_listings() {
+    let dir = Path::new("bundled");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let date = fs::read_to_string(dir.join("snapshot-date")).unwrap_or_default();
+    let listings: Vec<String> = BUNDLED_CATEGORIES
+        .iter()
+        .filter_map(|category| {
+            let listing = fs::read_to_string(dir.join(format!("{}.json", category))).ok()?;
+            Some(format!("\"{}\":{}", category, listing.trim()))
+        })
+        .collect();
+    if listings.is_empty() || date.trim().is_empty() {
+        println!(
+            "cargo:warning=no listing snapshot in bundled/, so offline first runs have nothing to \
+             fall back to; run bundled/refresh.sh"
+        );
+    }
+    let document = format!(
+        "{{\"date\":\"{}\",\"listings\":{{{}}}}}",
+        date.trim(),
+        listings.join(",")
+    );
+
+    let out = Path::new(&std::env::var("OUT_DIR").expect("cargo sets OUT_DIR"))
+        .join("bundled-listings.json.gz");
+    let mut encoder = flate2::write::GzEncoder::new(
+        fs::File::create(&out).expect("OUT_DIR is writable"),
+        flate2::Compression::best(),
+    );
+    encoder
+        .write_all(document.as_bytes())
+        .and_then(|_| encoder.finish().map(|_| ()))
+        .expect("failed to write the bundled listings");
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|out| !out.is_empty())
+}
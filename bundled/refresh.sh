@@ -0,0 +1,14 @@
+#!/bin/sh
+# Takes a fresh snapshot of the listings compiled into spc-utils. Needs
+# network access; run it from anywhere in the checkout before a release.
+set -eu
+
+cd "$(dirname "$0")/.."
+scratch=$(mktemp -d)
+trap 'rm -rf "$scratch"' EXIT
+
+for category in bulk common minimal win-min win-max frankenphp; do
+    cargo run --quiet -- --cache-dir "$scratch" list -C "$category" --no-cache > /dev/null
+    cp "$scratch/$category.json" bundled/
+done
+date -u +%Y-%m-%d > bundled/snapshot-date
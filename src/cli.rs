@@ -1,53 +1,294 @@
-use clap::{Args, Parser, Subcommand, command};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{
+    ArgGroup, Args, Parser, Subcommand,
+    builder::{FalseyValueParser, PossibleValuesParser},
+};
 use semver::Version;
+use std::{path::PathBuf, time::Duration};
 
-use crate::{commands::CacheAction, spc};
+use crate::{
+    commands::{
+        CacheAction, HistoryAction, MicroAction, MirrorAction, OutputFormat, examples,
+        install::InstallProfile,
+    },
+    spc,
+};
 
 #[derive(Parser)]
 #[command(name = "spc-utils")]
+#[command(version)]
 #[command(about = "CLI tool for managing Static PHP CLI versions")]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        env = "SPC_UTILS_BASE_URL",
+        help = "Mirror to use instead of dl.static-php.dev (file:// for a local mirror)"
+    )]
+    pub base_url: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = spc::Source::Dl,
+        help = "Where to resolve and download artifacts from"
+    )]
+    pub source: spc::Source,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "OWNER/NAME",
+        help = "Repository whose releases are used with --source github"
+    )]
+    pub github_repo: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Refetch cached data older than this, e.g. 30m, 2h, 1d"
+    )]
+    pub max_cache_age: Option<Duration>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Give up on the whole command after this long, e.g. 120s, 10m (exit status 124)"
+    )]
+    pub timeout_total: Option<Duration>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        env = "SPC_UTILS_DATA_DIR",
+        help = "Directory for receipts and other installed state"
+    )]
+    pub data_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        env = "SPC_UTILS_CACHE_DIR",
+        help = "Directory for cached listings and artifacts"
+    )]
+    pub cache_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Show timestamps in UTC instead of local time, e.g. to compare with server logs"
+    )]
+    pub utc: bool,
+
+    #[arg(
+        short = 'y',
+        long,
+        visible_alias = "yes",
+        global = true,
+        env = "SPC_UTILS_ASSUME_YES",
+        value_parser = FalseyValueParser::new(),
+        help = "Answer yes to confirmation prompts, for unattended use"
+    )]
+    pub assume_yes: bool,
+
+    #[arg(
+        long,
+        global = true,
+        env = "SPC_UTILS_NO_SELF_CHECK",
+        value_parser = FalseyValueParser::new(),
+        help = "Don't check for a newer spc-utils, even when self_update_check is enabled"
+    )]
+    pub no_self_check: bool,
+
+    #[arg(
+        long,
+        global = true,
+        exclusive = true,
+        help = "Show usage examples for the command instead of running it"
+    )]
+    pub examples: bool,
 }
 
 #[derive(Clone, Subcommand)]
 pub enum Commands {
     #[command(
         about = "Fetch the latest Static PHP CLI version",
-        after_help = "Examples:\n  spc-utils latest\n  spc-utils latest -C bulk\n  spc-utils latest -C common -V 8.4\n  spc-utils latest --no-cache"
+        after_help = examples::after_help("latest")
     )]
     Latest(LatestArgs),
 
     #[command(
         about = "Check if a given version is the latest",
-        after_help = "Examples:\n  spc-utils check-update -V 8.4.10\n  spc-utils check-update -C common -V 8.4.10\n  spc-utils check-update -V 8.4.10 --no-cache"
+        after_help = examples::after_help("check-update")
     )]
     CheckUpdate(CheckUpdateArgs),
 
     #[command(
         about = "Download a Static PHP CLI binary",
-        after_help = "Examples:\n  spc-utils download -o php\n  spc-utils download -C bulk -V 8.4.10 -o php\n  spc-utils download -C common -V 8.4 -O linux -A x86_64 -o ./php-binary\n  spc-utils download --no-cache -o php"
+        after_help = examples::after_help("download")
     )]
-    Download(DownloadArgs),
+    Download(Box<DownloadArgs>),
+
+    #[command(
+        about = "Install an artifact into the managed versions directory",
+        after_help = examples::after_help("install")
+    )]
+    Install(InstallArgs),
+
+    #[command(
+        about = "Record resolved versions, hashes and settings to replay elsewhere",
+        after_help = examples::after_help("export-manifest")
+    )]
+    ExportManifest(ExportManifestArgs),
+
+    #[command(
+        about = "Download one version for every combination of OS, arch and build type",
+        after_help = examples::after_help("fetch-matrix")
+    )]
+    FetchMatrix(FetchMatrixArgs),
+
+    #[command(
+        about = "Print the download URL of the newest matching artifact",
+        after_help = examples::after_help("url")
+    )]
+    Url(UrlArgs),
 
     #[command(
         about = "List versions available for download",
-        after_help = "Examples:\n  spc-utils list\n  spc-utils list -C common\n  spc-utils list -C common -V 8.4\n  spc-utils list -C common -O linux -A x86_64 -B cli\n  spc-utils list --no-cache"
+        after_help = examples::after_help("list")
     )]
     List(ListArgs),
 
     #[command(
         about = "Manage the local response cache",
-        after_help = "Examples:\n  spc-utils cache list\n  spc-utils cache clear\n  spc-utils cache clear -C bulk\n  spc-utils cache path"
+        after_help = examples::after_help("cache")
     )]
     Cache {
         #[command(subcommand)]
         action: CacheAction,
     },
 
-    #[command(about = "Show usage examples for all commands")]
-    Examples,
+    #[command(
+        about = "Report whether pinned PHP versions in projects are outdated",
+        after_help = examples::after_help("outdated")
+    )]
+    Outdated(OutdatedArgs),
+
+    #[command(
+        about = "Mirror artifacts into a local directory",
+        after_help = examples::after_help("sync")
+    )]
+    Sync(SyncArgs),
+
+    #[command(
+        about = "Compare and rank mirrors",
+        after_help = examples::after_help("mirror")
+    )]
+    Mirror {
+        #[command(subcommand)]
+        action: MirrorAction,
+    },
+
+    #[command(
+        about = "Serve cached listings and local artifacts over HTTP",
+        after_help = examples::after_help("serve")
+    )]
+    Serve(ServeArgs),
+
+    #[command(
+        about = "Fetch a raw JSON path under the base URL",
+        after_help = examples::after_help("api")
+    )]
+    Api(ApiArgs),
+
+    #[command(
+        about = "Diagnose the local setup",
+        after_help = examples::after_help("doctor")
+    )]
+    Doctor(DoctorArgs),
+
+    #[command(
+        about = "Reclaim disk space from caches, partial downloads and old installs",
+        after_help = examples::after_help("gc")
+    )]
+    Gc(GcArgs),
+
+    #[command(
+        about = "Decompose an artifact file name into its fields",
+        after_help = examples::after_help("parse")
+    )]
+    Parse(ParseArgs),
+
+    #[command(
+        about = "Pick the category and newest version that satisfy a project's requirements",
+        after_help = examples::after_help("resolve")
+    )]
+    Resolve(ResolveArgs),
+
+    #[command(
+        about = "Show which extensions and libraries each build category ships",
+        after_help = examples::after_help("categories")
+    )]
+    Categories(CategoriesArgs),
+
+    #[command(
+        about = "Compare two versions and their availability",
+        after_help = examples::after_help("compare")
+    )]
+    Compare(CompareArgs),
+
+    #[command(
+        about = "Check downloaded files against their install receipts",
+        after_help = examples::after_help("verify-install")
+    )]
+    VerifyInstall(VerifyInstallArgs),
+
+    #[command(
+        about = "Move the data and cache directories to the locations given by --data-dir/--cache-dir",
+        after_help = examples::after_help("migrate")
+    )]
+    Migrate,
+
+    #[command(
+        about = "Helpers for packaging phars with micro builds",
+        after_help = examples::after_help("micro")
+    )]
+    Micro {
+        #[command(subcommand)]
+        action: MicroAction,
+    },
+
+    #[command(
+        about = "Audit trail of changes seen in upstream listings",
+        after_help = examples::after_help("history")
+    )]
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+
+    #[command(
+        about = "Show version and build details",
+        after_help = examples::after_help("version")
+    )]
+    Version(VersionArgs),
+
+    #[command(
+        about = "Show usage examples for all commands, or one",
+        after_help = examples::after_help("examples")
+    )]
+    Examples(ExamplesArgs),
 }
 
 #[derive(Args, Clone)]
@@ -55,8 +296,13 @@ pub struct DownloadArgs {
     #[arg(short = 'C', long, value_enum)]
     pub category: Option<spc::BuildCategory>,
 
-    #[arg(short = 'V', long, value_parser = validate_version)]
-    pub version: Option<Version>,
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
 
     #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
     pub os: Option<String>,
@@ -68,7 +314,160 @@ pub struct DownloadArgs {
     pub build_type: Option<String>,
 
     #[arg(short = 'o', long, help = "Output file path")]
-    pub output: String,
+    pub output: Option<String>,
+
+    #[arg(
+        long = "target",
+        value_name = "NAME",
+        conflicts_with = "output",
+        help = "Only download this target from .spc-utils.toml (repeatable)"
+    )]
+    pub targets: Vec<String>,
+
+    #[arg(long, help = "Overwrite existing output files without asking")]
+    pub force: bool,
+
+    #[arg(
+        long,
+        help = "Leave an existing output file alone when its checksum or size matches the artifact"
+    )]
+    pub skip_existing: bool,
+
+    #[arg(long, help = "Print each step of version resolution to stderr")]
+    pub explain: bool,
+
+    #[arg(long, help = "Copy from the artifact cache instead of hardlinking")]
+    pub copy: bool,
+
+    #[arg(long, help = "Don't show download progress, even on a terminal")]
+    pub no_progress: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = spc::DEFAULT_DOWNLOAD_RETRIES,
+        help = "Retry an interrupted download up to N times, resuming where possible"
+    )]
+    pub retries: u32,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "1s",
+        value_parser = parse_duration,
+        help = "Wait before the first retry (e.g. 500ms, 2s); doubles with each further retry"
+    )]
+    pub retry_delay: Duration,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the resolved version to a file (.json/.toml also include the URL and SHA-256)"
+    )]
+    pub write_version_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Also fetch .sha256/.sig files published next to the artifact"
+    )]
+    pub with_checksums: bool,
+
+    #[arg(
+        long,
+        help = "Don't check the download against the .sha256 file the server publishes"
+    )]
+    pub no_verify: bool,
+
+    #[arg(
+        long,
+        help = "Refuse the download unless its published minisign signature is valid"
+    )]
+    pub verify_signature: bool,
+
+    #[arg(
+        long,
+        value_name = "KEY",
+        requires = "verify_signature",
+        help = "Minisign public key to trust instead of the configured signing_key"
+    )]
+    pub public_key: Option<String>,
+
+    #[arg(
+        long,
+        help = "Install into a shared prefix, binary included, and keep receipts system-wide"
+    )]
+    pub system: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "system",
+        help = "Prefix for --system installs [default: /usr/local]"
+    )]
+    pub prefix: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "HASH",
+        value_parser = validate_sha256,
+        conflicts_with = "targets",
+        help = "Fail and remove the download unless its SHA-256 is HASH"
+    )]
+    pub expect_sha256: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "MIRROR",
+        value_parser = validate_mirror,
+        help = "Also fetch the artifact from MIRROR (github or a URL) and require identical SHA-256s (repeatable)"
+    )]
+    pub mirror_check: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with = "targets",
+        help = "Unpack the downloaded archive into DIR"
+    )]
+    pub extract: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["targets", "output", "version", "category", "os", "arch", "build_type", "system", "expect_sha256", "mirror_check", "extract"],
+        help = "Download exactly the artifacts recorded by export-manifest, checking their SHA-256s"
+    )]
+    pub from_manifest: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "extract",
+        value_parser = validate_file_name,
+        help = "Move the extracted php, php-fpm or micro.sfx to DIR/NAME"
+    )]
+    pub rename: Option<String>,
+
+    #[arg(
+        long,
+        help = "Allow --system to install a build for another OS or architecture"
+    )]
+    pub target_confirm: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "TOML file with the required `php` version and `extensions`"
+    )]
+    pub requirements: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "EXTENSIONS",
+        value_delimiter = ',',
+        help = "Fail before downloading when the category lacks any of these extensions"
+    )]
+    pub require_ext: Vec<String>,
 
     #[arg(long, help = "Skip cache and fetch fresh data")]
     pub no_cache: bool,
@@ -79,20 +478,261 @@ pub struct CheckUpdateArgs {
     #[arg(short = 'C', long, value_enum)]
     pub category: Option<spc::BuildCategory>,
 
-    #[arg(short = 'V', long, value_parser = validate_version)]
-    pub version: Version,
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Current version: an exact version, or latest, 8 or 8.4 for the newest matching release"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(
+        long = "target",
+        value_name = "NAME",
+        conflicts_with = "version",
+        help = "Only check this target from .spc-utils.toml (repeatable)"
+    )]
+    pub targets: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the resolved version to a file (.json/.toml also include the URL)"
+    )]
+    pub write_version_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "bin",
+        conflicts_with_all = ["targets", "json"],
+        help = "Download, verify and install the update in place of --bin"
+    )]
+    pub install: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "install",
+        help = "Binary replaced by --install; its version is used when -V is omitted"
+    )]
+    pub bin: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "CHANGE",
+        help = "Exit with status 5 when a patch, minor or major update (or anything bigger) is available"
+    )]
+    pub fail_on: Option<spc::VersionChange>,
+
+    #[arg(long, help = "Print the result as JSON")]
+    pub json: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write the result as Prometheus gauges, for node_exporter's textfile collector"
+    )]
+    pub metrics_file: Option<PathBuf>,
 
     #[arg(long, help = "Skip cache and fetch fresh data")]
     pub no_cache: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "no_cache",
+        help = "Answer from the cache, however old, and only ask the server when it shows an update"
+    )]
+    pub confirm_online: bool,
 }
 
 #[derive(Args, Clone)]
+#[command(group(ArgGroup::new("output_fields").args(["url", "name"]).multiple(true)))]
 pub struct LatestArgs {
     #[arg(short = 'C', long, value_enum)]
     pub category: Option<spc::BuildCategory>,
 
-    #[arg(short = 'V', long, value_parser = validate_version)]
-    pub version: Option<Version>,
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
+    pub os: Option<String>,
+
+    #[arg(short = 'A', long, value_parser = spc::SPC_ARCH_OPTIONS)]
+    pub arch: Option<String>,
+
+    #[arg(short = 'B', long, value_parser = validate_build_type)]
+    pub build_type: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the resolved version to a file (.json/.toml also include the URL)"
+    )]
+    pub write_version_file: Option<PathBuf>,
+
+    #[arg(long, help = "Print the resolved artifact's download URL")]
+    pub url: bool,
+
+    #[arg(long, help = "Print the resolved artifact's file name")]
+    pub name: bool,
+
+    #[arg(
+        long,
+        requires = "output_fields",
+        help = "Also print the bare version before --url/--name"
+    )]
+    pub with_version: bool,
+
+    #[arg(
+        short = 'q',
+        long,
+        conflicts_with_all = ["output_fields", "matrix"],
+        help = "Print only the bare version; notes about the listing go to stderr"
+    )]
+    pub quiet: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        help = "stable skips releases younger than stable_channel_days (default 7); fresh takes the newest"
+    )]
+    pub channel: spc::Channel,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        value_parser = parse_as_of,
+        help = "Ignore artifacts published after this day (UTC), e.g. 2024-12-01"
+    )]
+    pub as_of: Option<DateTime<Utc>>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["output_fields", "write_version_file", "os", "arch", "build_type"],
+        help = "Show the newest version per build type and platform in a grid"
+    )]
+    pub matrix: bool,
+
+    #[arg(long, help = "Print each step of version resolution to stderr")]
+    pub explain: bool,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+#[command(group(ArgGroup::new("install_source").args(["from_file", "from_manifest", "profile"])))]
+pub struct InstallArgs {
+    #[arg(
+        long,
+        value_name = "ARCHIVE",
+        help = "Install this artifact archive instead of downloading one"
+    )]
+    pub from_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "expect_sha256",
+        help = "Download and install exactly the artifacts recorded by export-manifest"
+    )]
+    pub from_manifest: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "expect_sha256",
+        help = "Download and install a set of builds of one version together"
+    )]
+    pub profile: Option<InstallProfile>,
+
+    #[arg(
+        long = "target",
+        value_name = "NAME",
+        conflicts_with = "install_source",
+        help = "Only install this target from .spc-utils.toml (repeatable)"
+    )]
+    pub targets: Vec<String>,
+
+    #[arg(short = 'C', long, value_enum, conflicts_with_all = ["from_file", "from_manifest"])]
+    pub category: Option<spc::BuildCategory>,
+
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        conflicts_with_all = ["from_file", "from_manifest"],
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(
+        short = 'O',
+        value_parser = spc::SPC_OS_OPTIONS,
+        conflicts_with_all = ["from_file", "from_manifest"]
+    )]
+    pub os: Option<String>,
+
+    #[arg(
+        short = 'A',
+        long,
+        value_parser = spc::SPC_ARCH_OPTIONS,
+        conflicts_with_all = ["from_file", "from_manifest"]
+    )]
+    pub arch: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "HASH",
+        value_parser = validate_sha256,
+        requires = "from_file",
+        help = "Refuse the archive unless its SHA-256 is HASH"
+    )]
+    pub expect_sha256: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["from_file", "from_manifest"],
+        help = "Skip cache and fetch fresh data"
+    )]
+    pub no_cache: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["from_file", "from_manifest"],
+        help = "Copy from the artifact cache instead of hardlinking"
+    )]
+    pub copy: bool,
+
+    #[arg(
+        long,
+        help = "Replace an existing install of the same artifact without asking"
+    )]
+    pub force: bool,
+
+    #[arg(long, help = "Allow installing a build for another OS or architecture")]
+    pub target_confirm: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct ExportManifestArgs {
+    #[arg(short = 'C', long, value_enum)]
+    pub category: Option<spc::BuildCategory>,
+
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
 
     #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
     pub os: Option<String>,
@@ -103,6 +743,113 @@ pub struct LatestArgs {
     #[arg(short = 'B', long, value_parser = validate_build_type)]
     pub build_type: Option<String>,
 
+    #[arg(
+        long = "target",
+        value_name = "NAME",
+        help = "Only record this target from .spc-utils.toml (repeatable)"
+    )]
+    pub targets: Vec<String>,
+
+    #[arg(
+        short = 'o',
+        long,
+        value_name = "PATH",
+        default_value = "spc-manifest.json",
+        help = "Where to write the manifest"
+    )]
+    pub output: PathBuf,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct FetchMatrixArgs {
+    #[arg(short = 'C', long, value_enum)]
+    pub category: Option<spc::BuildCategory>,
+
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(
+        short = 'O',
+        value_delimiter = ',',
+        value_parser = spc::SPC_OS_OPTIONS,
+        help = "OSes to fetch (comma separated) [default: linux,macos, or windows for win-* categories]"
+    )]
+    pub os: Vec<String>,
+
+    #[arg(
+        short = 'A',
+        long,
+        value_delimiter = ',',
+        value_parser = spc::SPC_ARCH_OPTIONS,
+        default_values = spc::SPC_ARCH_OPTIONS,
+        help = "Architectures to fetch (comma separated)"
+    )]
+    pub arch: Vec<String>,
+
+    #[arg(
+        short = 'B',
+        long,
+        value_delimiter = ',',
+        value_parser = validate_build_type,
+        default_value = "cli",
+        help = "Build types to fetch (comma separated)"
+    )]
+    pub build_type: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Where to lay out <version>/<os>-<arch>/ directories"
+    )]
+    pub dest: PathBuf,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct UrlArgs {
+    #[arg(short = 'C', long, value_enum)]
+    pub category: Option<spc::BuildCategory>,
+
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
+    pub os: Option<String>,
+
+    #[arg(short = 'A', long, value_parser = spc::SPC_ARCH_OPTIONS)]
+    pub arch: Option<String>,
+
+    #[arg(short = 'B', long, value_parser = validate_build_type)]
+    pub build_type: Option<String>,
+
+    #[arg(
+        long = "target",
+        value_name = "NAME",
+        help = "Print the URL of this target from .spc-utils.toml (repeatable)"
+    )]
+    pub targets: Vec<String>,
+
+    #[arg(long, help = "Print the version, file name and URL as JSON")]
+    pub json: bool,
+
+    #[arg(long, help = "Print each step of version resolution to stderr")]
+    pub explain: bool,
+
     #[arg(long, help = "Skip cache and fetch fresh data")]
     pub no_cache: bool,
 }
@@ -112,9 +859,377 @@ pub struct ListArgs {
     #[arg(short = 'C', long, value_enum)]
     pub category: Option<spc::BuildCategory>,
 
-    #[arg(short = 'V', long, value_parser = validate_version)]
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
+    pub os: Option<String>,
+
+    #[arg(short = 'A', long, value_parser = spc::SPC_ARCH_OPTIONS)]
+    pub arch: Option<String>,
+
+    #[arg(short = 'B', long, value_parser = validate_build_type)]
+    pub build_type: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Only list tarballs or zips; -O/-A then only apply when given"
+    )]
+    pub artifact_type: Option<spc::ArtifactType>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        help = "Output format; csv, markdown and json list every matching artifact"
+    )]
+    pub format: OutputFormat,
+
+    #[arg(long, conflicts_with = "format", help = "Shorthand for --format json")]
+    pub json: bool,
+
+    #[arg(
+        long,
+        help = "Only show artifacts that appeared since the previous `list --new`"
+    )]
+    pub new: bool,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        value_parser = parse_as_of,
+        help = "Ignore artifacts published after this day (UTC), e.g. 2024-12-01"
+    )]
+    pub as_of: Option<DateTime<Utc>>,
+
+    #[arg(
+        short = '0',
+        long,
+        conflicts_with_all = ["format", "json"],
+        help = "Terminate entries with NUL instead of newline, for xargs -0"
+    )]
+    pub print0: bool,
+
+    #[arg(
+        long,
+        help = "Sort oldest version first, then by build type, OS and arch (default for json)"
+    )]
+    pub stable_order: bool,
+
+    #[arg(long, help = "Never pipe long output through $PAGER")]
+    pub no_pager: bool,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct OutdatedArgs {
+    #[arg(short = 'C', long, value_enum)]
+    pub category: Option<spc::BuildCategory>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Scan every project below this directory instead of only the current one"
+    )]
+    pub workspace: Option<PathBuf>,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct SyncArgs {
+    #[arg(
+        short = 'C',
+        long,
+        value_enum,
+        help = "Category to mirror (repeatable, defaults to the platform default)"
+    )]
+    pub category: Vec<spc::BuildCategory>,
+
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
+    pub os: Option<String>,
+
+    #[arg(short = 'A', long, value_parser = spc::SPC_ARCH_OPTIONS)]
+    pub arch: Option<String>,
+
+    #[arg(short = 'B', long, value_parser = validate_build_type)]
+    pub build_type: Option<String>,
+
+    #[arg(
+        long,
+        help = "Mirror directory laid out like the upstream server, or s3://bucket/prefix and gs://bucket/prefix with the object-storage feature"
+    )]
+    pub dest: String,
+
+    #[arg(
+        long,
+        help = "Also write browsable index.html pages and index.json listings"
+    )]
+    pub emit_index: bool,
+
+    #[arg(
+        long,
+        help = "Also fetch .sha256/.sig files published next to the artifact"
+    )]
+    pub with_checksums: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["emit_index", "with_checksums"],
+        help = "Check the existing mirror against upstream sizes and checksums without downloading"
+    )]
+    pub verify_only: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "verify_only",
+        help = "Remove mirrored artifacts that upstream no longer lists"
+    )]
+    pub delete: bool,
+
+    #[arg(
+        long,
+        requires = "delete",
+        help = "With --delete, only print what would be removed"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long,
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Artifacts to download at once; halved automatically when upstream rate limits"
+    )]
+    pub concurrency: u16,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Minimum milliseconds between starting downloads"
+    )]
+    pub delay_ms: u64,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct ServeArgs {
+    #[arg(long, default_value = "127.0.0.1:8080", help = "Address to listen on")]
+    pub addr: String,
+
+    #[arg(
+        long,
+        help = "Directory of artifacts laid out like the upstream server (e.g. bulk/php-8.4.10-cli-linux-x86_64.tar.gz)"
+    )]
+    pub artifacts_dir: Option<PathBuf>,
+}
+
+#[derive(Args, Clone)]
+pub struct ApiArgs {
+    #[arg(help = "Path under the base URL, e.g. bulk or windows/spc-max")]
+    pub path: String,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct DoctorArgs {
+    #[arg(long, help = "Repair the problems that can be fixed automatically")]
+    pub fix: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct GcArgs {
+    #[arg(long, help = "Show what would be removed without removing anything")]
+    pub dry_run: bool,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        default_value = "2G",
+        help = "Trim the artifact cache to SIZE, least recently used first (e.g. 500M, 2G)"
+    )]
+    pub max_artifact_cache: u64,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Remove installs in the versions directory beyond the N newest versions"
+    )]
+    pub keep_installed: Option<usize>,
+}
+
+#[derive(Args, Clone)]
+pub struct MicroFetchArgs {
+    #[arg(short = 'C', long, value_enum)]
+    pub category: Option<spc::BuildCategory>,
+
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
+    pub os: Option<String>,
+
+    #[arg(short = 'A', long, value_parser = spc::SPC_ARCH_OPTIONS)]
+    pub arch: Option<String>,
+
+    #[arg(
+        short = 'o',
+        long,
+        default_value = "micro.sfx",
+        help = "Where to save micro.sfx"
+    )]
+    pub output: PathBuf,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+#[command(group(ArgGroup::new("requirement_source").args(["requirements", "require_ext"]).required(true).multiple(true)))]
+pub struct ResolveArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "TOML file with the required `php` version and `extensions`"
+    )]
+    pub requirements: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "EXTENSIONS",
+        value_delimiter = ',',
+        help = "Required extensions, in addition to any from --requirements"
+    )]
+    pub require_ext: Vec<String>,
+
+    #[arg(
+        short = 'V',
+        long,
+        value_parser = validate_version_spec,
+        help = "Version: latest, a major (8), a major.minor (8.4), or an exact version [default: the file's php]"
+    )]
+    pub version: Option<spc::VersionSpec>,
+
+    #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
+    pub os: Option<String>,
+
+    #[arg(short = 'A', long, value_parser = spc::SPC_ARCH_OPTIONS)]
+    pub arch: Option<String>,
+
+    #[arg(short = 'B', long, value_parser = validate_build_type)]
+    pub build_type: Option<String>,
+
+    #[arg(long, help = "Print the result as JSON")]
+    pub json: bool,
+
+    #[arg(long, help = "Skip cache and fetch fresh data")]
+    pub no_cache: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct CategoriesArgs {
+    #[arg(value_enum, help = "Show the extensions and libraries of one category")]
+    pub category: Option<spc::BuildCategory>,
+
+    #[arg(
+        long,
+        value_enum,
+        requires = "category",
+        help = "List the extensions only one of the two categories has"
+    )]
+    pub compare: Option<spc::BuildCategory>,
+
+    #[arg(
+        long,
+        value_name = "EXTENSIONS",
+        value_delimiter = ',',
+        conflicts_with = "category",
+        help = "Print the smallest category that includes all of these extensions"
+    )]
+    pub with: Vec<String>,
+
+    #[arg(
+        short = 'O',
+        value_parser = spc::SPC_OS_OPTIONS,
+        requires = "with",
+        help = "Platform for --with [default: this machine's]"
+    )]
+    pub os: Option<String>,
+}
+
+#[derive(Args, Clone)]
+pub struct ParseArgs {
+    #[arg(help = "Artifact file name, e.g. php-8.3.12-fpm-linux-aarch64.tar.gz")]
+    pub name: String,
+
+    #[arg(long, help = "Print the fields as JSON")]
+    pub json: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct ExamplesArgs {
+    #[arg(
+        value_parser = PossibleValuesParser::new(examples::commands()),
+        help = "Only show the examples of this command"
+    )]
+    pub command: Option<String>,
+
+    #[arg(long, help = "Print the examples as JSON")]
+    pub json: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct VersionArgs {
+    #[arg(long, help = "Print the build details as JSON")]
+    pub json: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct VerifyInstallArgs {
+    #[arg(value_parser = validate_version, help = "Only verify this version, e.g. 8.4.12")]
     pub version: Option<Version>,
 
+    #[arg(long, help = "Verify the system-wide receipts of --system installs")]
+    pub system: bool,
+}
+
+#[derive(Args, Clone)]
+pub struct CompareArgs {
+    #[arg(value_parser = validate_version, help = "First version, e.g. 8.3.10")]
+    pub first: Version,
+
+    #[arg(value_parser = validate_version, help = "Second version, e.g. 8.4.1")]
+    pub second: Version,
+
+    #[arg(short = 'C', long, value_enum)]
+    pub category: Option<spc::BuildCategory>,
+
     #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS)]
     pub os: Option<String>,
 
@@ -128,7 +1243,7 @@ pub struct ListArgs {
     pub no_cache: bool,
 }
 
-fn validate_version(input: &str) -> Result<Version, String> {
+pub(crate) fn validate_version(input: &str) -> Result<Version, String> {
     let version = if let Ok(v) = Version::parse(input) {
         v
     } else {
@@ -152,6 +1267,37 @@ fn validate_version(input: &str) -> Result<Version, String> {
     Ok(version)
 }
 
+/// Parses a `-V` value: `latest`, a bare major (`8`), a major.minor (`8.4`),
+/// or a full version.
+pub(crate) fn validate_version_spec(input: &str) -> Result<spc::VersionSpec, String> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("latest") {
+        return Ok(spc::VersionSpec::Latest);
+    }
+
+    let parts: Vec<&str> = input.split('.').collect();
+    let number = |part: &str| {
+        part.parse::<u64>()
+            .map_err(|e| format!("Invalid version '{}': {}", input, e))
+    };
+    let spec = match parts.as_slice() {
+        [major] => spc::VersionSpec::Major(number(major)?),
+        [major, minor] => spc::VersionSpec::Minor(number(major)?, number(minor)?),
+        _ => return validate_version(input).map(spc::VersionSpec::Exact),
+    };
+
+    if let spc::VersionSpec::Major(major) | spc::VersionSpec::Minor(major, _) = spec
+        && major < 8
+    {
+        return Err(format!(
+            "Version {} is not supported. \nSPC only provides PHP 8.0.0 and later.",
+            input
+        ));
+    }
+
+    Ok(spec)
+}
+
 fn validate_build_type(input: &str) -> Result<String, String> {
     if !spc::SPC_PHP_BUILD_TYPE_OPTIONS.contains(&input) {
         return Err(format!("Invalid build type: {}", input));
@@ -168,3 +1314,117 @@ fn validate_build_type(input: &str) -> Result<String, String> {
 
     Ok(input.to_string())
 }
+
+/// The end of the day `input` (UTC): artifacts published later than this
+/// didn't exist yet "as of" that date.
+fn parse_as_of(input: &str) -> Result<DateTime<Utc>, String> {
+    let date = NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}': expected YYYY-MM-DD", input))?;
+    Ok(date
+        .and_hms_opt(23, 59, 59)
+        .expect("23:59:59 is a valid time")
+        .and_utc())
+}
+
+fn validate_sha256(input: &str) -> Result<String, String> {
+    if input.len() != 64 || !input.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Invalid SHA-256 '{}': expected 64 hexadecimal characters",
+            input
+        ));
+    }
+    Ok(input.to_lowercase())
+}
+
+/// A bare file name, so that `--rename` can't move a binary out of the
+/// extraction directory.
+fn validate_file_name(input: &str) -> Result<String, String> {
+    let path = std::path::Path::new(input);
+    match path.file_name() {
+        Some(name) if name == path.as_os_str() => Ok(input.to_string()),
+        _ => Err(format!(
+            "Invalid name '{}': expected a file name without directories",
+            input
+        )),
+    }
+}
+
+/// A second source to compare downloads against: `github` for the releases
+/// of `--github-repo`, or the base URL of a mirror.
+fn validate_mirror(input: &str) -> Result<String, String> {
+    if input == "github"
+        || ["http://", "https://", "file://"]
+            .iter()
+            .any(|s| input.starts_with(s))
+    {
+        return Ok(input.to_string());
+    }
+    Err(format!(
+        "Invalid mirror '{}': use github or a http://, https:// or file:// URL",
+        input
+    ))
+}
+
+/// Parses sizes such as `500M`, `2G`, or `1048576`. A bare number is bytes,
+/// and units are binary: `1K` is 1024 bytes.
+fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let (digits, unit) = input.split_at(
+        input
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(input.len()),
+    );
+
+    let amount: u64 = digits
+        .parse()
+        .map_err(|_| format!("Invalid size '{}': expected e.g. 500M, 2G", input))?;
+    let shift = match unit
+        .to_ascii_uppercase()
+        .trim_end_matches("IB")
+        .trim_end_matches('B')
+    {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(format!("Invalid size unit '{}': use K, M, G, or T", unit)),
+    };
+
+    amount
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("Size '{}' is too large", input))
+}
+
+/// Parses durations such as `90`, `500ms`, `45s`, `30m`, `2h`, or `1d`. A bare
+/// number is seconds.
+fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let (digits, unit) = input.split_at(
+        input
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(input.len()),
+    );
+
+    let amount: u64 = digits
+        .parse()
+        .map_err(|_| format!("Invalid duration '{}': expected e.g. 30m, 2h, 1d", input))?;
+    let millis = match unit {
+        "ms" => 1,
+        "" | "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit '{}': use ms, s, m, h, or d",
+                unit
+            ));
+        }
+    };
+
+    amount
+        .checked_mul(millis)
+        .map(Duration::from_millis)
+        .ok_or_else(|| format!("Duration '{}' is too long", input))
+}
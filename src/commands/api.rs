@@ -0,0 +1,22 @@
+use crate::{AppContext, cli::ApiArgs, spc::ApiOptions};
+
+pub fn run(ctx: &AppContext, args: ApiArgs) {
+    let options = ApiOptions::new(None, None, None, None, None);
+    let api = ctx.api(options).with_no_cache(args.no_cache);
+
+    match api.fetch_raw(&args.path) {
+        Ok((data, from_cache)) => {
+            if from_cache {
+                eprintln!("(cached)");
+            }
+            match serde_json::to_string_pretty(&data) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Failed to format response: {}", e),
+            }
+        }
+        Err(e) => {
+            eprintln!("Request failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
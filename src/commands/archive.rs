@@ -0,0 +1,275 @@
+use std::{
+    error::Error,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+
+use crate::{
+    interrupt::PartialFile,
+    platform::long_path,
+    spc::{ArtifactName, check_free_space},
+};
+
+pub(crate) use crate::spc::sha256_file;
+
+/// Writes the file called `name` inside a `.tar.gz` or `.zip` artifact to
+/// `dest`, wherever it sits in the archive. It is written to `dest.part`
+/// first, which is removed again if anything goes wrong or on Ctrl-C.
+pub(crate) fn extract_file(archive: &Path, name: &str, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let dest = long_path(dest);
+    let partial = PathBuf::from(format!("{}.part", dest.display()));
+    let _partial = PartialFile::track(&partial);
+    let extracted = extract_file_to(archive, name, &partial)
+        .and_then(|_| std::fs::rename(&partial, &dest).map_err(|e| e.into()));
+    if extracted.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    extracted
+}
+
+fn extract_file_to(archive: &Path, name: &str, partial: &Path) -> Result<(), Box<dyn Error>> {
+    let file = File::open(long_path(archive))?;
+
+    let found = if archive.extension().is_some_and(|ext| ext == "zip") {
+        let mut zip = zip::ZipArchive::new(file)?;
+        let mut found = false;
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index)?;
+            let matches = entry.name()?.rsplit('/').next() == Some(name);
+            if matches {
+                check_free_space(entry.size(), partial)?;
+                io::copy(&mut entry, &mut create(partial)?)?;
+                found = true;
+                break;
+            }
+        }
+        found
+    } else {
+        let mut tar = tar::Archive::new(GzDecoder::new(file));
+        let mut found = false;
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if entry
+                .path()?
+                .file_name()
+                .is_some_and(|file_name| file_name == name)
+            {
+                check_free_space(entry.size(), partial)?;
+                io::copy(&mut entry, &mut create(partial)?)?;
+                found = true;
+                break;
+            }
+        }
+        found
+    };
+
+    if !found {
+        return Err(format!("{} contains no {}", archive.display(), name).into());
+    }
+    Ok(())
+}
+
+/// Unpacks a whole `.tar.gz` or `.zip` artifact into `dir`, keeping its
+/// layout. Entries that would land outside `dir` are skipped. Fails before
+/// writing anything when the unpacked files won't fit. Returns the files
+/// written.
+pub(crate) fn extract_archive(archive: &Path, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    check_free_space(unpacked_size(archive)?, dir)?;
+    std::fs::create_dir_all(long_path(dir)).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let file = File::open(long_path(archive))?;
+    let mut extracted = Vec::new();
+
+    if archive.extension().is_some_and(|ext| ext == "zip") {
+        let mut zip = zip::ZipArchive::new(file)?;
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index)?;
+            let Some(relative) = entry.enclosed_name() else {
+                continue;
+            };
+            let path = dir.join(relative);
+            if entry.is_dir() {
+                std::fs::create_dir_all(long_path(&path))?;
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(long_path(parent))?;
+            }
+            io::copy(&mut entry, &mut create(&long_path(&path))?)?;
+            #[cfg(unix)]
+            if let Some(mode) = entry.unix_mode() {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode & 0o777))?;
+            }
+            extracted.push(path);
+        }
+    } else {
+        let mut tar = tar::Archive::new(GzDecoder::new(file));
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let path = dir.join(entry.path()?);
+            let is_file = entry.header().entry_type().is_file();
+            if entry.unpack_in(dir)? && is_file {
+                extracted.push(path);
+            }
+        }
+    }
+
+    Ok(extracted)
+}
+
+/// Total size of the files in a `.tar.gz` or `.zip` artifact once unpacked.
+/// Zips list it up front; tarballs have to be read through.
+fn unpacked_size(archive: &Path) -> Result<u64, Box<dyn Error>> {
+    let file = File::open(long_path(archive))?;
+    if archive.extension().is_some_and(|ext| ext == "zip") {
+        let mut zip = zip::ZipArchive::new(file)?;
+        let mut size = 0;
+        for index in 0..zip.len() {
+            size += zip.by_index_raw(index)?.size();
+        }
+        return Ok(size);
+    }
+
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    let mut size = 0;
+    for entry in tar.entries()? {
+        size += entry?.size();
+    }
+    Ok(size)
+}
+
+/// Binaries that static-php-cli archives ship, made executable on extraction.
+const EXECUTABLES: [&str; 3] = ["php", "php-fpm", "micro.sfx"];
+
+/// Makes the binaries among `extracted` executable, unless they are built
+/// for another machine, and with `rename` moves the artifact's own binary to
+/// `dir/rename`, so it lands at a known path however the archive is laid out.
+pub(crate) fn normalize_extracted(
+    extracted: &mut [PathBuf],
+    dir: &Path,
+    artifact: Option<&ArtifactName>,
+    rename: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let foreign = artifact.is_some_and(ArtifactName::is_foreign);
+    for path in extracted.iter().filter(|_| !foreign) {
+        let name = path.file_name().and_then(|name| name.to_str());
+        if name.is_some_and(|name| EXECUTABLES.contains(&name)) {
+            make_executable(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+    }
+
+    let Some(rename) = rename else {
+        return Ok(());
+    };
+    let binary = artifact
+        .and_then(ArtifactName::binary_name)
+        .ok_or("can't tell which binary to rename for this artifact")?;
+    let path = extracted
+        .iter_mut()
+        .find(|path| path.file_name().is_some_and(|name| name == binary))
+        .ok_or_else(|| format!("the archive contains no {}", binary))?;
+
+    let renamed = dir.join(rename);
+    std::fs::rename(long_path(path), long_path(&renamed))
+        .map_err(|e| format!("{}: {}", renamed.display(), e))?;
+    println!("Renamed {} to {}", binary, renamed.display());
+    *path = renamed;
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn make_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn make_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Creates `path`, naming it in the error instead of a bare OS message.
+fn create(path: &Path) -> Result<File, String> {
+    File::create(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::*;
+
+    /// A `.tar.gz` holding `php` with `contents`.
+    fn tarball(path: &Path, contents: &[u8]) {
+        let mut tar = tar::Builder::new(GzEncoder::new(
+            File::create(path).unwrap(),
+            Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "build/php", contents).unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn extract_file_finds_the_binary_wherever_it_sits() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("php-8.4.12-cli-linux-x86_64.tar.gz");
+        tarball(&archive, b"binary");
+
+        let dest = dir.path().join("php");
+        extract_file(&archive, "php", &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"binary");
+        assert!(!dir.path().join("php.part").exists());
+
+        let error = extract_file(&archive, "php-fpm", &dir.path().join("php-fpm")).unwrap_err();
+        assert!(error.to_string().contains("contains no php-fpm"));
+    }
+
+    #[test]
+    fn extraction_is_checked_against_the_unpacked_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("php-8.4.12-cli-linux-x86_64.tar.gz");
+        tarball(&archive, &[0; 5000]);
+        assert_eq!(unpacked_size(&archive).unwrap(), 5000);
+
+        // The directory to unpack into doesn't exist yet; its parent counts.
+        let target = dir.path().join("not/yet/there");
+        assert!(check_free_space(5000, &target).is_ok());
+        let error = check_free_space(u64::MAX, &target).unwrap_err();
+        assert!(error.to_string().contains(&format!(
+            "Not enough disk space in {}",
+            dir.path().display()
+        )));
+    }
+
+    #[test]
+    fn extract_file_removes_the_partial_file_of_a_broken_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("php-8.4.12-cli-linux-x86_64.tar.gz");
+        // Incompressible, so that cutting the archive short cuts into `php`.
+        let contents: Vec<u8> = (0..200_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        tarball(&archive, &contents);
+        let len = fs::metadata(&archive).unwrap().len();
+        File::options()
+            .write(true)
+            .open(&archive)
+            .unwrap()
+            .set_len(len / 2)
+            .unwrap();
+
+        let dest = dir.path().join("php");
+        assert!(extract_file(&archive, "php", &dest).is_err());
+        assert!(!dest.exists());
+        assert!(!dir.path().join("php.part").exists());
+    }
+}
@@ -2,17 +2,35 @@ use chrono::{DateTime, Local};
 use clap::Subcommand;
 use comfy_table::{Cell, ContentArrangement, Table, presets::UTF8_FULL};
 
-use crate::{AppContext, spc::BuildCategory};
+use crate::{
+    AppContext,
+    spc::{ApiOptions, BuildCategory, CacheFileInfo, HistoryEvent, summarize_changes},
+};
 
 #[derive(Clone, Subcommand)]
 pub enum CacheAction {
     #[command(about = "List all cached files with details")]
-    List,
+    List {
+        #[arg(long, help = "Print the listing as JSON for scripts")]
+        json: bool,
+    },
     #[command(about = "Clear cached files")]
     Clear {
         #[arg(short = 'C', long, value_enum, help = "Clear only a specific category")]
         category: Option<BuildCategory>,
     },
+    #[command(about = "Fetch listings again and report what changed")]
+    Refresh {
+        #[arg(
+            short = 'C',
+            long,
+            value_enum,
+            help = "Category to refresh (repeatable, defaults to every cached one)"
+        )]
+        category: Vec<BuildCategory>,
+        #[arg(long, help = "Print the changes as JSON for scripts")]
+        json: bool,
+    },
     #[command(about = "Print the cache directory path")]
     Path,
 }
@@ -21,9 +39,14 @@ pub fn run(ctx: &AppContext, action: CacheAction) {
     let cache = &ctx.cache;
 
     match action {
-        CacheAction::List => {
+        CacheAction::List { json } => {
             let files = cache.list_cached_files();
 
+            if json {
+                print_json(&files);
+                return;
+            }
+
             if files.is_empty() {
                 println!("No cached files found.");
                 println!("Cache directory: {}", cache.cache_dir().display());
@@ -45,9 +68,9 @@ pub fn run(ctx: &AppContext, action: CacheAction) {
             for file in &files {
                 table.add_row(vec![
                     Cell::new(file.category.to_string()),
-                    Cell::new(file.entry_count.to_string()),
+                    Cell::new(ctx.human.count(file.entry_count as u64)),
                     Cell::new(format_size(file.size)),
-                    Cell::new(file.modified.format("%Y-%m-%d %H:%M").to_string()),
+                    Cell::new(ctx.human.time(&file.modified, "%Y-%m-%d %H:%M")),
                     Cell::new(format_expires(&file.expires)),
                 ]);
             }
@@ -65,13 +88,98 @@ pub fn run(ctx: &AppContext, action: CacheAction) {
             }
             Err(e) => eprintln!("Failed to clear cache: {}", e),
         },
+        CacheAction::Refresh { category, json } => refresh(ctx, category, json),
         CacheAction::Path => {
             println!("{}", cache.cache_dir().display());
         }
     }
 }
 
-fn format_size(bytes: u64) -> String {
+fn refresh(ctx: &AppContext, categories: Vec<BuildCategory>, json: bool) {
+    let categories = if categories.is_empty() {
+        let cached: Vec<BuildCategory> = ctx
+            .cache
+            .list_cached_files()
+            .into_iter()
+            .map(|file| file.category)
+            .collect();
+        if cached.is_empty() {
+            vec![BuildCategory::default_for_os()]
+        } else {
+            cached
+        }
+    } else {
+        categories
+    };
+
+    let mut refreshed: Vec<(BuildCategory, usize, bool, Vec<HistoryEvent>)> = Vec::new();
+    let mut failed = false;
+    for category in categories {
+        let had_listing = ctx.cache.read(&category).is_some();
+        let api = ctx.api(ApiOptions::new(
+            Some(category.clone()),
+            None,
+            None,
+            None,
+            None,
+        ));
+        match api.refresh_listing() {
+            Ok((entries, changes)) => refreshed.push((category, entries, had_listing, changes)),
+            Err(e) => {
+                eprintln!("Failed to refresh {}: {}", category, e);
+                failed = true;
+            }
+        }
+    }
+
+    if json {
+        let entries: Vec<_> = refreshed
+            .iter()
+            .map(|(category, entries, had_listing, changes)| {
+                serde_json::json!({
+                    "category": category.to_string(),
+                    "entry_count": entries,
+                    "first_fetch": !had_listing,
+                    "changes": changes,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(entries));
+    } else {
+        for (category, entries, had_listing, changes) in &refreshed {
+            let summary = match (had_listing, changes.is_empty()) {
+                (false, _) => "first fetch".to_string(),
+                (true, true) => "no changes".to_string(),
+                (true, false) => summarize_changes(changes),
+            };
+            println!("{}: {} entries, {}", category, entries, summary);
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn print_json(files: &[CacheFileInfo]) {
+    let entries: Vec<_> = files
+        .iter()
+        .map(|file| {
+            serde_json::json!({
+                "category": file.category.to_string(),
+                "path": file.path,
+                "size": file.size,
+                "entry_count": file.entry_count,
+                "fetched_at": file.modified.to_rfc3339(),
+                "expires_at": file.expires.to_rfc3339(),
+                "expired": file.expires <= Local::now(),
+            })
+        })
+        .collect();
+    println!("{}", serde_json::Value::Array(entries));
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
 
@@ -0,0 +1,77 @@
+use std::env::consts::OS;
+
+use comfy_table::{Cell, ContentArrangement, Table, presets::UTF8_FULL};
+
+use crate::{cli::CategoriesArgs, requirements::Requirements, spc::CategoryProfile};
+
+pub fn run(args: CategoriesArgs) {
+    if !args.with.is_empty() {
+        let os = args.os.clone().unwrap_or_else(|| OS.to_string());
+        let requirements = Requirements {
+            extensions: args.with.clone(),
+            ..Default::default()
+        };
+        match requirements.smallest_category(&os) {
+            Ok(profile) => println!("{}", profile.category),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let Some(category) = &args.category else {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec![
+                Cell::new("Category"),
+                Cell::new("Platforms"),
+                Cell::new("Extensions"),
+                Cell::new("Description"),
+            ]);
+        for profile in CategoryProfile::all() {
+            table.add_row(vec![
+                Cell::new(profile.category.to_string()),
+                Cell::new(profile.platforms.join(", ")),
+                Cell::new(profile.extensions().len().to_string()),
+                Cell::new(profile.notes),
+            ]);
+        }
+        println!("{table}");
+        return;
+    };
+
+    let profile = CategoryProfile::of(category);
+
+    if let Some(other) = &args.compare {
+        let other = CategoryProfile::of(other);
+        for (a, b) in [(profile, other), (other, profile)] {
+            let only: Vec<_> = a.missing_from(b).into_iter().collect();
+            println!(
+                "Only in {}: {}",
+                a.category,
+                if only.is_empty() {
+                    "-".to_string()
+                } else {
+                    only.join(", ")
+                }
+            );
+        }
+        return;
+    }
+
+    let join = |set: std::collections::BTreeSet<&str>| {
+        if set.is_empty() {
+            "-".to_string()
+        } else {
+            set.into_iter().collect::<Vec<_>>().join(", ")
+        }
+    };
+    println!("{}: {}", profile.category, profile.notes);
+    println!("Platforms:  {}", profile.platforms.join(", "));
+    println!("Extensions: {}", join(profile.extensions()));
+    println!("Libraries:  {}", join(profile.libraries()));
+}
@@ -1,31 +1,497 @@
+use std::{error::Error, fmt::Write as _, fs, io::IsTerminal, path::Path, process::Command};
+
+use chrono::Local;
+use semver::Version;
+
 use crate::{
     AppContext,
     cli::CheckUpdateArgs,
-    spc::{Api, ApiOptions},
+    commands::{
+        archive::{extract_file, make_executable},
+        notices::{warn_eol, warn_republished},
+        prompt::{confirm, is_interactive},
+        selection::resolve_newest,
+        version_file::{VersionRecord, write_version_file},
+    },
+    config::{PROJECT_CONFIG_FILE, Target},
+    spc::{
+        ApiOptions, ArtifactName, Freshness, VersionChange, VersionSpec, eol_date, is_supported,
+        parse_sha256,
+    },
 };
 
+/// Exit status when the available update includes a PHP security release.
+const SECURITY_UPDATE_EXIT_CODE: i32 = 4;
+
+/// Exit status when an update at least as significant as `--fail-on` is
+/// available.
+const UPDATE_EXIT_CODE: i32 = 5;
+
+/// The outcome of checking one version.
+struct Checked {
+    /// The target's name, when checking targets from the project config.
+    name: Option<String>,
+    category: &'static str,
+    current: Version,
+    latest: Version,
+    url: String,
+    /// Whether the update includes a security release.
+    security: bool,
+    /// The most significant update available, to the newest release.
+    change: Option<VersionChange>,
+    freshness: Freshness,
+}
+
+impl Checked {
+    fn fails(&self, fail_on: Option<VersionChange>) -> bool {
+        fail_on.is_some_and(|fail_on| self.change.is_some_and(|change| change >= fail_on))
+    }
+}
+
 pub fn run(ctx: &AppContext, args: CheckUpdateArgs) {
-    let options = ApiOptions::new(
-        args.category.clone(),
-        Some(args.version.clone()),
-        None,
-        None,
-        None,
-    );
-    let api = Api::new(ctx.cache.clone(), options).with_no_cache(args.no_cache);
-    let (latest_version, from_cache) = api.fetch_latest_version();
-
-    let cached_marker = if from_cache { " (cached)" } else { "" };
-    if args.version == latest_version {
-        println!(
-            "You have the latest version: {}{}",
-            args.version, cached_marker
+    let version = match args.version.clone() {
+        Some(VersionSpec::Exact(version)) => Some(version),
+        Some(spec) => Some(newest_matching(ctx, &args, spec)),
+        None => None,
+    };
+    let version = version.or_else(|| {
+        let bin = args.bin.as_deref()?;
+        let version = installed_version(bin);
+        if version.is_none() {
+            eprintln!(
+                "Cannot tell which PHP version {} is; pass -V",
+                bin.display()
+            );
+            std::process::exit(2);
+        }
+        version
+    });
+
+    if let Some(version) = &version {
+        let target = Target {
+            category: args.category.clone(),
+            ..Target::default()
+        };
+        let checked = check(ctx, &args, None, &target, version);
+        if let Some(path) = &args.metrics_file {
+            write_metrics(path, std::slice::from_ref(&checked));
+        }
+
+        if let Some(path) = &args.write_version_file {
+            write_version_file(
+                path,
+                &VersionRecord::resolved(&checked.latest, checked.url.clone()),
+            );
+        }
+
+        if let Some(bin) = args.bin.as_deref().filter(|_| args.install) {
+            if checked.latest <= *version {
+                println!("{} is up to date", bin.display());
+            } else if is_interactive()
+                && !confirm(
+                    ctx,
+                    &format!("Replace {} with PHP {}?", bin.display(), checked.latest),
+                )
+            {
+                eprintln!("Not replacing {}", bin.display());
+            } else if let Err(e) = install(ctx, &args, &target, &checked.latest, bin) {
+                eprintln!("Failed to install PHP {}: {}", checked.latest, e);
+                std::process::exit(1);
+            } else {
+                // The update is installed now, nothing left to flag.
+                return;
+            }
+        }
+
+        if checked.security {
+            std::process::exit(SECURITY_UPDATE_EXIT_CODE);
+        }
+        if checked.fails(args.fail_on) {
+            std::process::exit(UPDATE_EXIT_CODE);
+        }
+        return;
+    }
+
+    if ctx.config.targets.is_empty() {
+        eprintln!(
+            "error: --version is required unless targets are defined in {}",
+            PROJECT_CONFIG_FILE
         );
+        std::process::exit(2);
+    }
+
+    let targets = ctx
+        .config
+        .select_targets(&args.targets)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+
+    let (mut security, mut fails) = (false, false);
+    let mut checks = Vec::new();
+    for (name, target) in targets {
+        let Some(version) = target.version() else {
+            eprintln!("[{}] Skipped: no version pinned", name);
+            continue;
+        };
+
+        let target = Target {
+            category: args.category.clone().or_else(|| target.category.clone()),
+            ..target.clone()
+        };
+        let checked = check(ctx, &args, Some(name), &target, &version);
+        security |= checked.security;
+        fails |= checked.fails(args.fail_on);
+        checks.push(checked);
+    }
+
+    if let Some(path) = &args.metrics_file {
+        write_metrics(path, &checks);
+    }
+
+    if security {
+        std::process::exit(SECURITY_UPDATE_EXIT_CODE);
+    }
+    if fails {
+        std::process::exit(UPDATE_EXIT_CODE);
+    }
+}
+
+/// The newest release matching a partial `-V` such as `8.4`, `8` or `latest`,
+/// which then stands for the current version.
+fn newest_matching(ctx: &AppContext, args: &CheckUpdateArgs, spec: VersionSpec) -> Version {
+    let options = ApiOptions::new(args.category.clone(), Some(spec), None, None, None);
+    resolve_newest(&ctx.api(options).with_no_cache(args.no_cache)).version
+}
+
+/// Reports whether `version` is current and what updates are available.
+fn check(
+    ctx: &AppContext,
+    args: &CheckUpdateArgs,
+    name: Option<&str>,
+    target: &Target,
+    version: &Version,
+) -> Checked {
+    let resolve = |no_cache: bool| {
+        let api = |spec| {
+            ctx.api(ApiOptions::new(
+                target.category.clone(),
+                spec,
+                target.os.clone(),
+                target.arch.clone(),
+                target.build_type.clone(),
+            ))
+            .with_no_cache(no_cache)
+            .with_any_cache_age(args.confirm_online && !no_cache)
+        };
+        let latest = api(Some(version.clone().into()));
+        let resolution = resolve_newest(&latest);
+        let (latest_version, freshness) = (resolution.version, resolution.freshness);
+        // The newest release of any minor, for users on an older one.
+        let newest = resolve_newest(&api(None))
+            .version
+            .max(latest_version.clone());
+        (latest, latest_version, freshness, newest)
+    };
+
+    let (mut api, mut latest_version, mut freshness, mut newest) = resolve(args.no_cache);
+    // With --confirm-online the cache answers first; only an update it shows
+    // is worth asking the server about before reporting it.
+    if args.confirm_online && newest > *version {
+        (api, latest_version, freshness, newest) = resolve(true);
+    }
+    let available = (newest > *version)
+        .then(|| VersionChange::between(version, &newest))
+        .flatten();
+
+    let download_url = api.download_url(&latest_version);
+    let republished =
+        api.report_republications(|name| download_url.ends_with(&format!("/{}", name)));
+    warn_republished(&ctx.human, &republished);
+
+    let change = if latest_version > *version {
+        VersionChange::between(version, &latest_version)
     } else {
-        println!(
-            "Update available: {} -> {}{}",
-            args.version, latest_version, cached_marker
-        );
-        println!("  {}", api.download_url(&latest_version));
+        None
+    };
+
+    // Unknown when php.net's release feed can't be read.
+    let security = change.and_then(|_| {
+        api.fetch_security_releases(version.major)
+            .ok()
+            .map(|releases| {
+                releases
+                    .iter()
+                    .any(|release| release > version && *release <= latest_version)
+            })
+    });
+
+    if args.json {
+        let mut report = serde_json::json!({
+            "current": version.to_string(),
+            "latest": latest_version.to_string(),
+            "update_available": change.is_some(),
+            "update": change,
+            "url": download_url,
+            "newest": newest.to_string(),
+            "eol_date": eol_date(version).map(|date| date.to_string()),
+            "supported": is_supported(version, Local::now().date_naive()),
+            "security": security,
+        });
+        if let Some(name) = name {
+            report["target"] = name.into();
+        }
+        println!("{}", report);
+        return Checked {
+            name: name.map(str::to_string),
+            category: api.category().url_path(),
+            current: version.clone(),
+            latest: latest_version,
+            url: download_url,
+            security: security == Some(true),
+            change: available,
+            freshness,
+        };
+    }
+
+    if let Some(name) = name {
+        print!("[{}] ", name);
+    }
+
+    let cached_marker = freshness.annotation();
+    // Set when a newer minor exists beyond the current minor's newest patch.
+    let newest_note = (newest > latest_version).then(|| {
+        let change = VersionChange::between(&latest_version, &newest)
+            .expect("newest is greater than the latest patch");
+        format!("{} ({} update)", newest, change)
+    });
+
+    match change {
+        None => match &newest_note {
+            None => println!("You have the latest version: {}{}", version, cached_marker),
+            Some(note) => println!(
+                "You have the latest {}.{} patch: {}; newest is {}{}",
+                version.major, version.minor, version, note, cached_marker
+            ),
+        },
+        Some(change) => {
+            let kind = if security == Some(true) {
+                format!("{} update, security", change)
+            } else {
+                format!("{} update", change)
+            };
+            let latest_note = if newest > latest_version {
+                format!(
+                    ", {} (latest)",
+                    highlight(&newest, VersionChange::between(version, &newest).unwrap())
+                )
+            } else {
+                String::new()
+            };
+            println!(
+                "Update available: {} -> {} ({}){}{}",
+                version,
+                highlight(&latest_version, change),
+                kind,
+                latest_note,
+                cached_marker
+            );
+            println!("  {}", download_url);
+        }
+    }
+
+    warn_eol(version);
+
+    Checked {
+        name: name.map(str::to_string),
+        category: api.category().url_path(),
+        current: version.clone(),
+        latest: latest_version,
+        url: download_url,
+        security: security == Some(true),
+        change: available,
+        freshness,
     }
 }
+
+/// Writes the checks to `path` as gauges in the Prometheus textfile format,
+/// for node_exporter's textfile collector. The file is replaced with a
+/// rename so the collector never scrapes a half-written one.
+fn write_metrics(path: &Path, checks: &[Checked]) {
+    let now = Local::now();
+    let labels = |checked: &Checked| {
+        let mut labels = format!("category=\"{}\"", checked.category);
+        if let Some(name) = &checked.name {
+            write!(labels, ",target=\"{}\"", escape_label(name)).unwrap();
+        }
+        labels
+    };
+
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: Vec<(String, String)>| {
+        writeln!(out, "# HELP spc_utils_{} {}", name, help).unwrap();
+        writeln!(out, "# TYPE spc_utils_{} gauge", name).unwrap();
+        for (labels, value) in samples {
+            writeln!(out, "spc_utils_{}{{{}}} {}", name, labels, value).unwrap();
+        }
+    };
+    gauge(
+        "update_available",
+        "Whether a newer PHP release than the current version is available.",
+        checks
+            .iter()
+            .map(|c| {
+                let labels = format!("{},current=\"{}\"", labels(c), c.current);
+                (labels, u8::from(c.change.is_some()).to_string())
+            })
+            .collect(),
+    );
+    gauge(
+        "latest_version_info",
+        "The newest patch release of the current minor, as a label.",
+        checks
+            .iter()
+            .map(|c| {
+                (
+                    format!("{},version=\"{}\"", labels(c), c.latest),
+                    "1".into(),
+                )
+            })
+            .collect(),
+    );
+    gauge(
+        "cache_age_seconds",
+        "Age of the listing the check was answered from; 0 when fetched just now.",
+        checks
+            .iter()
+            .map(|c| {
+                let age = match c.freshness {
+                    Freshness::Fresh => 0,
+                    Freshness::Cached(fetched, _)
+                    | Freshness::Stale(fetched)
+                    | Freshness::Bundled(fetched) => (now - fetched).num_seconds().max(0),
+                };
+                (labels(c), age.to_string())
+            })
+            .collect(),
+    );
+    gauge(
+        "last_check_timestamp_seconds",
+        "Unix time of the last check.",
+        checks
+            .iter()
+            .map(|c| (labels(c), now.timestamp().to_string()))
+            .collect(),
+    );
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    if let Err(e) = fs::write(&tmp, out).and_then(|_| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        eprintln!("Failed to write metrics to {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The version of the PHP binary at `bin`, as it reports it.
+fn installed_version(bin: &Path) -> Option<Version> {
+    let output = Command::new(bin)
+        .args(["-r", "echo PHP_VERSION;"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Version::parse(String::from_utf8_lossy(&output.stdout).trim()).ok()
+}
+
+/// Replaces the binary at `bin` with the one from `version`'s archive, after
+/// checking the archive against its published SHA-256 when there is one.
+/// The swap is a rename, so `bin` is never left half-written.
+fn install(
+    ctx: &AppContext,
+    args: &CheckUpdateArgs,
+    target: &Target,
+    version: &Version,
+    bin: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let options = ApiOptions::new(
+        target.category.clone(),
+        Some(version.clone().into()),
+        target.os.clone(),
+        target.arch.clone(),
+        target.build_type.clone(),
+    );
+    // The published checksum is checked below, where a missing one is
+    // worth a warning before replacing a binary.
+    let api = ctx
+        .api(options)
+        .with_no_cache(args.no_cache)
+        .with_verify(false);
+    let name = api.artifact_name(version);
+    let binary = ArtifactName::parse(&name)
+        .and_then(|artifact| artifact.binary_name())
+        .ok_or_else(|| format!("can't tell which binary {} contains", name))?;
+
+    let dir = match bin.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let archive = dir.join(&name);
+    let installed = api.download(&archive.to_string_lossy()).and_then(|digest| {
+        match api
+            .fetch_companion(&name, ".sha256")
+            .as_deref()
+            .and_then(parse_sha256)
+        {
+            Some(expected) if digest != expected => {
+                return Err(format!("{} does not match its published SHA-256", name).into());
+            }
+            Some(_) => println!("Verified {} against its published SHA-256", name),
+            None => eprintln!("warning: no SHA-256 published for {}", name),
+        }
+        extract_file(&archive, binary, bin)?;
+        Ok(make_executable(bin)?)
+    });
+    let _ = fs::remove_file(&archive);
+    installed?;
+
+    ctx.receipts
+        .record(version, &api.download_url(version), &[bin.to_path_buf()])?;
+    println!("Installed PHP {} to {}", version, bin.display());
+    Ok(())
+}
+
+/// Colours the components of `version` from the one that changed onwards:
+/// red for major, yellow for minor, green for patch updates. Plain text when
+/// stdout isn't a terminal or `NO_COLOR` is set.
+fn highlight(version: &Version, change: VersionChange) -> String {
+    if !std::io::stdout().is_terminal() || std::env::var_os("NO_COLOR").is_some() {
+        return version.to_string();
+    }
+
+    let (unchanged, changed, color) = match change {
+        VersionChange::Major => (String::new(), version.to_string(), 31),
+        VersionChange::Minor => (
+            format!("{}.", version.major),
+            format!("{}.{}", version.minor, version.patch),
+            33,
+        ),
+        VersionChange::Patch => (
+            format!("{}.{}.", version.major, version.minor),
+            version.patch.to_string(),
+            32,
+        ),
+    };
+
+    format!("{}\x1b[1;{}m{}\x1b[0m", unchanged, color, changed)
+}
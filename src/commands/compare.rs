@@ -0,0 +1,90 @@
+use std::cmp::Ordering;
+
+use semver::Version;
+
+use crate::{
+    AppContext,
+    cli::CompareArgs,
+    commands::cache::format_size,
+    spc::{ApiOptions, VersionChange},
+};
+
+pub fn run(ctx: &AppContext, args: CompareArgs) {
+    let (a, b) = (&args.first, &args.second);
+
+    let relation = match a.cmp(b) {
+        Ordering::Less => "older than",
+        Ordering::Equal => "the same as",
+        Ordering::Greater => "newer than",
+    };
+    match VersionChange::between(a, b) {
+        Some(change) => println!("{} is {} {} ({} difference)", a, relation, b, change),
+        None => println!("{} is {} {}", a, relation, b),
+    }
+    for (component, x, y) in [
+        ("major", a.major, b.major),
+        ("minor", a.minor, b.minor),
+        ("patch", a.patch, b.patch),
+    ] {
+        if x == y {
+            println!("  {}: {} (same)", component, x);
+        } else {
+            println!("  {}: {} -> {}", component, x, y);
+        }
+    }
+
+    let options = ApiOptions::new(
+        args.category.clone(),
+        None,
+        args.os.clone(),
+        args.arch.clone(),
+        args.build_type.clone(),
+    );
+    let category = options.category();
+    let api = ctx.api(options).with_no_cache(args.no_cache);
+
+    let (data, _) = match api.fetch_versions() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Failed to fetch versions: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("\nAvailability ({}):", category);
+    for version in [a, b] {
+        let name = api.artifact_name(version);
+        match data.iter().find(|resp| resp.name == name) {
+            Some(resp) => {
+                let size = resp
+                    .size()
+                    .parse()
+                    .map(format_size)
+                    .unwrap_or_else(|_| resp.size().to_string());
+                println!("  {:<10} {}  {}", version.to_string(), name, size);
+            }
+            None => println!("  {:<10} not available", version.to_string()),
+        }
+    }
+
+    // Every release after the older version up to and including the newer.
+    let (older, newer) = if a <= b { (a, b) } else { (b, a) };
+    let mut releases: Vec<Version> = data
+        .iter()
+        .filter_map(|resp| resp.version())
+        .chain([newer.clone()])
+        .filter(|version| version > older && version <= newer)
+        .collect();
+    releases.sort();
+    releases.dedup();
+
+    if !releases.is_empty() {
+        println!("\nChangelogs:");
+        for version in releases {
+            println!(
+                "  https://www.php.net/ChangeLog-{}.php#{}",
+                version.major, version
+            );
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::fs;
+
+use crate::{AppContext, cli::DoctorArgs, spc::ApiOptions};
+
+pub fn run(ctx: &AppContext, args: DoctorArgs) {
+    let cache = &ctx.cache;
+    let mut problems = 0;
+
+    let mut report = |ok: bool, message: String, fix: Option<&dyn Fn() -> Result<(), String>>| {
+        if ok {
+            println!("[ok]    {}", message);
+            return;
+        }
+
+        match fix {
+            Some(fix) if args.fix => match fix() {
+                Ok(()) => println!("[fixed] {}", message),
+                Err(e) => {
+                    println!("[fail]  {} (fix failed: {})", message, e);
+                    problems += 1;
+                }
+            },
+            Some(_) => {
+                println!("[fail]  {} (fixable with --fix)", message);
+                problems += 1;
+            }
+            None => {
+                println!("[fail]  {}", message);
+                problems += 1;
+            }
+        }
+    };
+
+    let ensure_dir = || cache.ensure_dir().map_err(|e| e.to_string());
+
+    report(
+        cache.cache_dir().is_dir() && cache.is_writable(),
+        format!(
+            "Cache directory is writable: {}",
+            cache.cache_dir().display()
+        ),
+        Some(&ensure_dir),
+    );
+
+    report(
+        cache.version_marker_matches(),
+        "Cache version marker is current".to_string(),
+        Some(&ensure_dir),
+    );
+
+    let corrupt = cache.corrupt_files();
+    let remove_corrupt = || {
+        corrupt
+            .iter()
+            .try_for_each(fs::remove_file)
+            .map_err(|e| e.to_string())
+    };
+    let message = if corrupt.is_empty() {
+        "No corrupt cache files".to_string()
+    } else {
+        let paths: Vec<String> = corrupt.iter().map(|p| p.display().to_string()).collect();
+        format!("Corrupt cache files: {}", paths.join(", "))
+    };
+    report(corrupt.is_empty(), message, Some(&remove_corrupt));
+
+    let api = ctx.api(ApiOptions::new(None, None, None, None, None));
+    match api.check_connectivity() {
+        Ok(()) => report(true, "Download server is reachable".to_string(), None),
+        Err(e) => report(
+            false,
+            format!("Download server is unreachable: {}", e),
+            None,
+        ),
+    }
+
+    if problems > 0 {
+        std::process::exit(1);
+    }
+}
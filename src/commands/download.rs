@@ -1,19 +1,721 @@
-use crate::{AppContext, cli::DownloadArgs, spc::{Api, ApiOptions}};
+use std::{
+    collections::BTreeMap,
+    env::consts::OS,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use minisign_verify::PublicKey;
+use semver::Version;
+
+use crate::{
+    AppContext,
+    cli::DownloadArgs,
+    commands::{
+        archive::{
+            extract_archive, extract_file, make_executable, normalize_extracted, sha256_file,
+        },
+        fpm,
+        hooks::run_hook,
+        notices::{foreign_install_error, notice_foreign, warn_eol},
+        pinned::{fetch_pinned, pinned_api},
+        prompt::{confirm, is_interactive},
+        selection::resolve_newest,
+        version_file::{VersionRecord, write_target_versions, write_version_file},
+    },
+    config::{PROJECT_CONFIG_FILE, Target},
+    manifest::Manifest,
+    platform::long_path,
+    receipts::Receipts,
+    requirements::Requirements,
+    signature::{self, SIGNATURE_EXTENSIONS},
+    spc::{
+        Api, ApiOptions, ArtifactName, BuildCategory, COMPANION_EXTENSIONS, CategoryProfile,
+        VersionSpec,
+    },
+};
+
+pub fn run(ctx: &AppContext, mut args: DownloadArgs) {
+    if let Some(path) = &args.from_manifest {
+        download_from_manifest(ctx, &args, path);
+        return;
+    }
+
+    let requirements = Requirements::collect(args.requirements.as_deref(), &args.require_ext)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+
+    let single = args.output.is_some() || (args.system && args.targets.is_empty());
+    if single {
+        args.version = args.version.or_else(|| requirements.version_spec());
+
+        // Without -C, the requirements pick the category.
+        if args.category.is_none() && !requirements.extensions.is_empty() {
+            let os = args.os.clone().unwrap_or_else(|| OS.to_string());
+            let profile = requirements.smallest_category(&os).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            println!("Using the {} category", profile.category);
+            args.category = Some(profile.category.clone());
+        }
+    }
+
+    if args.system && args.output.is_none() && args.targets.is_empty() {
+        if let Err(e) = requirements.check(CategoryProfile::of(&category_of(&args.category))) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        let (version, output) = system_output(ctx, &args);
+        download_single(ctx, &args, &requirements, output, Some(version.into()));
+        return;
+    }
+
+    if let Some(output) = &args.output {
+        download_single(
+            ctx,
+            &args,
+            &requirements,
+            PathBuf::from(output),
+            args.version.clone(),
+        );
+        return;
+    }
+
+    if ctx.config.targets.is_empty() {
+        eprintln!(
+            "error: --output is required unless targets are defined in {}",
+            PROJECT_CONFIG_FILE
+        );
+        std::process::exit(2);
+    }
+
+    let targets = ctx
+        .config
+        .select_targets(&args.targets)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+
+    let mut failed = false;
+    let mut records = BTreeMap::new();
+    for (name, target) in targets {
+        println!("[{}]", name);
+
+        if target.output.is_none() {
+            eprintln!("Target '{}' has no output path", name);
+            failed = true;
+            continue;
+        }
+
+        // Command line flags override what the target declares.
+        let target = Target {
+            category: args.category.clone().or_else(|| target.category.clone()),
+            os: args.os.clone().or_else(|| target.os.clone()),
+            arch: args.arch.clone().or_else(|| target.arch.clone()),
+            build_type: args
+                .build_type
+                .clone()
+                .or_else(|| target.build_type.clone()),
+            ..target.clone()
+        };
+        let version = args.version.clone().or_else(|| target.version_spec());
+
+        match download_target(ctx, &args, &requirements, &target, version) {
+            Some(record) => {
+                records.insert(name.clone(), record);
+            }
+            None => failed = true,
+        }
+    }
+
+    if let Some(path) = &args.write_version_file {
+        write_target_versions(path, &records);
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Downloads exactly the artifacts a manifest records, from the recorded URLs,
+/// to their recorded output paths or, without one, their file names.
+fn download_from_manifest(ctx: &AppContext, args: &DownloadArgs, path: &Path) {
+    let manifest = Manifest::load(path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let mut failed = false;
+    for artifact in &manifest.artifacts {
+        println!("[{}]", artifact.target.as_deref().unwrap_or(&artifact.name));
+        let output = artifact
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&artifact.name));
+
+        if args.skip_existing
+            && sha256_file(&long_path(&output))
+                .is_ok_and(|digest| digest.eq_ignore_ascii_case(&artifact.sha256))
+        {
+            println!("{} is up to date, skipping", output.display());
+            continue;
+        }
+
+        if !may_overwrite(ctx, args, &output) {
+            failed = true;
+            continue;
+        }
+
+        let api = pinned_api(ctx, artifact)
+            .with_progress(!args.no_progress)
+            .with_retries(args.retries)
+            .with_retry_delay(args.retry_delay);
+        let fetched = fetch_pinned(&api, artifact, &output).and_then(|_| {
+            ctx.receipts.record(
+                &artifact.version,
+                &artifact.url,
+                std::slice::from_ref(&output),
+            )
+        });
+        if let Err(e) = fetched {
+            eprintln!("Download failed: {}", e);
+            failed = true;
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Downloads to one output path rather than the configured targets.
+fn download_single(
+    ctx: &AppContext,
+    args: &DownloadArgs,
+    requirements: &Requirements,
+    output: PathBuf,
+    version: Option<VersionSpec>,
+) {
+    let target = Target {
+        category: args.category.clone(),
+        version: None,
+        os: args.os.clone(),
+        arch: args.arch.clone(),
+        build_type: args.build_type.clone(),
+        output: Some(output),
+    };
+
+    let Some(record) = download_target(ctx, args, requirements, &target, version) else {
+        std::process::exit(1);
+    };
+
+    if let Some(path) = &args.write_version_file {
+        write_version_file(path, &record);
+    }
+}
+
+/// Where `--system` puts the artifact when no output is given:
+/// `<prefix>/lib/spc-utils/<artifact name>`. Resolves the version first
+/// since the name depends on it.
+fn system_output(ctx: &AppContext, args: &DownloadArgs) -> (Version, PathBuf) {
+    let options = ApiOptions::new(
+        args.category.clone(),
+        args.version.clone(),
+        args.os.clone(),
+        args.arch.clone(),
+        args.build_type.clone(),
+    );
+    let api = ctx
+        .api(options)
+        .with_no_cache(args.no_cache)
+        .with_explain(args.explain);
+
+    let version = match args.version.as_ref().and_then(VersionSpec::exact) {
+        Some(version) => version.clone(),
+        None => resolve_newest(&api).version,
+    };
+
+    let prefix = args.prefix.clone().unwrap_or_else(default_prefix);
+    let output = prefix
+        .join("lib")
+        .join("spc-utils")
+        .join(api.artifact_name(&version));
+    (version, output)
+}
+
+fn category_of(category: &Option<BuildCategory>) -> BuildCategory {
+    category
+        .clone()
+        .unwrap_or_else(BuildCategory::default_for_os)
+}
+
+fn default_prefix() -> PathBuf {
+    if cfg!(windows) {
+        std::env::var_os("ProgramFiles")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\Program Files"))
+            .join("spc-utils")
+    } else {
+        PathBuf::from("/usr/local")
+    }
+}
+
+/// Installs the binary `name` from `archive` into `bin_dir`, replacing the
+/// one there. Returns the installed binary.
+fn install_binary(
+    archive: &Path,
+    name: &str,
+    bin_dir: &Path,
+    executable: bool,
+) -> Result<PathBuf, Box<dyn Error>> {
+    std::fs::create_dir_all(long_path(bin_dir))?;
+    let binary = bin_dir.join(name);
+    extract_file(archive, name, &binary)?;
+    if executable {
+        make_executable(&binary)?;
+    }
+    println!("Installed {}", binary.display());
+    Ok(binary)
+}
+
+/// Downloads the artifact `name` again from each of `mirrors` and compares it
+/// with the `expected` SHA-256 of the one already downloaded. The copies are never
+/// cached, so every source is really asked.
+fn check_mirrors(
+    ctx: &AppContext,
+    options: impl Fn() -> ApiOptions,
+    name: &str,
+    expected: &str,
+    mirrors: &[String],
+) -> Result<(), String> {
+    // Kept out of the output directory; `gc` sweeps what an interrupted run
+    // leaves in the temp directory.
+    let copy = std::env::temp_dir().join(format!(
+        "spc-utils-{}-{}.mirror-check",
+        std::process::id(),
+        name
+    ));
+
+    for mirror in mirrors {
+        let api = Api::new(ctx.cache.clone(), ctx.apply_defaults(options())).with_no_cache(true);
+        let api = match mirror.as_str() {
+            "github" => api.with_github_source(ctx.github_repo.clone()),
+            url => api.with_base_url(url),
+        };
+
+        let fetched = api.download(&copy.to_string_lossy());
+        let _ = std::fs::remove_file(long_path(&copy));
+        match fetched {
+            Ok(digest) if digest == expected => println!("{} matches on {}", name, mirror),
+            Ok(digest) => {
+                return Err(format!(
+                    "{} differs on {}: SHA-256 {} here, {} there. Not keeping it.",
+                    name, mirror, expected, digest
+                ));
+            }
+            Err(e) => return Err(format!("Failed to check {} on {}: {}", name, mirror, e)),
+        }
+    }
+    Ok(())
+}
+
+/// The public key `--verify-signature` trusts: `--public-key`, or else the
+/// project's `signing_key`.
+fn signing_key(ctx: &AppContext, args: &DownloadArgs) -> Result<PublicKey, String> {
+    let key = args
+        .public_key
+        .as_deref()
+        .or(ctx.config.signing_key.as_deref())
+        .ok_or_else(|| {
+            format!(
+                "--verify-signature needs a trusted key: pass --public-key or set signing_key in {}",
+                PROJECT_CONFIG_FILE
+            )
+        })?;
+    signature::parse_public_key(key)
+}
+
+/// Verifies `output` against the first signature published for `name`.
+fn check_signature(
+    api: &Api,
+    name: &str,
+    output: &Path,
+    key: &PublicKey,
+) -> Result<String, Box<dyn Error>> {
+    let published = SIGNATURE_EXTENSIONS
+        .iter()
+        .find_map(|ext| api.fetch_companion(name, ext))
+        .ok_or("no signature is published for it")?;
+    signature::verify_file(output, &String::from_utf8_lossy(&published), key)
+}
+
+/// Whether `output` may be replaced: it doesn't exist yet, `--force` was
+/// given, or the user agreed.
+fn may_overwrite(ctx: &AppContext, args: &DownloadArgs, output: &Path) -> bool {
+    if !output.exists()
+        || args.force
+        || confirm(
+            ctx,
+            &format!("{} already exists. Overwrite?", output.display()),
+        )
+    {
+        return true;
+    }
+    eprintln!(
+        "Not overwriting {} (use --force or --assume-yes to overwrite)",
+        output.display()
+    );
+    false
+}
+
+/// Whether the file at `output` already is the artifact at `version`, and
+/// by what measure. The SHA-256 from `--expect-sha256` or the receipt of an
+/// earlier download of the same URL is checked when there is one; otherwise
+/// the size the listing gives. Only the listing may need the network.
+fn already_downloaded(
+    args: &DownloadArgs,
+    receipts: &Receipts,
+    api: &Api,
+    version: &Version,
+    output: &Path,
+) -> Option<&'static str> {
+    let recorded = || {
+        let receipt = receipts.load(version).ok()??;
+        let path = std::fs::canonicalize(output).ok()?;
+        let url = api.download_url(version);
+        receipt
+            .files
+            .into_iter()
+            .find(|file| file.path == path && file.url == url)
+            .map(|file| file.sha256)
+    };
+    if let Some(expected) = args.expect_sha256.clone().or_else(recorded) {
+        let digest = sha256_file(&long_path(output)).ok()?;
+        return expected.eq_ignore_ascii_case(&digest).then_some("SHA-256");
+    }
+
+    let size = std::fs::metadata(long_path(output)).ok()?.len();
+    (api.listed_size(&api.artifact_name(version))? == size).then_some("size")
+}
+
+/// Checks that `dir` can be written to before a system-wide install starts,
+/// creating it when needed.
+fn ensure_writable(dir: &Path) -> std::io::Result<()> {
+    let dir = long_path(dir);
+    std::fs::create_dir_all(&dir)?;
+    let probe = dir.join(".spc-utils-write-test");
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+/// Downloads one target, resolving the newest matching version first unless
+/// an exact one is pinned. Returns what `--write-version-file` records.
+fn download_target(
+    ctx: &AppContext,
+    args: &DownloadArgs,
+    requirements: &Requirements,
+    target: &Target,
+    spec: Option<VersionSpec>,
+) -> Option<VersionRecord> {
+    let output = target
+        .output
+        .as_ref()
+        .expect("targets are only downloaded with an output path");
+
+    if let Err(e) = requirements.check(CategoryProfile::of(&category_of(&target.category))) {
+        eprintln!("{}", e);
+        return None;
+    }
+
+    // With --skip-existing, the file is only in the way once it turns out
+    // not to be the wanted artifact.
+    if !args.skip_existing && !may_overwrite(ctx, args, output) {
+        return None;
+    }
+
+    let receipts = if args.system {
+        Receipts::system()
+    } else {
+        ctx.receipts.clone()
+    };
+
+    if args.system {
+        // Explicitly asked for, so only a person at a terminal gets a say.
+        if is_interactive() && !confirm(ctx, &format!("Install {} system-wide?", output.display()))
+        {
+            eprintln!("Not installing {}", output.display());
+            return None;
+        }
+
+        let output_dir = output.parent().unwrap_or(Path::new("."));
+        for dir in [output_dir, receipts.dir()] {
+            if let Err(e) = ensure_writable(dir) {
+                eprintln!("Cannot write to {}: {}", dir.display(), e);
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    if cfg!(windows) {
+                        eprintln!(
+                            "System-wide installs need an elevated prompt (Run as administrator)."
+                        );
+                    } else {
+                        eprintln!(
+                            "System-wide installs need root, e.g. `sudo spc-utils download --system`."
+                        );
+                    }
+                }
+                return None;
+            }
+        }
+    }
+
+    let version = match spec.as_ref().and_then(VersionSpec::exact) {
+        Some(version) => version.clone(),
+        None => {
+            let options = ApiOptions::new(
+                target.category.clone(),
+                spec.clone(),
+                target.os.clone(),
+                target.arch.clone(),
+                target.build_type.clone(),
+            );
+            let api = ctx
+                .api(options)
+                .with_no_cache(args.no_cache)
+                .with_explain(args.explain);
+            resolve_newest(&api).version
+        }
+    };
+
+    warn_eol(&version);
 
-pub fn run(ctx: &AppContext, args: DownloadArgs) {
     let options = ApiOptions::new(
-        args.category,
-        args.version,
-        args.os,
-        args.arch,
-        args.build_type,
+        target.category.clone(),
+        Some(version.clone().into()),
+        target.os.clone(),
+        target.arch.clone(),
+        target.build_type.clone(),
     );
 
-    let output = args.output;
-    let api = Api::new(ctx.cache.clone(), options).with_no_cache(args.no_cache);
+    let api = ctx
+        .api(options)
+        .with_no_cache(args.no_cache)
+        .with_copy(args.copy)
+        .with_progress(!args.no_progress)
+        .with_verify(!args.no_verify)
+        .with_retries(args.retries)
+        .with_retry_delay(args.retry_delay)
+        .with_explain(args.explain);
 
-    match api.download(&output) {
-        Ok(()) => println!("Download complete!"),
-        Err(e) => eprintln!("Download failed: {}", e),
+    if args.skip_existing && output.exists() {
+        if let Some(matched) = already_downloaded(args, &receipts, &api, &version, output) {
+            println!(
+                "{} is up to date ({} matches), skipping",
+                output.display(),
+                matched
+            );
+            return Some(VersionRecord {
+                sha256: sha256_file(&long_path(output)).ok(),
+                ..VersionRecord::resolved(&version, api.download_url(&version))
+            });
+        }
+        if !may_overwrite(ctx, args, output) {
+            return None;
+        }
     }
+
+    // A missing key is reported before anything is downloaded.
+    let signing_key = match args.verify_signature.then(|| signing_key(ctx, args)) {
+        Some(Ok(key)) => Some(key),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return None;
+        }
+        None => None,
+    };
+
+    let artifact = ArtifactName::parse(&api.artifact_name(&version));
+    let foreign = notice_foreign(artifact.as_ref());
+    if foreign
+        && args.system
+        && !args.target_confirm
+        && let Some(artifact) = &artifact
+    {
+        eprintln!(
+            "Not installing system-wide: {}",
+            foreign_install_error(artifact)
+        );
+        return None;
+    }
+
+    let hooks = &ctx.config.hooks;
+    let mut env = vec![
+        ("VERSION", version.to_string()),
+        ("PATH_TO_FILE", output.display().to_string()),
+    ];
+
+    if let Some(hook) = &hooks.pre_download
+        && !run_hook("pre_download", hook, &env)
+    {
+        return None;
+    }
+
+    let digest = match api.download(&output.to_string_lossy()) {
+        Ok(digest) => {
+            println!("Download complete!");
+            println!("SHA-256: {}", digest);
+            digest
+        }
+        Err(e) => {
+            eprintln!("Download failed: {}", e);
+            return None;
+        }
+    };
+
+    if let Some(expected) = &args.expect_sha256
+        && !expected.eq_ignore_ascii_case(&digest)
+    {
+        eprintln!(
+            "SHA-256 mismatch for {}: expected {}, got {}. Removed it.",
+            output.display(),
+            expected,
+            digest
+        );
+        let _ = std::fs::remove_file(long_path(output));
+        return None;
+    }
+
+    if let Some(key) = &signing_key {
+        match check_signature(&api, &api.artifact_name(&version), output, key) {
+            Ok(comment) => println!("Signature verified (trusted comment: {})", comment),
+            Err(e) => {
+                eprintln!(
+                    "Signature verification failed for {}: {}. Removed it.",
+                    output.display(),
+                    e
+                );
+                let _ = std::fs::remove_file(long_path(output));
+                return None;
+            }
+        }
+    }
+
+    if !args.mirror_check.is_empty() {
+        let check = || {
+            ApiOptions::new(
+                target.category.clone(),
+                Some(version.clone().into()),
+                target.os.clone(),
+                target.arch.clone(),
+                target.build_type.clone(),
+            )
+        };
+        if let Err(e) = check_mirrors(
+            ctx,
+            check,
+            &api.artifact_name(&version),
+            &digest,
+            &args.mirror_check,
+        ) {
+            eprintln!("{}", e);
+            let _ = std::fs::remove_file(long_path(output));
+            return None;
+        }
+    }
+
+    let url = api.download_url(&version);
+    let mut files = vec![output.clone()];
+
+    if args.with_checksums {
+        let name = api.artifact_name(&version);
+        for ext in COMPANION_EXTENSIONS {
+            let Some(contents) = api.fetch_companion(&name, ext) else {
+                continue;
+            };
+            let path = PathBuf::from(format!("{}{}", output.display(), ext));
+            match std::fs::write(&path, contents) {
+                Ok(()) => {
+                    println!("Saved {}", path.display());
+                    files.push(path);
+                }
+                Err(e) => eprintln!("Failed to write {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    if let Some(dir) = &args.extract {
+        let extracted = extract_archive(output, dir).and_then(|mut extracted| {
+            normalize_extracted(
+                &mut extracted,
+                dir,
+                artifact.as_ref(),
+                args.rename.as_deref(),
+            )?;
+            Ok(extracted)
+        });
+        match extracted {
+            Ok(extracted) => {
+                println!("Extracted {} files to {}", extracted.len(), dir.display());
+                files.extend(extracted);
+            }
+            Err(e) => {
+                eprintln!("Failed to extract {}: {}", output.display(), e);
+                return None;
+            }
+        }
+    }
+
+    // --system installs what runs from the prefix rather than just the
+    // archive: fpm gets a usable layout, cli and frankenphp their binary in
+    // bin/. micro.sfx only works appended to a script, so it stays in lib/.
+    let prefix = args
+        .system
+        .then(|| args.prefix.clone().unwrap_or_else(default_prefix));
+    let build = artifact
+        .as_ref()
+        .and_then(|artifact| Some((artifact.build_type.as_deref()?, artifact.binary_name()?)));
+    if let Some(prefix) = &prefix
+        && let Some((build_type, binary)) = build
+    {
+        let installed = match build_type {
+            "fpm" => fpm::lay_out(output, prefix).map(Some),
+            "cli" | "frankenphp" => {
+                install_binary(output, binary, &prefix.join("bin"), !foreign).map(Some)
+            }
+            _ => Ok(None),
+        };
+        match installed {
+            Ok(installed) => files.extend(installed),
+            Err(e) => {
+                eprintln!("Failed to install {}: {}", binary, e);
+                return None;
+            }
+        }
+    }
+
+    if let Err(e) = receipts.record(&version, &url, &files) {
+        eprintln!("Warning: Failed to write install receipt: {}", e);
+    }
+
+    if let Some(hook) = &hooks.post_download {
+        env.push(("SHA256", digest.clone()));
+        if !run_hook("post_download", hook, &env) {
+            return None;
+        }
+    }
+
+    if let Some(prefix) = &prefix
+        && matches!(build, Some(("fpm", _)))
+        && cfg!(target_os = "linux")
+        && !foreign
+    {
+        fpm::print_systemd_hint(prefix);
+    }
+
+    Some(VersionRecord {
+        sha256: Some(digest),
+        ..VersionRecord::resolved(&version, url)
+    })
 }
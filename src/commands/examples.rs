@@ -1,23 +1,156 @@
-pub fn run() {
-    println!(
-        r#"Usage Examples:
+use serde::Serialize;
 
-  Get the latest version:
-    spc-utils latest
-    spc-utils latest -C common -V 8.4
+use crate::cli::ExamplesArgs;
 
-  Check for updates:
-    spc-utils check-update -V 8.4.10
+/// One documented invocation. The same entries back `spc-utils examples`,
+/// `--examples` on every command, and the examples in `--help`.
+#[derive(Serialize)]
+pub struct Example {
+    pub command: &'static str,
+    pub description: &'static str,
+    pub invocation: &'static str,
+}
+
+const fn example(
+    command: &'static str,
+    description: &'static str,
+    invocation: &'static str,
+) -> Example {
+    Example {
+        command,
+        description,
+        invocation,
+    }
+}
+
+#[rustfmt::skip]
+pub static EXAMPLES: &[Example] = &[
+    example("latest", "Newest version for this platform", "spc-utils latest"),
+    example("latest", "Newest version of the bulk category", "spc-utils latest -C bulk"),
+    example("latest", "Newest 8.4 release of the common category", "spc-utils latest -C common -V 8.4"),
+    example("latest", "Newest FrankenPHP build", "spc-utils latest -C frankenphp"),
+    example("latest", "Skip the cached listing", "spc-utils latest --no-cache"),
+    example("check-update", "Check whether 8.4.10 is the newest patch", "spc-utils check-update -V 8.4.10"),
+    example("check-update", "Check against the common category", "spc-utils check-update -C common -V 8.4.10"),
+    example("check-update", "Skip the cached listing", "spc-utils check-update -V 8.4.10 --no-cache"),
+    example("check-update", "Upgrade a binary in place when an update is out", "spc-utils check-update --install --bin /usr/local/bin/php"),
+    example("check-update", "Export the result for node_exporter's textfile collector", "spc-utils check-update -V 8.4.10 --metrics-file /var/lib/node_exporter/spc_utils.prom"),
+    example("download", "Download the newest build for this platform", "spc-utils download -o php"),
+    example("download", "Download an exact version", "spc-utils download -C bulk -V 8.4.10 -o php"),
+    example("download", "Only download when the output is missing or outdated", "spc-utils download -V 8.4.12 -o php --skip-existing --force"),
+    example("download", "Download for another platform", "spc-utils download -C common -V 8.4 -O linux -A x86_64 -o ./php-binary"),
+    example("download", "Download from a local mirror", "spc-utils download -V 8.4 -o php --base-url file:///srv/spc-mirror"),
+    example("download", "Require a second mirror to serve identical bytes", "spc-utils download -V 8.4.12 -o php --mirror-check https://mirror.example.com/static-php-cli"),
+    example("download", "Skip the cached listing and artifact", "spc-utils download --no-cache -o php"),
+    example("download", "Fetch exactly what a manifest records", "spc-utils download --from-manifest spc-manifest.json"),
+    example("download", "Only accept a build signed by the configured signing_key", "spc-utils download -V 8.4 -o php.tar.gz --verify-signature"),
+    example("install", "Install an archive copied onto an offline machine", "spc-utils install --from-file ./php-8.3.12-cli-linux-x86_64.tar.gz"),
+    example("install", "Install exactly what a manifest records", "spc-utils install --from-manifest spc-manifest.json"),
+    example("install", "Install matching cli and fpm builds of PHP 8.3", "spc-utils install --profile web -V 8.3"),
+    example("install", "Only install an archive with a known SHA-256", "spc-utils install --from-file ./php-8.3.12-cli-linux-x86_64.tar.gz --expect-sha256 <sha256>"),
+    example("export-manifest", "Record the targets in .spc-utils.toml", "spc-utils export-manifest -o spc-manifest.json"),
+    example("export-manifest", "Record one build", "spc-utils export-manifest -C bulk -V 8.4 -B fpm -o spc-manifest.json"),
+    example("fetch-matrix", "Newest 8.4 cli build for every unix platform", "spc-utils fetch-matrix -C bulk -V 8.4 --dest dist"),
+    example("fetch-matrix", "cli and fpm builds for Linux only", "spc-utils fetch-matrix -V 8.4.12 -O linux -A x86_64,aarch64 -B cli,fpm --dest dist"),
+    example("url", "URL of the newest 8.3 fpm build", "spc-utils url -C bulk -V 8.3 -B fpm"),
+    example("url", "Fetch with curl instead", "curl -LO \"$(spc-utils url -C bulk -V 8.4)\""),
+    example("url", "URLs of the targets in .spc-utils.toml, as JSON", "spc-utils url --target app --target worker --json"),
+    example("list", "List versions for this platform", "spc-utils list"),
+    example("list", "List versions of the common category", "spc-utils list -C common"),
+    example("list", "List 8.4 releases", "spc-utils list -C common -V 8.4"),
+    example("list", "List cli builds for a specific platform", "spc-utils list -C common -O linux -A x86_64 -B cli"),
+    example("list", "Skip the cached listing", "spc-utils list --no-cache"),
+    example("cache", "Show cached listings", "spc-utils cache list"),
+    example("cache", "Clear the whole cache", "spc-utils cache clear"),
+    example("cache", "Clear one category", "spc-utils cache clear -C bulk"),
+    example("cache", "Fetch cached listings again and show what changed", "spc-utils cache refresh"),
+    example("cache", "Print the cache directory", "spc-utils cache path"),
+    example("outdated", "Check the pinned version in this project", "spc-utils outdated"),
+    example("outdated", "Check pinned versions across projects", "spc-utils outdated --workspace ~/code/services"),
+    example("outdated", "Check against the common category", "spc-utils outdated --workspace . -C common"),
+    example("sync", "Mirror everything", "spc-utils sync --dest /srv/spc-mirror"),
+    example("sync", "Mirror 8.4 builds of two categories", "spc-utils sync -C bulk -C common -V 8.4 --dest /srv/spc-mirror"),
+    example("sync", "Mirror one platform with an index", "spc-utils sync -C minimal -O linux -A x86_64 --dest ./mirror --emit-index"),
+    example("mirror", "Compare the mirrors listed in .spc-utils.toml", "spc-utils mirror bench"),
+    example("mirror", "Compare two mirrors on a 4 MiB sample", "spc-utils mirror bench https://dl.static-php.dev/static-php-cli https://mirror.example.com/static-php-cli --bytes 4194304"),
+    example("mirror", "Put the fastest configured mirror first", "spc-utils mirror bench --write"),
+    example("serve", "Serve listings on localhost", "spc-utils serve"),
+    example("serve", "Serve listings to other machines", "spc-utils serve --addr 0.0.0.0:8080"),
+    example("serve", "Serve listings and mirrored artifacts", "spc-utils serve --addr 0.0.0.0:8080 --artifacts-dir /srv/spc-mirror"),
+    example("api", "Show the raw bulk listing", "spc-utils api bulk"),
+    example("api", "Show the raw Windows listing", "spc-utils api windows/spc-max"),
+    example("api", "Query the server directly", "spc-utils api 'common?format=json' --no-cache"),
+    example("doctor", "Diagnose the cache and configuration", "spc-utils doctor"),
+    example("doctor", "Repair what can be repaired", "spc-utils doctor --fix"),
+    example("gc", "Preview what housekeeping would remove", "spc-utils gc --dry-run"),
+    example("gc", "Weekly cleanup keeping the two newest installs", "spc-utils gc --max-artifact-cache 1G --keep-installed 2"),
+    example("parse", "Decompose an artifact name", "spc-utils parse php-8.3.12-fpm-linux-aarch64.tar.gz"),
+    example("parse", "Decompose an artifact name as JSON", "spc-utils parse php-8.3.12-cli-win.zip --json"),
+    example("resolve", "Resolve a requirements file", "spc-utils resolve --requirements requirements.toml"),
+    example("resolve", "Resolve extensions and a version", "spc-utils resolve --require-ext gd,redis -V 8.3"),
+    example("resolve", "Resolve a requirements file as JSON", "spc-utils resolve --requirements requirements.toml --json"),
+    example("categories", "Overview of the build categories", "spc-utils categories"),
+    example("categories", "Extensions and libraries of a category", "spc-utils categories common"),
+    example("categories", "What sets two categories apart", "spc-utils categories common --compare bulk"),
+    example("categories", "Smallest category with the extensions you need", "spc-utils categories --with gd,redis"),
+    example("compare", "Compare two versions", "spc-utils compare 8.3.10 8.4.1"),
+    example("compare", "Compare availability on one platform", "spc-utils compare 8.3.10 8.3.14 -C common -O linux -A x86_64"),
+    example("verify-install", "Check all downloads against their receipts", "spc-utils verify-install"),
+    example("verify-install", "Check one version", "spc-utils verify-install 8.4.12"),
+    example("migrate", "Move installed state to another disk", "spc-utils migrate --data-dir /srv/spc-utils"),
+    example("migrate", "Move installed state and the cache", "spc-utils migrate --data-dir /srv/spc-utils --cache-dir /var/cache/spc-utils"),
+    example("micro", "Get micro.sfx for phar packaging", "spc-utils micro fetch -V 8.3 -o micro.sfx"),
+    example("micro", "Get micro.sfx for another platform", "spc-utils micro fetch -C minimal -O linux -A aarch64 -o build/micro.sfx"),
+    example("history", "When builds appeared or changed upstream", "spc-utils history remote -C bulk"),
+    example("history", "Listing history as JSON", "spc-utils history remote -C bulk --json"),
+    example("version", "Show version and build details", "spc-utils version"),
+    example("version", "Build details for a bug report", "spc-utils version --json"),
+    example("examples", "Examples for one command", "spc-utils examples download"),
+    example("examples", "All examples as JSON", "spc-utils examples --json"),
+];
+
+/// Names of the commands that have examples, in the order they are listed.
+pub fn commands() -> Vec<&'static str> {
+    let mut commands: Vec<&str> = EXAMPLES.iter().map(|example| example.command).collect();
+    commands.dedup();
+    commands
+}
+
+/// The examples of `command`, formatted for the end of its `--help`.
+pub fn after_help(command: &str) -> String {
+    let mut help = "Examples:".to_string();
+    for example in EXAMPLES.iter().filter(|example| example.command == command) {
+        help.push_str("\n  ");
+        help.push_str(example.invocation);
+    }
+    help
+}
 
-  Download a binary:
-    spc-utils download -o php
-    spc-utils download -C bulk -V 8.4 -o ./php-bin
+/// Prints the examples of `command`, or of every command.
+pub fn print(command: Option<&str>, json: bool) {
+    let examples: Vec<&Example> = EXAMPLES
+        .iter()
+        .filter(|example| command.is_none_or(|command| example.command == command))
+        .collect();
 
-  Manage cache:
-    spc-utils cache list
-    spc-utils cache clear
+    if json {
+        println!("{}", serde_json::to_string_pretty(&examples).unwrap());
+        return;
+    }
+
+    println!("Usage Examples:");
+    let mut current = "";
+    for example in examples {
+        if example.command != current {
+            current = example.command;
+            println!();
+            println!("  {}:", current);
+        }
+        println!("    # {}", example.description);
+        println!("    {}", example.invocation);
+    }
+}
 
-  Skip cache on any command:
-    spc-utils latest --no-cache"#
-    );
+pub fn run(args: ExamplesArgs) {
+    print(args.command.as_deref(), args.json);
 }
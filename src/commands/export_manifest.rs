@@ -0,0 +1,155 @@
+use std::{error::Error, fs, path::Path};
+
+use chrono::Local;
+
+use crate::{
+    AppContext,
+    cli::ExportManifestArgs,
+    commands::{
+        archive::sha256_file,
+        selection::{resolve_newest, select_targets},
+    },
+    config::{PROJECT_CONFIG_FILE, Target},
+    manifest::{Manifest, ManifestArtifact},
+    spc::{Api, ApiOptions, BuildCategory, DEFAULT_BASE_URL, Source, VersionSpec, parse_sha256},
+};
+
+pub fn run(ctx: &AppContext, args: ExportManifestArgs) {
+    let flags = Target {
+        category: args.category.clone(),
+        version: None,
+        os: args.os.clone(),
+        arch: args.arch.clone(),
+        build_type: args.build_type.clone(),
+        output: None,
+    };
+    // Without any selection, a project's targets are what it depends on.
+    let all = !ctx.config.targets.is_empty()
+        && args.version.is_none()
+        && flags.category.is_none()
+        && flags.os.is_none()
+        && flags.arch.is_none()
+        && flags.build_type.is_none();
+
+    let mut artifacts = Vec::new();
+    for (name, target) in select_targets(ctx, flags, &args.targets, all) {
+        match resolve(ctx, &args, name, target) {
+            Ok(artifact) => {
+                println!("{} -> {}", artifact.name, artifact.sha256);
+                artifacts.push(artifact);
+            }
+            Err(e) => {
+                eprintln!("Failed to resolve an artifact: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let manifest = Manifest {
+        spc_utils_version: env!("CARGO_PKG_VERSION").to_string(),
+        generated_at: Local::now(),
+        base_url: ctx
+            .base_url
+            .clone()
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+        github_repo: (ctx.source == Source::Github)
+            .then(|| ctx.github_repo.clone())
+            .flatten(),
+        config: fs::read_to_string(PROJECT_CONFIG_FILE).ok(),
+        artifacts,
+    };
+
+    let written = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            fs::write(&args.output, json + "\n")
+                .map_err(|e| format!("{}: {}", args.output.display(), e))
+        });
+    match written {
+        Ok(()) => println!(
+            "Wrote {} ({} artifacts)",
+            args.output.display(),
+            manifest.artifacts.len()
+        ),
+        Err(e) => {
+            eprintln!("Failed to write manifest: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Resolves one target to an exact artifact and its SHA-256.
+fn resolve(
+    ctx: &AppContext,
+    args: &ExportManifestArgs,
+    target_name: Option<String>,
+    target: Target,
+) -> Result<ManifestArtifact, Box<dyn Error>> {
+    let spec = args.version.clone().or_else(|| target.version_spec());
+    let options = |spec: Option<VersionSpec>| {
+        ApiOptions::new(
+            target.category.clone(),
+            spec,
+            target.os.clone(),
+            target.arch.clone(),
+            target.build_type.clone(),
+        )
+    };
+
+    let version = match spec.as_ref().and_then(VersionSpec::exact) {
+        Some(version) => version.clone(),
+        None => {
+            resolve_newest(&ctx.api(options(spec.clone())).with_no_cache(args.no_cache)).version
+        }
+    };
+
+    let exact = ctx.apply_defaults(options(Some(version.clone().into())));
+    let (category, os, arch, build_type) = (
+        exact.category(),
+        exact.os(),
+        exact.arch(),
+        exact.build_type(),
+    );
+    let api = ctx.api(exact);
+    let name = api.artifact_name(&version);
+    let sha256 = artifact_sha256(ctx, &api, &category, &name)?;
+
+    Ok(ManifestArtifact {
+        target: target_name,
+        category,
+        requested: spec.map(|spec| spec.to_string()),
+        url: api.download_url(&version),
+        version,
+        os,
+        arch,
+        build_type,
+        name,
+        sha256,
+        output: target.output,
+    })
+}
+
+/// The artifact's SHA-256: from the artifact cache, else the checksum the
+/// server publishes, else by downloading it (into the cache for later).
+fn artifact_sha256(
+    ctx: &AppContext,
+    api: &Api,
+    category: &BuildCategory,
+    name: &str,
+) -> Result<String, Box<dyn Error>> {
+    let cached = ctx.cache.artifact_path(category, name);
+    if cached.exists() {
+        return Ok(sha256_file(&cached)?);
+    }
+    if let Some(digest) = api
+        .fetch_companion(name, ".sha256")
+        .and_then(|contents| parse_sha256(&contents))
+    {
+        return Ok(digest);
+    }
+
+    let scratch = std::env::temp_dir().join(format!("spc-utils-{}-{}", std::process::id(), name));
+    let digest = api.download(&scratch.to_string_lossy());
+    let _ = fs::remove_file(Path::new(&scratch));
+    digest
+}
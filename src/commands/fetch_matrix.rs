@@ -0,0 +1,167 @@
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::{
+    AppContext,
+    cli::FetchMatrixArgs,
+    commands::selection::newest_common_version,
+    spc::{ApiOptions, BuildCategory, VersionSpec},
+};
+
+/// One cell of the matrix: where it is fetched from and where it goes,
+/// relative to `DEST/<version>`.
+struct Cell {
+    os: Option<String>,
+    arch: Option<String>,
+    build_type: String,
+    dir: String,
+}
+
+impl Cell {
+    fn label(&self) -> String {
+        match (&self.os, &self.arch) {
+            (Some(os), Some(arch)) => format!("{} {}-{}", self.build_type, os, arch),
+            _ => format!("{} windows", self.build_type),
+        }
+    }
+
+    fn options(&self, category: &BuildCategory, version: Option<VersionSpec>) -> ApiOptions {
+        ApiOptions::new(
+            Some(category.clone()),
+            version,
+            self.os.clone(),
+            self.arch.clone(),
+            Some(self.build_type.clone()),
+        )
+    }
+}
+
+pub fn run(ctx: &AppContext, args: FetchMatrixArgs) {
+    let category = args
+        .category
+        .clone()
+        .unwrap_or_else(BuildCategory::default_for_os);
+    let cells = cells(&category, &args);
+
+    // The version is resolved once for the whole matrix, so every platform
+    // ends up on the same release.
+    let version = match args.version.as_ref().and_then(VersionSpec::exact) {
+        Some(version) => version.clone(),
+        None => {
+            let selections: Vec<(String, ApiOptions)> = cells
+                .iter()
+                .map(|cell| (cell.label(), cell.options(&category, args.version.clone())))
+                .collect();
+            newest_common_version(ctx, &category, args.no_cache, &selections)
+        }
+    };
+    println!("Fetching PHP {} for {} platforms", version, cells.len());
+
+    let root = args.dest.join(version.to_string());
+    let spec = Some(VersionSpec::Exact(version.clone()));
+    let mut sums = Vec::new();
+    let mut failed = Vec::new();
+    for cell in &cells {
+        let api = ctx
+            .api(cell.options(&category, spec.clone()))
+            .with_no_cache(args.no_cache);
+        if api.is_cancelled() {
+            failed.push(cell.label());
+            continue;
+        }
+        let name = api.artifact_name(&version);
+        let path = root.join(&cell.dir).join(&name);
+
+        let result = fs::create_dir_all(root.join(&cell.dir))
+            .map_err(|e| e.into())
+            .and_then(|()| api.download(&path.to_string_lossy()));
+        match result {
+            Ok(digest) => sums.push((digest, format!("{}/{}", cell.dir, name))),
+            Err(e) => {
+                eprintln!("Failed to fetch {}: {}", cell.label(), e);
+                failed.push(cell.label());
+            }
+        }
+    }
+
+    if !sums.is_empty()
+        && let Err(e) = write_sums(&root.join("SHA256SUMS"), &sums)
+    {
+        eprintln!("Failed to write checksums: {}", e);
+        std::process::exit(1);
+    }
+
+    println!(
+        "Fetched {} of {} artifacts into {}",
+        sums.len(),
+        cells.len(),
+        root.display()
+    );
+    if !failed.is_empty() {
+        eprintln!("Missing: {}", failed.join(", "));
+        std::process::exit(1);
+    }
+}
+
+/// Every requested combination. Windows builds carry no arch, so a Windows
+/// category has one cell per build type, all under `windows/`.
+fn cells(category: &BuildCategory, args: &FetchMatrixArgs) -> Vec<Cell> {
+    let windows = matches!(category, BuildCategory::WinMin | BuildCategory::WinMax);
+    let oses: Vec<String> = match (windows, args.os.is_empty()) {
+        (true, true) => vec!["windows".to_string()],
+        (false, true) => vec!["linux".to_string(), "macos".to_string()],
+        (_, false) => args
+            .os
+            .iter()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    };
+    if oses.iter().any(|os| (os == "windows") != windows) {
+        eprintln!(
+            "The {} category only has {} builds",
+            category,
+            if windows {
+                "Windows"
+            } else {
+                "Linux and macOS"
+            }
+        );
+        std::process::exit(2);
+    }
+
+    let mut cells = Vec::new();
+    for build_type in &args.build_type {
+        if windows {
+            cells.push(Cell {
+                os: None,
+                arch: None,
+                build_type: build_type.clone(),
+                dir: "windows".to_string(),
+            });
+            continue;
+        }
+        for os in &oses {
+            for arch in &args.arch {
+                cells.push(Cell {
+                    os: Some(os.clone()),
+                    arch: Some(arch.clone()),
+                    build_type: build_type.clone(),
+                    dir: format!("{}-{}", os, arch),
+                });
+            }
+        }
+    }
+    cells
+}
+
+/// Writes `sha256sum`-compatible checksums, paths relative to the file.
+fn write_sums(path: &Path, sums: &[(String, String)]) -> std::io::Result<()> {
+    let contents: String = sums
+        .iter()
+        .map(|(digest, name)| format!("{}  {}\n", digest, name))
+        .collect();
+    fs::write(path, contents)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    commands::archive::{extract_file, make_executable},
+    platform::long_path,
+};
+
+/// Sets up a system-wide fpm install under `prefix`: the `php-fpm` binary
+/// from the archive in `sbin/`, plus a minimal `php-fpm.conf` and `www` pool
+/// in `etc/` unless they already exist. Returns the installed binary.
+pub fn lay_out(archive: &Path, prefix: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let sbin = prefix.join("sbin");
+    fs::create_dir_all(long_path(&sbin))?;
+    let binary = sbin.join("php-fpm");
+    extract_file(archive, "php-fpm", &binary)?;
+    make_executable(&binary)?;
+    println!("Installed {}", binary.display());
+
+    let etc = prefix.join("etc");
+    let pool_dir = etc.join("php-fpm.d");
+    fs::create_dir_all(long_path(&pool_dir))?;
+
+    let main_config = format!(
+        "; Written by spc-utils; edit freely, it is never overwritten.\n\
+         [global]\n\
+         pid = /run/php-fpm.pid\n\
+         error_log = /var/log/php-fpm.log\n\
+         include = {}/*.conf\n",
+        pool_dir.display()
+    );
+    let pool_config = "\
+; Written by spc-utils; edit freely, it is never overwritten.
+[www]
+user = www-data
+group = www-data
+listen = 127.0.0.1:9000
+pm = dynamic
+pm.max_children = 5
+pm.start_servers = 2
+pm.min_spare_servers = 1
+pm.max_spare_servers = 3
+";
+
+    for (path, contents) in [
+        (etc.join("php-fpm.conf"), main_config.as_str()),
+        (pool_dir.join("www.conf"), pool_config),
+    ] {
+        if path.exists() {
+            println!("Keeping existing {}", path.display());
+        } else {
+            fs::write(long_path(&path), contents)?;
+            println!("Wrote {}", path.display());
+        }
+    }
+
+    Ok(binary)
+}
+
+/// Prints a systemd unit that runs the installed php-fpm.
+pub fn print_systemd_hint(prefix: &Path) {
+    let binary = prefix.join("sbin").join("php-fpm");
+    let config = prefix.join("etc").join("php-fpm.conf");
+
+    println!();
+    println!("To run php-fpm as a service, save this as /etc/systemd/system/php-fpm.service:");
+    println!();
+    println!("  [Unit]");
+    println!("  Description=PHP FastCGI Process Manager");
+    println!("  After=network.target");
+    println!();
+    println!("  [Service]");
+    println!(
+        "  ExecStart={} --nodaemonize --fpm-config {}",
+        binary.display(),
+        config.display()
+    );
+    println!("  ExecReload=/bin/kill -USR2 $MAINPID");
+    println!();
+    println!("  [Install]");
+    println!("  WantedBy=multi-user.target");
+    println!();
+    println!("then run `systemctl daemon-reload && systemctl enable --now php-fpm`.");
+}
@@ -0,0 +1,251 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use semver::Version;
+
+use crate::{
+    AppContext,
+    cli::GcArgs,
+    commands::{
+        cache::format_size,
+        prompt::{confirm, is_interactive},
+    },
+    platform::long_path,
+};
+
+/// Partial downloads and scratch files younger than this may belong to a
+/// run that is still going.
+const PARTIAL_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// Removes files and directories, or only reports them with `--dry-run`, and
+/// keeps count.
+struct Sweep {
+    dry_run: bool,
+    reclaimed: u64,
+    failed: bool,
+}
+
+impl Sweep {
+    /// Removes `paths` and prints one summary line for them. Returns whether
+    /// all of them are gone.
+    fn remove(&mut self, label: &str, paths: &[(PathBuf, u64)]) -> bool {
+        let mut removed = 0;
+        let mut size = 0;
+        for (path, len) in paths {
+            let path = long_path(path);
+            let result = if self.dry_run {
+                println!("Would remove {} ({})", path.display(), format_size(*len));
+                Ok(())
+            } else if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to remove {}: {}", path.display(), e);
+                self.failed = true;
+                continue;
+            }
+            removed += 1;
+            size += len;
+        }
+
+        self.reclaimed += size;
+        println!(
+            "{}: {} file{}, {}",
+            label,
+            removed,
+            if removed == 1 { "" } else { "s" },
+            format_size(size)
+        );
+        removed == paths.len()
+    }
+}
+
+pub fn run(ctx: &AppContext, args: GcArgs) {
+    let mut sweep = Sweep {
+        dry_run: args.dry_run,
+        reclaimed: 0,
+        failed: false,
+    };
+
+    sweep.remove("Expired cache entries", &ctx.cache.expired_files());
+
+    let (partial, mut artifacts): (Vec<_>, Vec<_>) = ctx
+        .cache
+        .artifact_files()
+        .into_iter()
+        .partition(|(path, _, _)| path.extension().is_some_and(|ext| ext == "part"));
+
+    let mut abandoned: Vec<(PathBuf, u64)> = partial
+        .into_iter()
+        .filter(|(_, _, used)| is_abandoned(*used))
+        .map(|(path, len, _)| (path, len))
+        .collect();
+    abandoned.extend(scratch_files(ctx));
+    sweep.remove("Leftovers of interrupted runs", &abandoned);
+
+    // Least recently used first, until the rest fits.
+    artifacts.sort_by_key(|(_, _, used)| *used);
+    let mut total: u64 = artifacts.iter().map(|(_, len, _)| len).sum();
+    let mut evicted = Vec::new();
+    for (path, len, _) in artifacts {
+        if total <= args.max_artifact_cache {
+            break;
+        }
+        total -= len;
+        evicted.push((path, len));
+    }
+    sweep.remove(
+        &format!(
+            "Artifact cache over {}",
+            format_size(args.max_artifact_cache)
+        ),
+        &evicted,
+    );
+
+    if let Some(keep) = args.keep_installed {
+        prune_installed(ctx, &mut sweep, keep);
+    }
+
+    println!(
+        "{} {}",
+        if args.dry_run {
+            "Would reclaim"
+        } else {
+            "Reclaimed"
+        },
+        format_size(sweep.reclaimed)
+    );
+    if sweep.failed {
+        std::process::exit(1);
+    }
+}
+
+fn is_abandoned(used: SystemTime) -> bool {
+    SystemTime::now()
+        .duration_since(used)
+        .is_ok_and(|age| age > PARTIAL_GRACE)
+}
+
+/// Scratch files and directories that runs create in the temp directory
+/// (`spc-utils-<pid>...`), receipts or registries left half-written and
+/// installs left in staging, once they are old enough to be abandoned.
+fn scratch_files(ctx: &AppContext) -> Vec<(PathBuf, u64)> {
+    let temp = fs::read_dir(std::env::temp_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("spc-utils-")
+        });
+    let half_written = [ctx.receipts.dir(), ctx.registry.dir()]
+        .into_iter()
+        .flat_map(fs::read_dir)
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.ends_with(".json.tmp") || name.starts_with(".staging-")
+        });
+
+    temp.chain(half_written)
+        .filter(|entry| {
+            entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(is_abandoned)
+        })
+        .map(|entry| (entry.path(), disk_usage(&entry.path())))
+        .collect()
+}
+
+/// The size of the file at `path`, or of everything under it.
+fn disk_usage(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| disk_usage(&entry.path()))
+            .sum(),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Removes the installs of every version but the `keep` newest from the
+/// versions directory, and their files from the receipts. The active install
+/// of each build type is kept too, and downloads that receipts record
+/// elsewhere on disk are left alone.
+fn prune_installed(ctx: &AppContext, sweep: &mut Sweep, keep: usize) {
+    let installs = ctx.registry.load().unwrap_or_else(|e| {
+        eprintln!("Failed to read the registry of installs: {}", e);
+        std::process::exit(1);
+    });
+
+    let mut versions: Vec<&Version> = installs.installs.values().map(|i| &i.version).collect();
+    versions.sort();
+    versions.dedup();
+    let kept = &versions[versions.len().saturating_sub(keep)..];
+    let prune: Vec<(&String, &Version)> = installs
+        .installs
+        .iter()
+        .filter(|(name, install)| !kept.contains(&&install.version) && !installs.is_active(name))
+        .map(|(name, install)| (name, &install.version))
+        .collect();
+    if prune.is_empty() {
+        return;
+    }
+
+    // `--keep-installed` was asked for, so only a person at a terminal gets
+    // a say.
+    if !sweep.dry_run && is_interactive() {
+        for (name, _) in &prune {
+            println!("  {}", name);
+        }
+        if !confirm(ctx, &format!("Remove {} installs?", prune.len())) {
+            eprintln!("Not pruning installed versions");
+            return;
+        }
+    }
+
+    for (name, version) in prune {
+        let dir = ctx.registry.dir().join(name);
+        let canonical = fs::canonicalize(&dir).unwrap_or_else(|_| dir.clone());
+        let label = format!("PHP {} ({})", version, name);
+        let files = [(dir.clone(), disk_usage(&dir))];
+        if sweep.dry_run {
+            sweep.remove(&label, &files);
+            continue;
+        }
+
+        // Under the registry's lock, so a concurrent install can't make it
+        // active or replace it halfway through.
+        let removed = ctx.registry.update(|installs| {
+            if installs.is_active(name) {
+                return false;
+            }
+            let removed = sweep.remove(&label, &files);
+            if removed {
+                installs.remove(name);
+            }
+            removed
+        });
+        let result = match removed {
+            Ok(true) => ctx.receipts.forget(version, &canonical),
+            Ok(false) => Ok(()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to forget {}: {}", name, e);
+            sweep.failed = true;
+        }
+    }
+}
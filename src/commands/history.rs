@@ -0,0 +1,107 @@
+use clap::Subcommand;
+
+use crate::{
+    AppContext,
+    commands::HumanFormat,
+    spc::{ApiOptions, BuildCategory, ChangeKind, HistoryEvent, ListingHistory},
+};
+
+#[derive(Clone, Subcommand)]
+pub enum HistoryAction {
+    #[command(about = "Show when artifacts appeared, changed or disappeared upstream")]
+    Remote {
+        #[arg(short = 'C', long, value_enum)]
+        category: Option<BuildCategory>,
+        #[arg(long, help = "Print the history as JSON for scripts")]
+        json: bool,
+        #[arg(long, help = "Refresh the listing even if the cached one is valid")]
+        no_cache: bool,
+    },
+}
+
+pub fn run(ctx: &AppContext, action: HistoryAction) {
+    match action {
+        HistoryAction::Remote {
+            category,
+            json,
+            no_cache,
+        } => {
+            let category = category.unwrap_or_else(BuildCategory::default_for_os);
+            let options = ApiOptions::new(Some(category.clone()), None, None, None, None);
+            let api = ctx.api(options).with_no_cache(no_cache);
+
+            // History is only recorded when a listing is fetched, so bring it
+            // up to date first.
+            if let Err(e) = api.fetch_versions() {
+                eprintln!("Warning: Failed to refresh the {} listing: {}", category, e);
+            }
+
+            let history = api.history();
+            if json {
+                match serde_json::to_string_pretty(&history) {
+                    Ok(out) => println!("{}", out),
+                    Err(e) => {
+                        eprintln!("Failed to serialize history: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+
+            print_history(&ctx.human, &category, &history);
+        }
+    }
+}
+
+fn print_history(human: &HumanFormat, category: &BuildCategory, history: &ListingHistory) {
+    let Some(since) = history.since else {
+        println!("No history recorded for {} yet.", category);
+        return;
+    };
+
+    println!(
+        "History of {} since {}",
+        category,
+        human.time(&since, "%Y-%m-%d %H:%M")
+    );
+    if history.events.is_empty() {
+        println!("No changes seen upstream.");
+        return;
+    }
+
+    for event in &history.events {
+        println!(
+            "{}  {:<8}  {}  {}",
+            human.time(&event.detected_at, "%Y-%m-%d %H:%M"),
+            kind_label(event.kind),
+            event.name,
+            describe(human, event)
+        );
+    }
+}
+
+fn kind_label(kind: ChangeKind) -> &'static str {
+    match kind {
+        ChangeKind::Appeared => "appeared",
+        ChangeKind::Changed => "changed",
+        ChangeKind::Removed => "removed",
+    }
+}
+
+fn describe(human: &HumanFormat, event: &HistoryEvent) -> String {
+    let modified = event.last_modified.format("%Y-%m-%d %H:%M:%S");
+    match (&event.previous_size, event.previous_modified) {
+        (Some(previous_size), Some(previous_modified)) => format!(
+            "(size: {} -> {}, last modified: {} -> {})",
+            human.size(previous_size),
+            human.size(&event.size),
+            previous_modified.format("%Y-%m-%d %H:%M:%S"),
+            modified
+        ),
+        _ => format!(
+            "(size: {}, last modified: {})",
+            human.size(&event.size),
+            modified
+        ),
+    }
+}
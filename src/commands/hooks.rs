@@ -0,0 +1,28 @@
+use std::process::Command;
+
+/// Runs a configured hook through the shell with `env` added to its
+/// environment. Returns whether it succeeded.
+pub(crate) fn run_hook(name: &str, command: &str, env: &[(&str, String)]) -> bool {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+    shell.envs(env.iter().map(|(key, value)| (key, value)));
+
+    match shell.status() {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            eprintln!("{} hook failed ({})", name, status);
+            false
+        }
+        Err(e) => {
+            eprintln!("Failed to run {} hook: {}", name, e);
+            false
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use semver::Version;
+
+use crate::{
+    AppContext,
+    cli::InstallArgs,
+    commands::{
+        archive::{extract_archive, normalize_extracted, sha256_file},
+        hooks::run_hook,
+        notices::{foreign_install_error, notice_foreign},
+        pinned::{fetch_pinned, pinned_api},
+        prompt::confirm,
+        selection::{newest_common_version, resolve_newest, select_targets},
+    },
+    config::{PROJECT_CONFIG_FILE, Target},
+    interrupt::PartialFile,
+    manifest::Manifest,
+    platform::long_path,
+    spc::{ApiOptions, ArtifactName, BuildCategory, CategoryProfile, VersionSpec, parse_sha256},
+};
+
+/// Builds that are installed together and always on the same version.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum InstallProfile {
+    /// The cli and fpm builds a PHP web host runs side by side
+    Web,
+}
+
+impl InstallProfile {
+    fn build_types(self) -> &'static [&'static str] {
+        match self {
+            InstallProfile::Web => &["cli", "fpm"],
+        }
+    }
+}
+
+pub fn run(ctx: &AppContext, args: InstallArgs) {
+    if let Some(path) = &args.from_manifest {
+        install_from_manifest(ctx, &args, path);
+        return;
+    }
+    if let Some(profile) = args.profile {
+        install_profile(ctx, &args, profile);
+        return;
+    }
+    let Some(archive) = args.from_file.as_deref() else {
+        install_targets(ctx, &args);
+        return;
+    };
+
+    if let Err(e) = install_from_file(ctx, &args, archive, args.expect_sha256.as_deref()) {
+        eprintln!("Failed to install {}: {}", archive.display(), e);
+        std::process::exit(1);
+    }
+}
+
+/// Downloads each artifact a manifest records and installs it, checked
+/// against the recorded SHA-256.
+fn install_from_manifest(ctx: &AppContext, args: &InstallArgs, path: &Path) {
+    let manifest = Manifest::load(path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let scratch = std::env::temp_dir().join(format!("spc-utils-{}", std::process::id()));
+    let _scratch = PartialFile::track(&scratch);
+    let mut failed = false;
+    for artifact in &manifest.artifacts {
+        // Installing needs the upstream file name, so keep it.
+        let archive = scratch.join(&artifact.name);
+        let installed = fs::create_dir_all(&scratch)
+            .map_err(|e| e.into())
+            .and_then(|_| fetch_pinned(&pinned_api(ctx, artifact), artifact, &archive))
+            .and_then(|_| install_from_file(ctx, args, &archive, Some(&artifact.sha256)));
+        let _ = fs::remove_file(&archive);
+        if let Err(e) = installed {
+            eprintln!("Failed to install {}: {}", artifact.name, e);
+            failed = true;
+        }
+    }
+    let _ = fs::remove_dir(&scratch);
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Installs every build of `profile` at the newest version they all have.
+/// All of them are downloaded, verified and unpacked before any existing
+/// install is replaced, so a failure leaves the previous installs as they
+/// were.
+fn install_profile(ctx: &AppContext, args: &InstallArgs, profile: InstallProfile) {
+    let category = args
+        .category
+        .clone()
+        .unwrap_or_else(BuildCategory::default_for_os);
+    let published = CategoryProfile::of(&category);
+    let missing: Vec<&str> = profile
+        .build_types()
+        .iter()
+        .copied()
+        .filter(|build_type| !published.has_build_type(build_type))
+        .collect();
+    if !missing.is_empty() {
+        eprintln!(
+            "The {} category has no {} builds, so it can't be used with --profile {}",
+            category,
+            missing.join(" or "),
+            profile_name(profile)
+        );
+        std::process::exit(2);
+    }
+
+    let options = |version: Option<VersionSpec>, build_type: &str| {
+        ApiOptions::new(
+            Some(category.clone()),
+            version,
+            args.os.clone(),
+            args.arch.clone(),
+            Some(build_type.to_string()),
+        )
+    };
+    let version = match args.version.as_ref().and_then(VersionSpec::exact) {
+        Some(version) => version.clone(),
+        None => {
+            let selections: Vec<(String, ApiOptions)> = profile
+                .build_types()
+                .iter()
+                .map(|build_type| {
+                    (
+                        build_type.to_string(),
+                        options(args.version.clone(), build_type),
+                    )
+                })
+                .collect();
+            newest_common_version(ctx, &category, args.no_cache, &selections)
+        }
+    };
+    println!(
+        "Installing PHP {} ({})",
+        version,
+        profile.build_types().join(" + ")
+    );
+
+    let scratch = std::env::temp_dir().join(format!("spc-utils-{}", std::process::id()));
+    let _scratch = PartialFile::track(&scratch);
+    let mut downloads = Vec::new();
+    for build_type in profile.build_types() {
+        let options = options(Some(VersionSpec::Exact(version.clone())), build_type);
+        match download_archive(ctx, args, options, &version, &scratch) {
+            Ok(download) => downloads.push(download),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&scratch);
+                eprintln!(
+                    "Failed to download the {} build: {}. Nothing was installed.",
+                    build_type, e
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let installed = unpack_all(ctx, args, &downloads);
+    let _ = fs::remove_dir_all(&scratch);
+    if let Err(e) = installed {
+        eprintln!(
+            "Failed to install the {} profile: {}",
+            profile_name(profile),
+            e
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Installs the targets in the project config, or those picked with
+/// `--target`, each at the newest version its pin allows. As with profiles,
+/// no existing install is replaced until all of them are unpacked.
+fn install_targets(ctx: &AppContext, args: &InstallArgs) {
+    if ctx.config.targets.is_empty() {
+        eprintln!(
+            "error: --from-file, --from-manifest or --profile is required unless targets are defined in {}",
+            PROJECT_CONFIG_FILE
+        );
+        std::process::exit(2);
+    }
+
+    let flags = Target {
+        category: args.category.clone(),
+        os: args.os.clone(),
+        arch: args.arch.clone(),
+        ..Target::default()
+    };
+    let scratch = std::env::temp_dir().join(format!("spc-utils-{}", std::process::id()));
+    let _scratch = PartialFile::track(&scratch);
+    let mut downloads = Vec::new();
+    for (name, target) in select_targets(ctx, flags, &args.targets, true) {
+        let name = name.unwrap_or_default();
+        println!("[{}]", name);
+        let options = |version: Option<VersionSpec>| {
+            ApiOptions::new(
+                target.category.clone(),
+                version,
+                target.os.clone(),
+                target.arch.clone(),
+                target.build_type.clone(),
+            )
+        };
+        let spec = args.version.clone().or_else(|| target.version_spec());
+        let version = match spec.as_ref().and_then(VersionSpec::exact) {
+            Some(version) => version.clone(),
+            None => resolve_newest(&ctx.api(options(spec)).with_no_cache(args.no_cache)).version,
+        };
+
+        let options = options(Some(VersionSpec::Exact(version.clone())));
+        match download_archive(ctx, args, options, &version, &scratch) {
+            Ok(download) => downloads.push(download),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&scratch);
+                eprintln!(
+                    "Failed to download target '{}': {}. Nothing was installed.",
+                    name, e
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let installed = unpack_all(ctx, args, &downloads);
+    let _ = fs::remove_dir_all(&scratch);
+    if let Err(e) = installed {
+        eprintln!("Failed to install the targets: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Downloads the artifact `options` select at `version` into `scratch`,
+/// returning it with its SHA-256 and URL. Builds for another machine are
+/// refused before anything is downloaded rather than after.
+fn download_archive(
+    ctx: &AppContext,
+    args: &InstallArgs,
+    options: ApiOptions,
+    version: &Version,
+    scratch: &Path,
+) -> Result<(PathBuf, String, String), Box<dyn Error>> {
+    let api = ctx
+        .api(options)
+        .with_no_cache(args.no_cache)
+        .with_copy(args.copy);
+    let name = api.artifact_name(version);
+    if let Some(artifact) = ArtifactName::parse(&name)
+        && artifact.is_foreign()
+        && !args.target_confirm
+    {
+        return Err(foreign_install_error(&artifact).into());
+    }
+
+    let archive = scratch.join(&name);
+    fs::create_dir_all(scratch)?;
+    let digest = api.download(&archive.to_string_lossy())?;
+    Ok((archive, digest, api.download_url(version)))
+}
+
+fn profile_name(profile: InstallProfile) -> String {
+    profile
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Unpacks every downloaded `(archive, sha256, url)` into staging and only
+/// then moves them into place and records them. Replacing existing installs
+/// is confirmed once for all.
+fn unpack_all(
+    ctx: &AppContext,
+    args: &InstallArgs,
+    downloads: &[(PathBuf, String, String)],
+) -> Result<(), Box<dyn Error>> {
+    let stems: Vec<String> = downloads
+        .iter()
+        .filter_map(|(archive, _, _)| archive_stem(archive))
+        .collect();
+    confirm_replace(ctx, &stems, args.force)?;
+
+    let staging = Staging::new(ctx);
+    let mut unpacked = Vec::new();
+    for (archive, digest, url) in downloads {
+        let mut install = unpack(ctx, args.target_confirm, archive, Some(digest), &staging)?;
+        install.url = url.clone();
+        unpacked.push(install);
+    }
+    staging.place(&unpacked)?;
+
+    for install in &unpacked {
+        record(ctx, install)?;
+    }
+    Ok(())
+}
+
+/// Fails unless replacing whichever of the installs `stems` already exist is
+/// forced or confirmed.
+fn confirm_replace(ctx: &AppContext, stems: &[String], force: bool) -> Result<(), Box<dyn Error>> {
+    let existing: Vec<&str> = stems
+        .iter()
+        .filter(|stem| versions_dir(ctx).join(stem).exists())
+        .map(String::as_str)
+        .collect();
+    if existing.is_empty() || force {
+        return Ok(());
+    }
+    let (question, error) = match existing.as_slice() {
+        [stem] => (
+            format!("{} is already installed. Replace it?", stem),
+            format!(
+                "{} is already installed (use --force or --assume-yes to replace it)",
+                stem
+            ),
+        ),
+        _ => (
+            format!("{} already installed. Replace?", existing.join(", ")),
+            format!(
+                "{} already installed (use --force or --assume-yes to replace)",
+                existing.join(", ")
+            ),
+        ),
+    };
+    if confirm(ctx, &question) {
+        Ok(())
+    } else {
+        Err(error.into())
+    }
+}
+
+/// A directory inside the versions directory that artifacts are unpacked
+/// into before any install is replaced. It is removed when dropped, and on
+/// Ctrl-C.
+struct Staging {
+    dir: PathBuf,
+    _partial: PartialFile,
+}
+
+impl Staging {
+    /// Staging for this run; the directory itself is created by the first
+    /// artifact unpacked into it.
+    fn new(ctx: &AppContext) -> Self {
+        // Hidden, so the registry never takes it for an install.
+        let dir = versions_dir(ctx).join(format!(".staging-{}", std::process::id()));
+        Self {
+            _partial: PartialFile::track(&dir),
+            dir,
+        }
+    }
+
+    /// Moves every unpacked artifact into place, replacing the install it
+    /// upgrades. If one can't be moved, those moved before it are undone.
+    fn place(&self, installs: &[Unpacked]) -> Result<(), Box<dyn Error>> {
+        let replaced = self.dir.join("replaced");
+        let mut placed: Vec<(&Path, Option<PathBuf>)> = Vec::new();
+        for install in installs {
+            match self.swap(install, &replaced) {
+                Ok(previous) => placed.push((&install.dir, previous)),
+                Err(e) => {
+                    for (dir, previous) in placed.into_iter().rev() {
+                        let _ = fs::remove_dir_all(long_path(dir));
+                        if let Some(previous) = previous {
+                            let _ = fs::rename(long_path(&previous), long_path(dir));
+                        }
+                    }
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves `install` into place, returning where the install it replaced
+    /// was put aside.
+    fn swap(&self, install: &Unpacked, replaced: &Path) -> std::io::Result<Option<PathBuf>> {
+        let previous = if install.dir.exists() {
+            let name = install.dir.file_name().unwrap_or_default();
+            let previous = replaced.join(name);
+            fs::create_dir_all(long_path(replaced))?;
+            fs::rename(long_path(&install.dir), long_path(&previous))?;
+            Some(previous)
+        } else {
+            None
+        };
+        if let Err(e) = fs::rename(long_path(&install.staged), long_path(&install.dir)) {
+            if let Some(previous) = &previous {
+                let _ = fs::rename(long_path(previous), long_path(&install.dir));
+            }
+            return Err(e);
+        }
+        Ok(previous)
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(long_path(&self.dir));
+    }
+}
+
+/// Where installed artifacts are unpacked, one directory per artifact under
+/// the data directory, e.g. `versions/php-8.3.12-cli-linux-x86_64`.
+pub fn versions_dir(ctx: &AppContext) -> PathBuf {
+    ctx.registry.dir().to_path_buf()
+}
+
+/// Verifies a manually obtained archive, unpacks it into the versions
+/// directory and records a receipt for it, as if it had been downloaded.
+fn install_from_file(
+    ctx: &AppContext,
+    args: &InstallArgs,
+    archive: &Path,
+    expect_sha256: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let stem = archive_stem(archive).ok_or_else(|| {
+        format!(
+            "{} is not a static-php-cli artifact name",
+            archive.file_name().unwrap_or_default().to_string_lossy()
+        )
+    })?;
+    confirm_replace(ctx, &[stem], args.force)?;
+
+    let staging = Staging::new(ctx);
+    let install = unpack(ctx, args.target_confirm, archive, expect_sha256, &staging)?;
+    staging.place(std::slice::from_ref(&install))?;
+    record(ctx, &install)
+}
+
+/// An artifact unpacked into staging, bound for `dir` in the versions
+/// directory, whose receipt is not recorded yet.
+struct Unpacked {
+    artifact: ArtifactName,
+    dir: PathBuf,
+    staged: PathBuf,
+    extracted: Vec<PathBuf>,
+    url: String,
+    sha256: String,
+}
+
+/// The directory name an archive is unpacked to: its name without extension.
+fn archive_stem(archive: &Path) -> Option<String> {
+    let file_name = archive.file_name()?.to_str()?;
+    let artifact = ArtifactName::parse(file_name)?;
+    let stem = file_name
+        .strip_suffix(&format!(".{}", artifact.extension))
+        .unwrap_or(file_name);
+    Some(stem.to_string())
+}
+
+/// Verifies `archive` and unpacks it into `staging`. Builds for another
+/// machine need `target_confirm`.
+fn unpack(
+    ctx: &AppContext,
+    target_confirm: bool,
+    archive: &Path,
+    expect_sha256: Option<&str>,
+    staging: &Staging,
+) -> Result<Unpacked, Box<dyn Error>> {
+    let file_name = archive
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("not a file")?;
+    let artifact = ArtifactName::parse(file_name)
+        .ok_or_else(|| format!("{} is not a static-php-cli artifact name", file_name))?;
+    if artifact.is_foreign() && !target_confirm {
+        return Err(foreign_install_error(&artifact).into());
+    }
+    notice_foreign(Some(&artifact));
+
+    let digest = sha256_file(archive)?;
+    println!("SHA-256: {}", digest);
+    // A checksum shipped alongside the archive is checked as well.
+    let sidecar = PathBuf::from(format!("{}.sha256", archive.display()));
+    let published = match fs::read(long_path(&sidecar)) {
+        Ok(contents) => Some(
+            parse_sha256(&contents)
+                .ok_or_else(|| format!("{} holds no SHA-256", sidecar.display()))?,
+        ),
+        Err(_) => None,
+    };
+    for (expected, source) in [
+        (expect_sha256, "--expect-sha256"),
+        (published.as_deref(), "the .sha256 file"),
+    ] {
+        if let Some(expected) = expected
+            && expected != digest
+        {
+            return Err(format!("SHA-256 mismatch: {} says {}", source, expected).into());
+        }
+    }
+    if expect_sha256.is_none() && published.is_none() {
+        println!("No checksum to verify against; pass --expect-sha256 to pin one.");
+    }
+
+    let stem = archive_stem(archive).ok_or("not an artifact")?;
+    let dir = versions_dir(ctx).join(&stem);
+    let staged = staging.dir.join(&stem);
+    let mut extracted = extract_archive(archive, &staged)?;
+    normalize_extracted(&mut extracted, &staged, Some(&artifact), None)?;
+    // Recorded where the files will be once the install is in place.
+    let extracted = extracted
+        .into_iter()
+        .map(|path| match path.strip_prefix(&staged) {
+            Ok(relative) => dir.join(relative),
+            Err(_) => path,
+        })
+        .collect();
+
+    let source = fs::canonicalize(archive)?;
+    Ok(Unpacked {
+        artifact,
+        dir,
+        staged,
+        extracted,
+        url: format!("file://{}", source.display()),
+        sha256: digest,
+    })
+}
+
+/// Records the receipt of an unpacked artifact, registers it as the active
+/// install of its build type, reports where it went and runs the
+/// `post_install` hook.
+fn record(ctx: &AppContext, install: &Unpacked) -> Result<(), Box<dyn Error>> {
+    let Unpacked {
+        artifact,
+        dir,
+        extracted,
+        url,
+        sha256,
+        ..
+    } = install;
+    ctx.receipts.record(&artifact.version, url, extracted)?;
+    let name = dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("not an install directory")?;
+    ctx.registry
+        .update(|installs| installs.add(name, artifact))?;
+
+    println!(
+        "Installed PHP {} ({}) to {}",
+        artifact.version,
+        artifact.build_type.as_deref().unwrap_or("unknown build"),
+        dir.display()
+    );
+    if let Some(binary) = artifact.binary_name()
+        && let Some(path) = extracted
+            .iter()
+            .find(|path| path.file_name().is_some_and(|name| name == binary))
+    {
+        println!("Binary: {}", path.display());
+    }
+
+    if let Some(hook) = &ctx.config.hooks.post_install {
+        let env = [
+            ("VERSION", artifact.version.to_string()),
+            ("PATH_TO_FILE", dir.display().to_string()),
+            ("SHA256", sha256.clone()),
+        ];
+        if !run_hook("post_install", hook, &env) {
+            return Err("the post_install hook failed".into());
+        }
+    }
+    Ok(())
+}
@@ -1,19 +1,156 @@
-use crate::{AppContext, cli::LatestArgs, spc::{Api, ApiOptions}};
+use std::collections::{BTreeMap, BTreeSet};
+
+use comfy_table::{Cell, ContentArrangement, Table, presets::UTF8_FULL};
+use semver::Version;
+
+use crate::{
+    AppContext,
+    cli::LatestArgs,
+    commands::{
+        notices::warn_eol,
+        selection::resolve_newest,
+        version_file::{VersionRecord, write_version_file},
+    },
+    spc::{Api, ApiOptions, ArtifactFilter, DEFAULT_STABLE_CHANNEL_DAYS, Resolution},
+};
 
 pub fn run(ctx: &AppContext, args: LatestArgs) {
     let options = ApiOptions::new(
         args.category,
-        args.version,
+        args.version.clone(),
         args.os,
         args.arch,
         args.build_type,
     );
-    let api = Api::new(ctx.cache.clone(), options).with_no_cache(args.no_cache);
-    let (latest_version, from_cache) = api.fetch_latest_version();
+    let stable_days = ctx
+        .config
+        .stable_channel_days
+        .unwrap_or(DEFAULT_STABLE_CHANNEL_DAYS);
+    // The stricter of the channel's and --as-of's cutoffs.
+    let cutoff = [args.channel.cutoff(stable_days), args.as_of]
+        .into_iter()
+        .flatten()
+        .min();
+    let api = ctx
+        .api(options)
+        .with_no_cache(args.no_cache)
+        .with_explain(args.explain)
+        .with_released_before(cutoff);
+
+    if args.matrix {
+        let filter = ArtifactFilter::only(None, None, None, args.version.clone())
+            .with_released_before(cutoff);
+        print_matrix(&api, &filter);
+        return;
+    }
+
+    let Resolution {
+        version: latest_version,
+        freshness,
+        ..
+    } = resolve_newest(&api);
 
-    if from_cache {
-        println!("Latest Version: {} (cached)", latest_version);
+    // Bare values, one per line, for feeding straight into other tools.
+    if args.url || args.name {
+        if args.with_version {
+            println!("{}", latest_version);
+        }
+        if args.name {
+            println!("{}", api.artifact_name(&latest_version));
+        }
+        if args.url {
+            println!("{}", api.download_url(&latest_version));
+        }
+    } else if args.quiet {
+        println!("{}", latest_version);
+        let annotation = freshness.annotation();
+        if !annotation.is_empty() {
+            eprintln!("Listing{}", annotation);
+        }
     } else {
-        println!("Latest Version: {}", latest_version);
+        println!(
+            "Latest Version: {}{}",
+            latest_version,
+            freshness.annotation()
+        );
+    }
+    warn_eol(&latest_version);
+
+    if let Some(path) = &args.write_version_file {
+        let url = api.download_url(&latest_version);
+        write_version_file(path, &VersionRecord::resolved(&latest_version, url));
+    }
+}
+
+/// Prints the newest version for every build type (rows) and platform
+/// (columns) in the category. Cells behind the newest version overall are
+/// starred, so lagging platforms stand out.
+fn print_matrix(api: &Api, filter: &ArtifactFilter) {
+    let (data, freshness) = api.fetch_versions().unwrap_or_else(|e| {
+        eprintln!("Failed to fetch listing: {}", e);
+        std::process::exit(1);
+    });
+
+    let mut cells: BTreeMap<(String, String), Version> = BTreeMap::new();
+    for artifact in data
+        .iter()
+        .filter(|resp| filter.matches(resp))
+        .filter_map(|resp| resp.artifact())
+    {
+        let platform = match (&artifact.os, &artifact.arch) {
+            (Some(os), Some(arch)) => format!("{}-{}", os, arch),
+            (Some(os), None) => os.clone(),
+            _ => continue,
+        };
+        let build_type = artifact.build_type.unwrap_or_default();
+        let newest = cells
+            .entry((build_type, platform))
+            .or_insert(artifact.version.clone());
+        if artifact.version > *newest {
+            *newest = artifact.version;
+        }
+    }
+
+    let Some(overall) = cells.values().max().cloned() else {
+        eprintln!("No artifacts match");
+        std::process::exit(1);
+    };
+
+    let build_types: BTreeSet<&String> = cells.keys().map(|(build_type, _)| build_type).collect();
+    let platforms: BTreeSet<&String> = cells.keys().map(|(_, platform)| platform).collect();
+
+    let mut header = vec![Cell::new("Build type")];
+    header.extend(platforms.iter().map(Cell::new));
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header);
+
+    let mut lagging = false;
+    for build_type in &build_types {
+        let mut row = vec![Cell::new(build_type)];
+        for platform in &platforms {
+            let text = match cells.get(&((*build_type).clone(), (*platform).clone())) {
+                None => "-".to_string(),
+                Some(version) if *version < overall => {
+                    lagging = true;
+                    format!("{}*", version)
+                }
+                Some(version) => version.to_string(),
+            };
+            row.push(Cell::new(text));
+        }
+        table.add_row(row);
+    }
+
+    println!("{table}");
+    if lagging {
+        println!("* behind {}", overall);
+    }
+    let annotation = freshness.annotation();
+    if !annotation.is_empty() {
+        println!("Listing{}", annotation);
     }
 }
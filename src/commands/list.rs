@@ -1,19 +1,25 @@
 use semver::Version;
 
-use crate::{AppContext, cli::ListArgs, spc::{Api, ApiOptions, BuildCategory}};
+use crate::{AppContext, cli::ListArgs, commands::{HumanFormat, OutputFormat, output::{print_csv, print_markdown, print_paged}, notices::warn_republished}, spc::{ApiOptions, ArtifactFilter, ArtifactName, BuildCategory, Freshness, SpcJsonResponse}};
 
 pub fn run(ctx: &AppContext, args: ListArgs) {
-	let options = ApiOptions::new(args.category, args.version, args.os, args.arch, args.build_type);
+	let options = ctx.apply_defaults(ApiOptions::new(args.category, args.version.clone(), args.os.clone(), args.arch.clone(), args.build_type));
 
-	let os_needle = options.os();
-	let arch_needle = options.arch();
 	let category = options.category();
-	let build_type_needle = options.build_type();
-	let version_bound = options.version_bound().cloned();
+	let filter = match args.artifact_type {
+		// The host's OS and arch say nothing about which zips or tarballs to
+		// show, so only what was asked for is matched.
+		Some(artifact_type) => {
+			let os = args.os.map(|os| if os == "windows" { "win".to_string() } else { os });
+			ArtifactFilter::only(os, args.arch, Some(options.build_type()), args.version).with_artifact_type(artifact_type)
+		}
+		None => ArtifactFilter::new(&options),
+	}
+	.with_released_before(args.as_of);
 
-	let api = Api::new(ctx.cache.clone(), options).with_no_cache(args.no_cache);
+	let api = ctx.api(options).with_no_cache(args.no_cache);
 
-	let (data, _) = match api.fetch_versions() {
+	let (data, freshness) = match api.fetch_versions() {
 		Ok(v) => v,
 		Err(e) => {
 			eprintln!("Failed to fetch versions: {}", e);
@@ -21,40 +27,152 @@ pub fn run(ctx: &AppContext, args: ListArgs) {
 		}
 	};
 
-	let mut versions: Vec<Version> = data
-		.into_iter()
-		.filter(|resp| {
-			let version_match = if let Some(v) = resp.version() {
-				if let Some(bound) = version_bound.as_ref() {
-					v.major == bound.major && v.minor == bound.minor
-				} else {
-					true
-				}
-			} else {
-				false
-			};
-
-			let name_match = match category {
-				BuildCategory::WinMin | BuildCategory::WinMax => {
-					resp.name.contains(&build_type_needle) && resp.name.ends_with("-win.zip")
-				}
-				_ => {
-					resp.name.contains(&os_needle)
-						&& resp.name.contains(&arch_needle)
-						&& resp.name.contains(&build_type_needle)
-				}
-			};
-
-			version_match && name_match
-		})
-		.filter_map(|resp| resp.version())
+	if let Freshness::Cached(..) = freshness {
+		eprintln!("Listing{}", freshness.annotation());
+	}
+
+	let matched: Vec<SpcJsonResponse> = data
+		.iter()
+		.filter(|resp| filter.matches(resp))
+		.cloned()
 		.collect();
 
-	versions.sort();
-	versions.dedup();
-	versions.sort_by(|a, b| b.cmp(a));
+	let republished =
+		api.report_republications(|name| matched.iter().any(|resp| resp.name == name));
+	warn_republished(&ctx.human, &republished);
+
+	let matched = if args.new {
+		match only_new(ctx, &category, &data, matched) {
+			Some(new) => new,
+			None => return,
+		}
+	} else {
+		matched
+	};
+
+	let format = if args.json { OutputFormat::Json } else { args.format };
+	let stable = args.stable_order || format == OutputFormat::Json;
+	match format {
+		OutputFormat::Json => {
+			println!("{}", artifacts_json(&matched, stable));
+			return;
+		}
+		OutputFormat::Csv => {
+			let headers = ["version", "build_type", "os", "arch", "size", "date", "downloads"];
+			print_csv(&headers, &artifact_rows(&matched, stable, None));
+			return;
+		}
+		OutputFormat::Markdown => {
+			let headers = ["Version", "Build type", "OS", "Arch", "Size", "Date", "Downloads"];
+			print_markdown(&headers, &artifact_rows(&matched, stable, Some(&ctx.human)));
+			return;
+		}
+		OutputFormat::Text => {}
+	}
+
+	let entries: Vec<String> = if args.new && stable {
+		sorted_artifacts(&matched, true).into_iter().map(|(_, resp)| resp.name.clone()).collect()
+	} else if args.new {
+		let mut names: Vec<String> = matched.iter().map(|resp| resp.name.clone()).collect();
+		names.sort();
+		names
+	} else {
+		let mut versions: Vec<Version> = matched.iter().filter_map(|resp| resp.version()).collect();
+
+		versions.sort();
+		versions.dedup();
+		if !stable {
+			versions.sort_by(|a, b| b.cmp(a));
+		}
+		versions.iter().map(Version::to_string).collect()
+	};
+
+	if args.print0 {
+		for entry in entries {
+			print!("{}\0", entry);
+		}
+		return;
+	}
+
+	let prefix = if args.new { "NEW " } else { "" };
+	let text: String = entries.iter().map(|entry| format!("{}{}\n", prefix, entry)).collect();
+	print_paged(&text, args.no_pager);
+}
+
+/// Keeps the artifacts missing from the snapshot taken by the previous
+/// `list --new`, then adds them to it. The snapshot holds the whole listing,
+/// not just what the filters matched, so a run with other filters doesn't
+/// take older artifacts for new ones. The first run only takes the snapshot.
+fn only_new(ctx: &AppContext, category: &BuildCategory, listing: &[SpcJsonResponse], matched: Vec<SpcJsonResponse>) -> Option<Vec<SpcJsonResponse>> {
+	let previous = ctx.cache.read_seen(category);
 
-	for v in versions {
-		println!("{}", v);
+	let mut seen = previous.clone().unwrap_or_default();
+	seen.extend(listing.iter().map(|resp| resp.name.clone()));
+	if let Err(e) = ctx.cache.write_seen(category, &seen) {
+		eprintln!("Warning: Failed to record seen artifacts: {}", e);
 	}
+
+	let Some(previous) = previous else {
+		eprintln!("Recorded {} artifacts; later runs of `list --new` will show what appears after this.", listing.len());
+		return None;
+	};
+
+	Some(matched.into_iter().filter(|resp| !previous.contains(&resp.name)).collect())
+}
+
+/// Matching artifacts with their parsed names, newest version first. The
+/// stable order is oldest version first, then build type, OS and arch, so new
+/// releases are appended and output diffs cleanly between runs.
+fn sorted_artifacts(matched: &[SpcJsonResponse], stable: bool) -> Vec<(ArtifactName, &SpcJsonResponse)> {
+	let mut artifacts: Vec<_> = matched
+		.iter()
+		.filter_map(|resp| resp.artifact().map(|artifact| (artifact, resp)))
+		.collect();
+	if stable {
+		artifacts.sort_by(|(a, x), (b, y)| {
+			(&a.version, &a.build_type, &a.os, &a.arch, &x.name).cmp(&(&b.version, &b.build_type, &b.os, &b.arch, &y.name))
+		});
+	} else {
+		artifacts.sort_by(|(a, x), (b, y)| b.version.cmp(&a.version).then_with(|| x.name.cmp(&y.name)));
+	}
+	artifacts
+}
+
+/// Every field of the listing plus what the file name says about the build.
+fn artifacts_json(matched: &[SpcJsonResponse], stable: bool) -> serde_json::Value {
+	sorted_artifacts(matched, stable)
+		.into_iter()
+		.map(|(artifact, resp)| {
+			serde_json::json!({
+				"name": resp.name,
+				"full_path": resp.full_path(),
+				"size": resp.size().parse::<u64>().ok(),
+				"last_modified": resp.last_modified().to_rfc3339(),
+				"download_count": resp.download_count(),
+				"version": artifact.version.to_string(),
+				"build_type": artifact.build_type,
+				"os": artifact.os,
+				"arch": artifact.arch,
+			})
+		})
+		.collect()
+}
+
+/// One row per matching artifact. With `human`, sizes and download counts are
+/// grouped for reading; without, they stay raw for machines.
+fn artifact_rows(matched: &[SpcJsonResponse], stable: bool, human: Option<&HumanFormat>) -> Vec<Vec<String>> {
+	sorted_artifacts(matched, stable)
+		.into_iter()
+		.map(|(artifact, resp)| {
+			vec![
+				artifact.version.to_string(),
+				artifact.build_type.unwrap_or_default(),
+				artifact.os.unwrap_or_default(),
+				artifact.arch.unwrap_or_default(),
+				human.map_or_else(|| resp.size().to_string(), |human| human.size(resp.size())),
+				resp.last_modified().format("%Y-%m-%d %H:%M:%S").to_string(),
+				human.map_or_else(|| resp.download_count().to_string(), |human| human.count(resp.download_count().into())),
+			]
+		})
+		.collect()
 }
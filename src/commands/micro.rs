@@ -0,0 +1,99 @@
+use std::{error::Error, fs, path::Path};
+
+use clap::Subcommand;
+
+use crate::{
+    AppContext,
+    cli::MicroFetchArgs,
+    commands::{archive::extract_file, notices::warn_eol, selection::resolve_newest},
+    platform::long_path,
+    spc::{ApiOptions, VersionSpec},
+};
+
+const MICRO_SFX: &str = "micro.sfx";
+
+#[derive(Clone, Subcommand)]
+pub enum MicroAction {
+    #[command(about = "Save the micro.sfx of a micro build, ready to prepend to a phar")]
+    Fetch(MicroFetchArgs),
+}
+
+pub fn run(ctx: &AppContext, action: MicroAction) {
+    match action {
+        MicroAction::Fetch(args) => {
+            if let Err(e) = fetch(ctx, &args) {
+                eprintln!("Failed to fetch {}: {}", MICRO_SFX, e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn fetch(ctx: &AppContext, args: &MicroFetchArgs) -> Result<(), Box<dyn Error>> {
+    let options = |version: Option<VersionSpec>| {
+        ApiOptions::new(
+            args.category.clone(),
+            version,
+            args.os.clone(),
+            args.arch.clone(),
+            Some("micro".to_string()),
+        )
+    };
+
+    let version = match args.version.as_ref().and_then(VersionSpec::exact) {
+        Some(version) => version.clone(),
+        None => {
+            resolve_newest(
+                &ctx.api(options(args.version.clone()))
+                    .with_no_cache(args.no_cache),
+            )
+            .version
+        }
+    };
+    warn_eol(&version);
+
+    let options = options(Some(version.clone().into()));
+    let category = options.category();
+    let api = ctx.api(options).with_no_cache(args.no_cache);
+    let name = api.artifact_name(&version);
+
+    if ctx.cache.is_in_memory() {
+        // Nowhere to keep it for next time, so extract it straight to the output.
+        let dir = match args.output.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let archive = dir.join(&name);
+        let extracted = api
+            .download(&archive.to_string_lossy())
+            .and_then(|_| extract_file(&archive, MICRO_SFX, &args.output));
+        let _ = fs::remove_file(&archive);
+        extracted?;
+    } else {
+        // Extracted once per artifact, i.e. per version and platform.
+        let cached = ctx.cache.micro_sfx_path(&category, &name);
+        if cached.exists() && !args.no_cache {
+            println!("Using cached {}: {}", MICRO_SFX, cached.display());
+        } else {
+            let dir = cached.parent().expect("cached micro.sfx has a directory");
+            fs::create_dir_all(dir)?;
+
+            let archive = dir.join(&name);
+            let extracted = api
+                .download(&archive.to_string_lossy())
+                .and_then(|_| extract_file(&archive, MICRO_SFX, &cached));
+            let _ = fs::remove_file(&archive);
+            extracted?;
+        }
+
+        fs::copy(&cached, long_path(&args.output))
+            .map_err(|e| format!("{}: {}", args.output.display(), e))?;
+    }
+    println!(
+        "Saved {} from {} to {}",
+        MICRO_SFX,
+        name,
+        args.output.display()
+    );
+    Ok(())
+}
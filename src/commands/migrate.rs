@@ -0,0 +1,269 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    AppContext, config::PROJECT_CONFIG_FILE, default_data_dir, receipts::Receipts, spc::Cache,
+};
+
+pub fn run(ctx: &AppContext) {
+    // Where state lives when --data-dir/--cache-dir aren't given.
+    let from_data = env_dir("SPC_UTILS_DATA_DIR")
+        .or_else(|| ctx.config.data_dir.clone())
+        .unwrap_or_else(default_data_dir);
+    let from_cache = env_dir("SPC_UTILS_CACHE_DIR")
+        .or_else(|| ctx.config.cache_dir.clone())
+        .unwrap_or_else(Cache::default_dir);
+
+    let to_data = ctx.data_dir.clone();
+    let to_cache = ctx.cache.cache_dir().clone();
+
+    if from_data == to_data && from_cache == to_cache {
+        eprintln!("error: pass --data-dir and/or --cache-dir with the new locations");
+        std::process::exit(2);
+    }
+
+    let mut failed = false;
+
+    if from_data != to_data {
+        match move_dir(&from_data, &to_data, false) {
+            Ok(false) => println!("No data directory at {}", from_data.display()),
+            Ok(true) => {
+                println!(
+                    "Moved data directory: {} -> {}",
+                    from_data.display(),
+                    to_data.display()
+                );
+                if let Err(e) = relink(&from_data, &to_data) {
+                    eprintln!("Failed to rewrite links into the data directory: {}", e);
+                    failed = true;
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to move data directory: {}", e);
+                failed = true;
+            }
+        }
+    }
+
+    if from_cache != to_cache {
+        // Anything already at the destination is only cache and can be replaced.
+        match move_dir(&from_cache, &to_cache, true) {
+            Ok(false) => println!("No cache directory at {}", from_cache.display()),
+            Ok(true) => println!(
+                "Moved cache directory: {} -> {}",
+                from_cache.display(),
+                to_cache.display()
+            ),
+            Err(e) => {
+                eprintln!("Failed to move cache directory: {}", e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+
+    let mut changes = Vec::new();
+    if from_data != to_data {
+        changes.push((
+            "data_dir",
+            "SPC_UTILS_DATA_DIR",
+            &to_data,
+            default_data_dir(),
+        ));
+    }
+    if from_cache != to_cache {
+        changes.push((
+            "cache_dir",
+            "SPC_UTILS_CACHE_DIR",
+            &to_cache,
+            Cache::default_dir(),
+        ));
+    }
+
+    let mut config = fs::read_to_string(PROJECT_CONFIG_FILE).ok();
+    let mut config_changed = false;
+    for (key, env, dir, default) in changes {
+        if let Some(updated) = config.as_deref().and_then(|c| set_config_path(c, key, dir)) {
+            config = Some(updated);
+            config_changed = true;
+            println!("Updated {} in {}", key, PROJECT_CONFIG_FILE);
+        } else if *dir != default {
+            println!(
+                "Later runs need --{} {}, {} or `{}` in {} to find it",
+                key.replace('_', "-"),
+                dir.display(),
+                env,
+                key,
+                PROJECT_CONFIG_FILE
+            );
+        }
+    }
+
+    if let Some(config) = config
+        && config_changed
+        && let Err(e) = fs::write(PROJECT_CONFIG_FILE, config)
+    {
+        eprintln!("Failed to update {}: {}", PROJECT_CONFIG_FILE, e);
+        std::process::exit(1);
+    }
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Moves everything in `from` into `to`, then removes `from`. Entries that
+/// already exist in `to` are replaced when `overwrite` is set and abort the
+/// move otherwise, before anything is touched. Returns whether there was
+/// anything to move.
+fn move_dir(from: &Path, to: &Path, overwrite: bool) -> io::Result<bool> {
+    if !from.exists() {
+        return Ok(false);
+    }
+
+    fs::create_dir_all(to)?;
+    let entries = fs::read_dir(from)?.collect::<Result<Vec<_>, _>>()?;
+
+    if !overwrite
+        && let Some(entry) = entries
+            .iter()
+            .find(|entry| fs::symlink_metadata(to.join(entry.file_name())).is_ok())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.join(entry.file_name()).display()),
+        ));
+    }
+
+    for entry in entries {
+        let target = to.join(entry.file_name());
+        if fs::symlink_metadata(&target).is_ok() {
+            remove_entry(&target)?;
+        }
+        if fs::rename(entry.path(), &target).is_err() {
+            // Across filesystems: copy, then remove the original.
+            copy_entry(&entry.path(), &target)?;
+            remove_entry(&entry.path())?;
+        }
+    }
+
+    fs::remove_dir(from)?;
+    Ok(true)
+}
+
+fn remove_entry(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn copy_entry(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
+    if metadata.is_symlink() {
+        return symlink(&fs::read_link(from)?, to);
+    }
+
+    if metadata.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+
+    fs::copy(from, to).map(|_| ())
+}
+
+/// Points symlinks and receipt entries that referred to files under `from`
+/// at their new location under `to`.
+fn relink(from: &Path, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    relink_dir(to, from, to)?;
+
+    let receipts = Receipts::new(to);
+    for mut receipt in receipts.load_all()? {
+        let mut changed = false;
+        for file in &mut receipt.files {
+            if let Ok(rest) = file.path.strip_prefix(from) {
+                file.path = to.join(rest);
+                changed = true;
+            }
+        }
+        if changed {
+            receipts.save(&receipt)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn relink_dir(dir: &Path, from: &Path, to: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let metadata = fs::symlink_metadata(&path)?;
+
+        if metadata.is_symlink() {
+            let target = fs::read_link(&path)?;
+            if let Ok(rest) = target.strip_prefix(from) {
+                fs::remove_file(&path)?;
+                symlink(&to.join(rest), &path)?;
+            }
+        } else if metadata.is_dir() {
+            relink_dir(&path, from, to)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+/// Rewrites a top-level `key = "..."` line of the project config to `dir`,
+/// leaving the rest of the file untouched. `None` when the key isn't set.
+fn set_config_path(contents: &str, key: &str, dir: &Path) -> Option<String> {
+    let mut found = false;
+    let mut top_level = true;
+
+    let lines: Vec<String> = contents
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('[') {
+                top_level = false;
+            }
+
+            let sets_key = trimmed
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='));
+            if top_level && !found && sets_key {
+                found = true;
+                let value = toml::Value::String(dir.display().to_string());
+                return format!("{} = {}", key, value);
+            }
+            line.to_string()
+        })
+        .collect();
+
+    found.then(|| lines.join("\n") + "\n")
+}
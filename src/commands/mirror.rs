@@ -0,0 +1,189 @@
+use std::{fs, path::Path};
+
+use clap::Subcommand;
+use comfy_table::{Cell, ContentArrangement, Table, presets::UTF8_FULL};
+use toml_edit::{Array, DocumentMut};
+
+use crate::{
+    AppContext,
+    commands::selection::resolve_newest,
+    config::PROJECT_CONFIG_FILE,
+    spc::{self, ApiOptions, BuildCategory, Probe},
+};
+
+/// Bytes read from each mirror when none are requested.
+const DEFAULT_PROBE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Clone, Subcommand)]
+pub enum MirrorAction {
+    #[command(about = "Measure latency and throughput to each mirror")]
+    Bench {
+        #[arg(
+            value_name = "MIRROR",
+            help = "Mirrors to compare (defaults to the mirrors in .spc-utils.toml)"
+        )]
+        mirrors: Vec<String>,
+        #[arg(
+            short = 'C',
+            long,
+            value_enum,
+            help = "Category whose newest artifact is fetched"
+        )]
+        category: Option<BuildCategory>,
+        #[arg(short = 'O', value_parser = spc::SPC_OS_OPTIONS, help = "OS of the fetched artifact")]
+        os: Option<String>,
+        #[arg(short = 'A', long, value_parser = spc::SPC_ARCH_OPTIONS, help = "Architecture of the fetched artifact")]
+        arch: Option<String>,
+        #[arg(
+            long,
+            default_value_t = DEFAULT_PROBE_BYTES,
+            help = "Bytes to fetch from each mirror"
+        )]
+        bytes: u64,
+        #[arg(long, help = "Reorder the mirrors in .spc-utils.toml, fastest first")]
+        write: bool,
+    },
+}
+
+pub fn run(ctx: &AppContext, action: MirrorAction) {
+    match action {
+        MirrorAction::Bench {
+            mirrors,
+            category,
+            os,
+            arch,
+            bytes,
+            write,
+        } => {
+            let mirrors = if mirrors.is_empty() {
+                ctx.config.mirrors.clone()
+            } else {
+                mirrors
+            };
+            if mirrors.is_empty() {
+                eprintln!(
+                    "No mirrors to compare: pass them as arguments or list them under `mirrors` in {}",
+                    PROJECT_CONFIG_FILE
+                );
+                std::process::exit(2);
+            }
+
+            let options =
+                || ApiOptions::new(category.clone(), None, os.clone(), arch.clone(), None);
+            let results = bench(ctx, options, &mirrors, bytes);
+            print_results(&results);
+
+            if write {
+                let ranked = rank(&results);
+                if let Err(e) = write_mirrors(Path::new(PROJECT_CONFIG_FILE), &ranked) {
+                    eprintln!("Failed to update {}: {}", PROJECT_CONFIG_FILE, e);
+                    std::process::exit(1);
+                }
+                println!("Reordered mirrors in {}", PROJECT_CONFIG_FILE);
+            }
+
+            if results.iter().all(|(_, result)| result.is_err()) {
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+type BenchResult = (String, Result<Probe, String>);
+
+/// Fetches the start of the same artifact from every mirror. The artifact is
+/// the newest one listed by the first mirror that answers.
+fn bench(
+    ctx: &AppContext,
+    options: impl Fn() -> ApiOptions,
+    mirrors: &[String],
+    bytes: u64,
+) -> Vec<BenchResult> {
+    let Some(api) = mirrors
+        .iter()
+        .map(|mirror| ctx.api(options()).with_base_url(mirror))
+        .find(|api| api.fetch_versions().is_ok())
+    else {
+        eprintln!("None of the mirrors serve a listing");
+        std::process::exit(1);
+    };
+    let version = resolve_newest(&api).version;
+    println!(
+        "Fetching {} bytes of {} from each mirror",
+        bytes,
+        api.artifact_name(&version)
+    );
+
+    mirrors
+        .iter()
+        .map(|mirror| {
+            let api = ctx.api(options()).with_base_url(mirror);
+            let result = api
+                .probe(&api.download_url(&version), bytes)
+                .map_err(|e| e.to_string());
+            (mirror.clone(), result)
+        })
+        .collect()
+}
+
+fn print_results(results: &[BenchResult]) {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("Mirror"),
+            Cell::new("Latency"),
+            Cell::new("Throughput"),
+        ]);
+
+    for (mirror, result) in results {
+        match result {
+            Ok(probe) => table.add_row(vec![
+                Cell::new(mirror),
+                Cell::new(format!("{} ms", probe.latency.as_millis())),
+                Cell::new(format!(
+                    "{:.1} MB/s",
+                    probe.throughput() / (1024.0 * 1024.0)
+                )),
+            ]),
+            Err(e) => table.add_row(vec![
+                Cell::new(mirror),
+                Cell::new("-"),
+                Cell::new(format!("failed: {}", e)),
+            ]),
+        };
+    }
+
+    println!("{table}");
+}
+
+/// Fastest first; mirrors that failed keep their relative order at the end.
+fn rank(results: &[BenchResult]) -> Vec<String> {
+    let mut reached: Vec<(&String, f64)> = results
+        .iter()
+        .filter_map(|(mirror, result)| Some((mirror, result.as_ref().ok()?.throughput())))
+        .collect();
+    reached.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let failed = results.iter().filter(|(_, result)| result.is_err());
+    reached
+        .into_iter()
+        .map(|(mirror, _)| mirror.clone())
+        .chain(failed.map(|(mirror, _)| mirror.clone()))
+        .collect()
+}
+
+/// Replaces `mirrors` in the project config, keeping the rest of the file
+/// (comments and formatting included) as it is.
+fn write_mirrors(path: &Path, mirrors: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut document: DocumentMut = contents.parse()?;
+    document["mirrors"] = toml_edit::value(mirrors.iter().collect::<Array>());
+    fs::write(path, document.to_string())?;
+    Ok(())
+}
@@ -1,8 +1,41 @@
+pub mod api;
+pub mod archive;
 pub mod cache;
+pub mod categories;
 pub mod check_update;
+pub mod compare;
+pub mod doctor;
 pub mod download;
 pub mod examples;
+pub mod export_manifest;
+pub mod fetch_matrix;
+pub mod fpm;
+pub mod gc;
+pub mod history;
+pub mod hooks;
+pub mod install;
 pub mod latest;
 pub mod list;
+pub mod micro;
+pub mod migrate;
+pub mod mirror;
+pub mod notices;
+pub mod outdated;
+pub mod output;
+pub mod parse;
+pub mod pinned;
+pub mod prompt;
+pub mod resolve;
+pub mod selection;
+pub mod serve;
+pub mod sync;
+pub mod url;
+pub mod verify_install;
+pub mod version;
+pub mod version_file;
 
 pub use cache::CacheAction;
+pub use history::HistoryAction;
+pub use micro::MicroAction;
+pub use mirror::MirrorAction;
+pub use output::{HumanFormat, OutputFormat};
@@ -0,0 +1,70 @@
+use chrono::Local;
+use semver::Version;
+
+use crate::{
+    commands::HumanFormat,
+    spc::{ArtifactName, Republication, eol_date, is_supported},
+};
+
+/// Warns when `version`'s minor is past the end of its security support.
+pub(crate) fn warn_eol(version: &Version) {
+    if is_supported(version, Local::now().date_naive()) == Some(false)
+        && let Some(eol) = eol_date(version)
+    {
+        eprintln!(
+            "WARNING: PHP {}.{} reached end of life on {} and no longer receives security fixes.",
+            version.major, version.minor, eol
+        );
+    }
+}
+
+pub(crate) fn warn_republished(human: &HumanFormat, republished: &[Republication]) {
+    for record in republished {
+        eprintln!(
+            "WARNING: {} was re-published upstream with different metadata!",
+            record.name
+        );
+        eprintln!(
+            "  size: {} -> {}, last modified: {} -> {} (detected {})",
+            human.size(&record.previous_size),
+            human.size(&record.current_size),
+            record.previous_modified.format("%Y-%m-%d %H:%M:%S"),
+            record.current_modified.format("%Y-%m-%d %H:%M:%S"),
+            human.time(&record.detected_at, "%Y-%m-%d %H:%M")
+        );
+        if let Some(sha256) = &record.previous_sha256 {
+            eprintln!("  the copy cached before had SHA-256 {}", sha256);
+        }
+        eprintln!("  Verify this artifact through an independent channel before trusting it.");
+    }
+}
+
+/// Prints a prominent notice when `artifact` is built for another machine,
+/// and returns whether it is, so steps that only make sense on the machine
+/// running the binary can be skipped.
+pub(crate) fn notice_foreign(artifact: Option<&ArtifactName>) -> bool {
+    let Some(artifact) = artifact.filter(|artifact| artifact.is_foreign()) else {
+        return false;
+    };
+    eprintln!(
+        "Note: this is a {} build, but this machine is {}-{}.",
+        artifact.platform(),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    eprintln!(
+        "Note: it is fetched for another machine; nothing is made executable or set up here."
+    );
+    true
+}
+
+/// The error for installing `artifact` on a machine it wasn't built for
+/// without `--target-confirm`.
+pub(crate) fn foreign_install_error(artifact: &ArtifactName) -> String {
+    format!(
+        "{} builds don't run on this {}-{} machine (pass --target-confirm to install it anyway)",
+        artifact.platform(),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
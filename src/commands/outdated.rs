@@ -0,0 +1,145 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use comfy_table::{Cell, ContentArrangement, Table, presets::UTF8_FULL};
+use semver::Version;
+
+use crate::{
+    AppContext,
+    cli::{OutdatedArgs, validate_version},
+    spc::ApiOptions,
+};
+
+const SKIPPED_DIRS: [&str; 4] = [".git", "node_modules", "target", "vendor"];
+
+struct ProjectPin {
+    dir: PathBuf,
+    source: &'static str,
+    version: Version,
+}
+
+pub fn run(ctx: &AppContext, args: OutdatedArgs) {
+    let pins = match &args.workspace {
+        Some(root) => {
+            let mut pins = Vec::new();
+            scan(root, &mut pins);
+            pins
+        }
+        None => read_pin(Path::new(".")).into_iter().collect(),
+    };
+
+    if pins.is_empty() {
+        println!("No .spc-version or composer.json platform pins found.");
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("Project"),
+            Cell::new("Source"),
+            Cell::new("Pinned"),
+            Cell::new("Latest"),
+            Cell::new("Status"),
+        ]);
+
+    let root = args.workspace.as_deref().unwrap_or(Path::new("."));
+    for pin in &pins {
+        let options = ApiOptions::new(
+            args.category.clone(),
+            Some(pin.version.clone().into()),
+            None,
+            None,
+            None,
+        );
+        let api = ctx.api(options).with_no_cache(args.no_cache);
+        // One pin without a matching build shouldn't sink the whole report.
+        let (latest, status) = match api.resolve() {
+            Ok(resolution) if resolution.version > pin.version => {
+                (resolution.version.to_string(), "outdated".to_string())
+            }
+            Ok(resolution) => (resolution.version.to_string(), "up to date".to_string()),
+            Err(e) => ("-".to_string(), format!("unresolved: {}", e)),
+        };
+        let project = pin.dir.strip_prefix(root).unwrap_or(&pin.dir);
+        let project = if project.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            project.display().to_string()
+        };
+
+        table.add_row(vec![
+            Cell::new(project),
+            Cell::new(pin.source),
+            Cell::new(pin.version.to_string()),
+            Cell::new(latest),
+            Cell::new(status),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+fn scan(dir: &Path, pins: &mut Vec<ProjectPin>) {
+    if let Some(pin) = read_pin(dir) {
+        pins.push(pin);
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut children: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|entry| {
+            let name = entry.file_name();
+            !SKIPPED_DIRS.contains(&name.to_string_lossy().as_ref())
+        })
+        .map(|entry| entry.path())
+        .collect();
+    children.sort();
+
+    for child in children {
+        scan(&child, pins);
+    }
+}
+
+/// A `.spc-version` file wins over composer.json's `config.platform.php`.
+fn read_pin(dir: &Path) -> Option<ProjectPin> {
+    if let Ok(contents) = fs::read_to_string(dir.join(".spc-version"))
+        && let Some(line) = contents.lines().map(str::trim).find(|l| !l.is_empty())
+    {
+        return match validate_version(line) {
+            Ok(version) => Some(ProjectPin {
+                dir: dir.to_path_buf(),
+                source: ".spc-version",
+                version,
+            }),
+            Err(e) => {
+                eprintln!("Skipping {}: {}", dir.join(".spc-version").display(), e);
+                None
+            }
+        };
+    }
+
+    let contents = fs::read_to_string(dir.join("composer.json")).ok()?;
+    let composer: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let pinned = composer.pointer("/config/platform/php")?.as_str()?;
+
+    match validate_version(pinned) {
+        Ok(version) => Some(ProjectPin {
+            dir: dir.to_path_buf(),
+            source: "composer.json",
+            version,
+        }),
+        Err(e) => {
+            eprintln!("Skipping {}: {}", dir.join("composer.json").display(), e);
+            None
+        }
+    }
+}
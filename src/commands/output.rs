@@ -0,0 +1,173 @@
+use std::{
+    env,
+    io::{IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+
+/// How commands that print tabular data render it.
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable output
+    #[default]
+    Text,
+    /// RFC 4180 CSV with a header row
+    Csv,
+    /// A GitHub-flavoured markdown table
+    Markdown,
+    /// A JSON array with one object per entry
+    Json,
+}
+
+/// How timestamps and counts are shown to people. Machine-readable output
+/// (JSON, CSV) keeps raw values.
+#[derive(Clone, Copy, Default)]
+pub struct HumanFormat {
+    utc: bool,
+    separator: Option<char>,
+}
+
+impl HumanFormat {
+    /// Thousands separators follow the locale in `LC_ALL`, `LC_NUMERIC` or
+    /// `LANG`; timestamps are local unless `utc`.
+    pub fn from_env(utc: bool) -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self {
+            utc,
+            separator: thousands_separator(&locale),
+        }
+    }
+
+    /// A timestamp spc-utils recorded itself, in local time or, with
+    /// `--utc`, in UTC. Listing times are UTC as served and aren't passed here.
+    pub fn time(&self, at: &DateTime<Local>, format: &str) -> String {
+        if self.utc {
+            at.with_timezone(&Utc).format(format).to_string()
+        } else {
+            at.format(format).to_string()
+        }
+    }
+
+    /// `1234567` as `1,234,567`, `1.234.567` or `1 234 567`, per the locale.
+    pub fn count(&self, n: u64) -> String {
+        let digits = n.to_string();
+        let Some(separator) = self.separator else {
+            return digits;
+        };
+
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// A size from the listing, grouped like [`count`](Self::count) when it
+    /// is a plain number of bytes.
+    pub fn size(&self, size: &str) -> String {
+        size.parse()
+            .map_or_else(|_| size.to_string(), |n| self.count(n))
+    }
+}
+
+/// The digit group separator for a POSIX locale name such as `de_DE.UTF-8`.
+/// `C`, `POSIX` and unknown languages don't group digits.
+fn thousands_separator(locale: &str) -> Option<char> {
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+    match language {
+        "en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" => Some(','),
+        "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" => Some('.'),
+        "fr" | "ru" | "pl" | "cs" | "sv" | "fi" | "nb" | "uk" | "hu" | "sk" => Some('\u{a0}'),
+        _ => None,
+    }
+}
+
+/// Prints an RFC 4180 CSV document: CRLF line endings, with fields quoted
+/// only when they contain a delimiter, quote, or line break.
+pub fn print_csv(headers: &[&str], rows: &[Vec<String>]) {
+    print!("{}\r\n", csv_record(headers.iter().copied()));
+    for row in rows {
+        print!("{}\r\n", csv_record(row.iter().map(String::as_str)));
+    }
+}
+
+fn csv_record<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    fields.map(csv_field).collect::<Vec<_>>().join(",")
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Prints a GitHub-flavoured markdown table, escaping pipes inside cells.
+pub fn print_markdown(headers: &[&str], rows: &[Vec<String>]) {
+    println!("{}", markdown_row(headers.iter().copied()));
+    println!("{}", markdown_row(headers.iter().map(|_| "---")));
+    for row in rows {
+        println!("{}", markdown_row(row.iter().map(String::as_str)));
+    }
+}
+
+fn markdown_row<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    let cells: Vec<String> = cells
+        .map(|cell| cell.replace('|', "\\|").replace('\n', " "))
+        .collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+/// Prints `text`, through `$PAGER` (or `less`) when stdout is a terminal
+/// that it doesn't fit on, the way git does.
+pub fn print_paged(text: &str, no_pager: bool) {
+    let fits = crossterm::terminal::size()
+        .map(|(_, rows)| text.lines().count() < usize::from(rows))
+        .unwrap_or(true);
+
+    if no_pager || fits || !std::io::stdout().is_terminal() || !page(text) {
+        print!("{}", text);
+    }
+}
+
+/// Returns false when no pager could be started.
+fn page(text: &str) -> bool {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "more" } else { "less" }.to_string());
+
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", &pager]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", &pager]);
+        shell
+    };
+    if env::var_os("LESS").is_none() {
+        // Quit if one screen, keep colours, don't clear the screen on exit.
+        shell.env("LESS", "FRX");
+    }
+
+    let Ok(mut child) = shell.stdin(Stdio::piped()).spawn() else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user quitting the pager early closes the pipe; that's fine.
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let _ = child.wait();
+    true
+}
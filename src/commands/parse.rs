@@ -0,0 +1,37 @@
+use crate::{cli::ParseArgs, spc::ArtifactName};
+
+pub fn run(args: ParseArgs) {
+    let Some(artifact) = ArtifactName::parse(&args.name) else {
+        eprintln!("Not a recognised artifact file name: {}", args.name);
+        std::process::exit(1);
+    };
+
+    let categories: Vec<String> = artifact
+        .candidate_categories()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+
+    if args.json {
+        let json = serde_json::json!({
+            "name": args.name,
+            "version": artifact.version.to_string(),
+            "build_type": artifact.build_type,
+            "os": artifact.os,
+            "arch": artifact.arch,
+            "extension": artifact.extension,
+            "categories": categories,
+        });
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        return;
+    }
+
+    let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    println!("name:       {}", args.name);
+    println!("version:    {}", artifact.version);
+    println!("build type: {}", or_dash(&artifact.build_type));
+    println!("os:         {}", or_dash(&artifact.os));
+    println!("arch:       {}", or_dash(&artifact.arch));
+    println!("extension:  {}", artifact.extension);
+    println!("categories: {}", categories.join(", "));
+}
@@ -0,0 +1,40 @@
+use std::{error::Error, path::Path};
+
+use crate::{
+    AppContext,
+    manifest::ManifestArtifact,
+    platform::long_path,
+    spc::{Api, ApiOptions},
+};
+
+/// Downloads a manifest's `artifact` from its recorded URL to `output` and
+/// checks it against the recorded SHA-256. A mismatching file is removed.
+pub(crate) fn fetch_pinned(
+    api: &Api,
+    artifact: &ManifestArtifact,
+    output: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let digest = api.download_url_to(&artifact.url, output)?;
+    if digest != artifact.sha256 {
+        let _ = std::fs::remove_file(long_path(output));
+        return Err(format!(
+            "SHA-256 mismatch for {}: the manifest says {}, got {}. Removed it.",
+            artifact.name, artifact.sha256, digest
+        )
+        .into());
+    }
+    println!("SHA-256: {} (matches the manifest)", digest);
+    Ok(())
+}
+
+/// An API client for replaying `artifact`, e.g. to share its retry and
+/// progress settings with regular downloads.
+pub(crate) fn pinned_api(ctx: &AppContext, artifact: &ManifestArtifact) -> Api {
+    ctx.api(ApiOptions::new(
+        Some(artifact.category.clone()),
+        Some(artifact.version.clone().into()),
+        None,
+        None,
+        None,
+    ))
+}
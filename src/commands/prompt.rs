@@ -0,0 +1,29 @@
+use std::io::{BufRead, IsTerminal, Write};
+
+use crate::AppContext;
+
+/// Whether there is someone at a terminal to answer questions.
+pub(crate) fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Asks a yes/no question on the terminal. With `--assume-yes` the answer is
+/// yes; without a terminal to ask on, it is no.
+pub(crate) fn confirm(ctx: &AppContext, question: &str) -> bool {
+    if ctx.assume_yes {
+        return true;
+    }
+    if !is_interactive() {
+        return false;
+    }
+
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
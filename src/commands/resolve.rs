@@ -0,0 +1,60 @@
+use std::env::consts::OS;
+
+use crate::{
+    AppContext, cli::ResolveArgs, commands::selection::resolve_newest, requirements::Requirements,
+    spc::ApiOptions,
+};
+
+pub fn run(ctx: &AppContext, args: ResolveArgs) {
+    let requirements = Requirements::collect(args.requirements.as_deref(), &args.require_ext)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+
+    let os = args.os.clone().unwrap_or_else(|| OS.to_string());
+    let profile = requirements.smallest_category(&os).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let options = ApiOptions::new(
+        Some(profile.category.clone()),
+        args.version.clone().or_else(|| requirements.version_spec()),
+        args.os,
+        args.arch,
+        args.build_type,
+    );
+    let api = ctx.api(options).with_no_cache(args.no_cache);
+    let resolution = resolve_newest(&api);
+
+    if args.json {
+        let defaults: serde_json::Map<String, serde_json::Value> = resolution
+            .defaults
+            .iter()
+            .map(|(field, value)| (field.to_string(), value.clone().into()))
+            .collect();
+        let json = serde_json::json!({
+            "category": profile.category.to_string(),
+            "version": resolution.version.to_string(),
+            "name": resolution.artifact,
+            "url": resolution.url,
+            "candidates": resolution.candidates,
+            "defaults": defaults,
+        });
+        println!("{}", json);
+        return;
+    }
+
+    println!("Category: {}", profile.category);
+    println!(
+        "Version:  {}{}",
+        resolution.version,
+        resolution.freshness.annotation()
+    );
+    println!(
+        "Artifact: {} (newest of {} matching builds)",
+        resolution.artifact, resolution.candidates
+    );
+    println!("  {}", resolution.url);
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeSet;
+
+use semver::Version;
+
+use crate::{
+    AppContext,
+    config::Target,
+    spc::{Api, ApiOptions, ArtifactFilter, BuildCategory, Resolution},
+};
+
+/// Resolves the newest build `api` selects, exiting when the listing can't be
+/// fetched or has no such build.
+pub(crate) fn resolve_newest(api: &Api) -> Resolution {
+    api.resolve().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// The newest version in `category`'s listing that every one of `selections`
+/// (a label and the options selecting it) has a build of, so that builds
+/// fetched together stay on one release. Exits naming the selections without
+/// any build, or when they share no version.
+pub(crate) fn newest_common_version(
+    ctx: &AppContext,
+    category: &BuildCategory,
+    no_cache: bool,
+    selections: &[(String, ApiOptions)],
+) -> Version {
+    let api = ctx
+        .api(ApiOptions::new(
+            Some(category.clone()),
+            None,
+            None,
+            None,
+            None,
+        ))
+        .with_no_cache(no_cache);
+    let (data, _) = api.fetch_versions().unwrap_or_else(|e| {
+        eprintln!("Failed to fetch listing: {}", e);
+        std::process::exit(1);
+    });
+
+    let mut common: Option<BTreeSet<Version>> = None;
+    let mut empty = Vec::new();
+    for (label, options) in selections {
+        let filter = ArtifactFilter::new(options);
+        let versions: BTreeSet<Version> = data
+            .iter()
+            .filter_map(|resp| filter.check(resp).ok())
+            .collect();
+        if versions.is_empty() {
+            empty.push(label.as_str());
+        }
+        common = Some(match common {
+            Some(common) => common.intersection(&versions).cloned().collect(),
+            None => versions,
+        });
+    }
+
+    if !empty.is_empty() {
+        eprintln!("No matching builds for: {}", empty.join(", "));
+        std::process::exit(1);
+    }
+    common
+        .and_then(|versions| versions.last().cloned())
+        .unwrap_or_else(|| {
+            let labels: Vec<&str> = selections.iter().map(|(label, _)| label.as_str()).collect();
+            eprintln!(
+                "No single version is published for all of: {}",
+                labels.join(", ")
+            );
+            std::process::exit(1);
+        })
+}
+
+/// The artifacts a command works on: the targets named in `names` from the
+/// project config (every target with `all`), with `flags` overriding their
+/// fields, or just `flags` when no target is selected.
+pub(crate) fn select_targets(
+    ctx: &AppContext,
+    flags: Target,
+    names: &[String],
+    all: bool,
+) -> Vec<(Option<String>, Target)> {
+    if names.is_empty() && !all {
+        return vec![(None, flags)];
+    }
+
+    let selected = ctx.config.select_targets(names).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+    // Command line flags override what the target declares.
+    selected
+        .into_iter()
+        .map(|(name, target)| {
+            let target = Target {
+                category: flags.category.clone().or_else(|| target.category.clone()),
+                os: flags.os.clone().or_else(|| target.os.clone()),
+                arch: flags.arch.clone().or_else(|| target.arch.clone()),
+                build_type: flags
+                    .build_type
+                    .clone()
+                    .or_else(|| target.build_type.clone()),
+                ..target.clone()
+            };
+            (Some(name.clone()), target)
+        })
+        .collect()
+}
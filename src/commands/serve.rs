@@ -0,0 +1,116 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    thread,
+};
+
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::{
+    AppContext,
+    cli::ServeArgs,
+    spc::{ApiOptions, BuildCategory},
+};
+
+/// Requests handled at once, so that a slow client fetching an artifact
+/// doesn't hold up everyone else.
+const WORKERS: usize = 8;
+
+pub fn run(ctx: &AppContext, args: ServeArgs) {
+    let server = match Server::http(&args.addr) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Failed to bind {}: {}", args.addr, e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Serving on http://{}", args.addr);
+    if let Some(dir) = &args.artifacts_dir {
+        println!("Artifacts directory: {}", dir.display());
+    }
+
+    thread::scope(|scope| {
+        for _ in 0..WORKERS {
+            scope.spawn(|| {
+                while let Ok(request) = server.recv() {
+                    handle(ctx, &args, request);
+                }
+            });
+        }
+    });
+}
+
+fn handle(ctx: &AppContext, args: &ServeArgs, request: Request) {
+    let path = request.url().split('?').next().unwrap_or("/").to_string();
+
+    let status = if let Some(category) = BuildCategory::from_url_path(&path) {
+        serve_listing(ctx, request, category)
+    } else {
+        serve_artifact(args.artifacts_dir.as_deref(), request, &path)
+    };
+
+    println!("GET {} {}", path, status);
+}
+
+fn serve_listing(ctx: &AppContext, request: Request, category: BuildCategory) -> u16 {
+    let options = ApiOptions::new(Some(category), None, None, None, None);
+    let api = ctx.api(options);
+
+    let (status, body) = match api.fetch_versions() {
+        Ok((data, _)) => match serde_json::to_string(&data) {
+            Ok(json) => (200, json),
+            Err(e) => (500, error_body(&e)),
+        },
+        Err(e) => (502, error_body(&e)),
+    };
+
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type("application/json"));
+    let _ = request.respond(response);
+    status
+}
+
+fn error_body(error: &dyn std::fmt::Display) -> String {
+    serde_json::json!({ "error": error.to_string() }).to_string()
+}
+
+fn serve_artifact(artifacts_dir: Option<&Path>, request: Request, path: &str) -> u16 {
+    let Some(file_path) = artifacts_dir.and_then(|dir| resolve_artifact(dir, path)) else {
+        let _ = request.respond(Response::from_string("Not Found").with_status_code(404));
+        return 404;
+    };
+
+    match fs::File::open(&file_path) {
+        Ok(file) => {
+            let response =
+                Response::from_file(file).with_header(content_type("application/octet-stream"));
+            let _ = request.respond(response);
+            200
+        }
+        Err(_) => {
+            let _ = request.respond(Response::from_string("Not Found").with_status_code(404));
+            404
+        }
+    }
+}
+
+/// Maps a request path onto a file inside the artifacts directory, refusing
+/// anything that would escape it.
+fn resolve_artifact(dir: &Path, path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let file_path = dir.join(relative);
+    file_path.is_file().then_some(file_path)
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("static header is valid")
+}
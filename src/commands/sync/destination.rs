@@ -0,0 +1,228 @@
+use std::{error::Error, fs, path::PathBuf};
+
+use crate::spc::Api;
+
+/// Where `sync` writes the mirror: a local directory, or an object storage
+/// bucket when built with the `object-storage` feature.
+pub enum Destination {
+    Local(PathBuf),
+    #[cfg(feature = "object-storage")]
+    Remote(remote::Remote),
+}
+
+impl Destination {
+    pub fn parse(dest: &str) -> Result<Self, String> {
+        if dest.starts_with("s3://") || dest.starts_with("gs://") {
+            #[cfg(feature = "object-storage")]
+            return remote::Remote::parse(dest).map(Destination::Remote);
+
+            #[cfg(not(feature = "object-storage"))]
+            return Err(format!(
+                "Syncing to {} requires spc-utils built with the `object-storage` feature",
+                dest
+            ));
+        }
+
+        Ok(Destination::Local(PathBuf::from(dest)))
+    }
+
+    pub fn prepare(&self, dir: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            Destination::Local(root) => Ok(fs::create_dir_all(root.join(dir))?),
+            #[cfg(feature = "object-storage")]
+            Destination::Remote(_) => Ok(()),
+        }
+    }
+
+    /// Size of an already mirrored file, if present.
+    pub fn size(&self, path: &str) -> Option<u64> {
+        match self {
+            Destination::Local(root) => fs::metadata(root.join(path)).ok().map(|m| m.len()),
+            #[cfg(feature = "object-storage")]
+            Destination::Remote(remote) => remote.size(path),
+        }
+    }
+
+    /// Where a mirrored file lives on this machine, when the mirror is local.
+    pub fn local_path(&self, path: &str) -> Option<PathBuf> {
+        match self {
+            Destination::Local(root) => Some(root.join(path)),
+            #[cfg(feature = "object-storage")]
+            Destination::Remote(_) => None,
+        }
+    }
+
+    pub fn store_artifact(&self, api: &Api, name: &str, path: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            Destination::Local(root) => {
+                let target = root.join(path);
+                let result = api.download_artifact(name, &target);
+                if result.is_err() {
+                    let _ = fs::remove_file(&target);
+                }
+                result
+            }
+            #[cfg(feature = "object-storage")]
+            Destination::Remote(remote) => {
+                let staging = std::env::temp_dir().join(format!("spc-utils-sync-{}", name));
+                let _partial = crate::interrupt::PartialFile::track(&staging);
+                let result = api
+                    .download_artifact(name, &staging)
+                    .and_then(|()| remote.upload_file(path, &staging));
+                let _ = fs::remove_file(&staging);
+                result
+            }
+        }
+    }
+
+    /// Names of the files directly inside `dir`.
+    pub fn list(&self, dir: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        match self {
+            Destination::Local(root) => {
+                let mut names = Vec::new();
+                for entry in fs::read_dir(root.join(dir))? {
+                    let entry = entry?;
+                    if entry.file_type()?.is_file() {
+                        names.push(entry.file_name().to_string_lossy().into_owned());
+                    }
+                }
+                Ok(names)
+            }
+            #[cfg(feature = "object-storage")]
+            Destination::Remote(remote) => remote.list(dir),
+        }
+    }
+
+    pub fn remove(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            Destination::Local(root) => Ok(fs::remove_file(root.join(path))?),
+            #[cfg(feature = "object-storage")]
+            Destination::Remote(remote) => remote.delete(path),
+        }
+    }
+
+    pub fn write(&self, path: &str, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+        match self {
+            Destination::Local(root) => Ok(fs::write(root.join(path), contents)?),
+            #[cfg(feature = "object-storage")]
+            Destination::Remote(remote) => remote.put(path, contents),
+        }
+    }
+}
+
+#[cfg(feature = "object-storage")]
+mod remote {
+    use std::{error::Error, fs::File, io::Read, path::Path};
+
+    use object_store::{
+        ObjectStore, PutPayload, WriteMultipart, aws::AmazonS3Builder,
+        gcp::GoogleCloudStorageBuilder, path::Path as ObjectPath,
+    };
+    use tokio::runtime::Runtime;
+
+    const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+    pub struct Remote {
+        store: Box<dyn ObjectStore>,
+        prefix: String,
+        runtime: Runtime,
+    }
+
+    impl Remote {
+        /// Parses `s3://bucket/prefix` or `gs://bucket/prefix`. Credentials come
+        /// from the usual `AWS_*` / `GOOGLE_*` environment variables.
+        pub fn parse(dest: &str) -> Result<Self, String> {
+            let (scheme, rest) = dest
+                .split_once("://")
+                .ok_or_else(|| format!("Invalid destination: {}", dest))?;
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+
+            let store: Box<dyn ObjectStore> = match scheme {
+                "s3" => Box::new(
+                    AmazonS3Builder::from_env()
+                        .with_bucket_name(bucket)
+                        .build()
+                        .map_err(|e| e.to_string())?,
+                ),
+                "gs" => Box::new(
+                    GoogleCloudStorageBuilder::from_env()
+                        .with_bucket_name(bucket)
+                        .build()
+                        .map_err(|e| e.to_string())?,
+                ),
+                _ => return Err(format!("Unsupported destination scheme: {}", scheme)),
+            };
+
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| e.to_string())?;
+
+            Ok(Self {
+                store,
+                prefix: prefix.trim_matches('/').to_string(),
+                runtime,
+            })
+        }
+
+        fn object_path(&self, path: &str) -> ObjectPath {
+            if self.prefix.is_empty() {
+                ObjectPath::from(path)
+            } else {
+                ObjectPath::from(format!("{}/{}", self.prefix, path))
+            }
+        }
+
+        pub fn size(&self, path: &str) -> Option<u64> {
+            self.runtime
+                .block_on(self.store.head(&self.object_path(path)))
+                .ok()
+                .map(|meta| meta.size)
+        }
+
+        pub fn upload_file(&self, path: &str, local: &Path) -> Result<(), Box<dyn Error>> {
+            self.runtime.block_on(async {
+                let upload = self.store.put_multipart(&self.object_path(path)).await?;
+                let mut writer = WriteMultipart::new_with_chunk_size(upload, CHUNK_SIZE);
+                let mut file = File::open(local)?;
+                let mut buf = vec![0; CHUNK_SIZE];
+
+                loop {
+                    let read = file.read(&mut buf)?;
+                    if read == 0 {
+                        break;
+                    }
+                    writer.wait_for_capacity(4).await?;
+                    writer.write(&buf[..read]);
+                }
+
+                writer.finish().await?;
+                Ok(())
+            })
+        }
+
+        pub fn list(&self, dir: &str) -> Result<Vec<String>, Box<dyn Error>> {
+            let listing = self
+                .runtime
+                .block_on(self.store.list_with_delimiter(Some(&self.object_path(dir))))?;
+            Ok(listing
+                .objects
+                .into_iter()
+                .filter_map(|meta| meta.location.filename().map(str::to_string))
+                .collect())
+        }
+
+        pub fn delete(&self, path: &str) -> Result<(), Box<dyn Error>> {
+            self.runtime
+                .block_on(self.store.delete(&self.object_path(path)))?;
+            Ok(())
+        }
+
+        pub fn put(&self, path: &str, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+            let payload = PutPayload::from(contents.to_vec());
+            self.runtime
+                .block_on(self.store.put(&self.object_path(path), payload))?;
+            Ok(())
+        }
+    }
+}
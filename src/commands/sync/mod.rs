@@ -0,0 +1,373 @@
+mod destination;
+mod throttle;
+mod verify;
+
+use std::{collections::HashSet, error::Error, thread, time::Duration};
+
+use crate::{
+    AppContext,
+    cli::SyncArgs,
+    commands::prompt::{confirm, is_interactive},
+    spc::{
+        Api, ApiOptions, ArtifactFilter, ArtifactName, BuildCategory, COMPANION_EXTENSIONS,
+        Cancelled, Freshness, SpcJsonResponse,
+    },
+};
+
+use destination::Destination;
+use throttle::Throttle;
+use verify::{Entry, Status};
+
+pub fn run(ctx: &AppContext, args: SyncArgs) {
+    let dest = Destination::parse(&args.dest).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+
+    let categories = if args.category.is_empty() {
+        vec![BuildCategory::default_for_os()]
+    } else {
+        args.category.clone()
+    };
+
+    let throttle = Throttle::new(
+        args.concurrency.into(),
+        Duration::from_millis(args.delay_ms),
+    );
+    let mut failed = 0;
+
+    // Only the given fields narrow the selection; the rest mirror everything.
+    let selection = ArtifactFilter::only(
+        args.os.clone(),
+        args.arch.clone(),
+        args.build_type.clone(),
+        args.version.clone(),
+    );
+
+    for category in &categories {
+        let options = ApiOptions::new(Some(category.clone()), None, None, None, None);
+        let api = ctx
+            .api(options)
+            .with_no_cache(args.no_cache)
+            .with_progress(args.concurrency == 1);
+        if api.is_cancelled() {
+            eprintln!("Not syncing {}: {}", category, Cancelled);
+            failed += 1;
+            continue;
+        }
+
+        let (data, freshness) = match api.fetch_versions() {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Failed to fetch {} listing: {}", category, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        // Everything upstream still serves, regardless of the selection.
+        let upstream: HashSet<String> = data.iter().map(|resp| resp.name.clone()).collect();
+
+        let artifacts: Vec<SpcJsonResponse> = data
+            .into_iter()
+            .filter(|resp| selection.matches(resp))
+            .collect();
+
+        let category_dir = category.url_path();
+        let entries: Vec<Entry> = artifacts
+            .iter()
+            .map(|artifact| Entry {
+                name: artifact.name.clone(),
+                path: format!("{}/{}", category_dir, artifact.name),
+                size: artifact.size().parse().ok(),
+            })
+            .collect();
+
+        if args.verify_only {
+            let statuses = verify::verify_all(&dest, &api, &entries);
+            failed += report_verification(category, &entries, &statuses);
+            continue;
+        }
+
+        if let Err(e) = dest.prepare(category_dir) {
+            eprintln!("Failed to create {}: {}", category_dir, e);
+            failed += 1;
+            continue;
+        }
+
+        let (existing, missing): (Vec<Entry>, Vec<Entry>) = entries
+            .into_iter()
+            .partition(|entry| is_up_to_date(&dest, entry));
+
+        // Files already mirrored are hashed while the missing ones download.
+        let missing: Vec<&Entry> = missing.iter().collect();
+        let (statuses, (mut downloaded, download_failures)) = thread::scope(|scope| {
+            let verifier = scope.spawn(|| verify::verify_all(&dest, &api, &existing));
+            let downloads = fetch_all(&throttle, &dest, &api, &missing);
+            (verifier.join().unwrap(), downloads)
+        });
+        failed += download_failures;
+
+        let damaged: Vec<&Entry> = existing
+            .iter()
+            .zip(statuses)
+            .filter(|(_, status)| status.is_problem())
+            .map(|(entry, status)| {
+                eprintln!("{}: {}, downloading again", entry.path, status);
+                entry
+            })
+            .collect();
+        let up_to_date = existing.len() - damaged.len();
+
+        let (repaired, repair_failures) = fetch_all(&throttle, &dest, &api, &damaged);
+        downloaded += repaired;
+        failed += repair_failures;
+
+        if args.with_checksums {
+            for artifact in &artifacts {
+                let path = format!("{}/{}", category_dir, artifact.name);
+                for ext in COMPANION_EXTENSIONS {
+                    let companion = format!("{}{}", path, ext);
+                    if dest.size(&companion).is_some() {
+                        continue;
+                    }
+                    if let Some(contents) = api.fetch_companion(&artifact.name, ext)
+                        && let Err(e) = dest.write(&companion, &contents)
+                    {
+                        eprintln!("Failed to write {}: {}", companion, e);
+                        failed += 1;
+                    }
+                }
+            }
+        }
+
+        if args.emit_index
+            && let Err(e) = write_category_index(&dest, category, &artifacts)
+        {
+            eprintln!("Failed to write index for {}: {}", category, e);
+            failed += 1;
+        }
+
+        let mut summary = format!(
+            "{}: {} downloaded, {} up to date",
+            category, downloaded, up_to_date
+        );
+        // A stale or bundled snapshot doesn't know about newer uploads, so
+        // pruning by it would delete them.
+        if args.delete && matches!(freshness, Freshness::Stale(_) | Freshness::Bundled(_)) {
+            eprintln!(
+                "Not pruning {}: the listing did not come from the server{}",
+                category_dir,
+                freshness.annotation()
+            );
+            failed += 1;
+        } else if args.delete {
+            match prune(ctx, &dest, category_dir, &upstream, args.dry_run) {
+                Ok(removed) if args.dry_run => {
+                    summary.push_str(&format!(", {} to remove", removed))
+                }
+                Ok(removed) => summary.push_str(&format!(", {} removed", removed)),
+                Err(e) => {
+                    eprintln!("Failed to prune {}: {}", category_dir, e);
+                    failed += 1;
+                }
+            }
+        }
+        println!("{}", summary);
+    }
+
+    if args.emit_index
+        && let Err(e) = write_root_index(&dest, &categories)
+    {
+        eprintln!("Failed to write root index: {}", e);
+        failed += 1;
+    }
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Downloads `entries` into the mirror as fast as `throttle` allows. Returns
+/// how many succeeded and how many failed.
+fn fetch_all(
+    throttle: &Throttle,
+    dest: &Destination,
+    api: &Api,
+    entries: &[&Entry],
+) -> (usize, usize) {
+    let results = throttle.run(entries, |entry| {
+        if api.is_cancelled() {
+            return Err(Cancelled.to_string());
+        }
+        dest.store_artifact(api, &entry.name, &entry.path)
+            .map_err(|e| e.to_string())
+    });
+
+    let mut downloaded = 0;
+    let mut failed = 0;
+    for (entry, result) in entries.iter().zip(results) {
+        match result {
+            Ok(()) => downloaded += 1,
+            Err(e) => {
+                eprintln!("Failed to download {}: {}", entry.name, e);
+                failed += 1;
+            }
+        }
+    }
+    (downloaded, failed)
+}
+
+/// An artifact is considered synced when a file of the advertised size exists.
+/// Its contents are verified separately.
+fn is_up_to_date(dest: &Destination, entry: &Entry) -> bool {
+    entry
+        .size
+        .is_some_and(|size| dest.size(&entry.path) == Some(size))
+}
+
+/// Removes artifacts from a category directory of the mirror that upstream no
+/// longer lists, along with their checksum and signature files. Other files,
+/// such as the generated indexes, are left alone. At a terminal, asks before
+/// removing anything. Returns how many files were (or, with `dry_run`, would
+/// be) removed.
+fn prune(
+    ctx: &AppContext,
+    dest: &Destination,
+    dir: &str,
+    upstream: &HashSet<String>,
+    dry_run: bool,
+) -> Result<usize, Box<dyn Error>> {
+    // An empty listing is far more likely an upstream hiccup than every build
+    // being retracted.
+    if upstream.is_empty() {
+        eprintln!("Upstream lists nothing for {}, not pruning", dir);
+        return Ok(0);
+    }
+
+    let stale: Vec<String> = dest
+        .list(dir)?
+        .into_iter()
+        .filter(|name| {
+            let artifact = COMPANION_EXTENSIONS
+                .iter()
+                .find_map(|ext| name.strip_suffix(ext))
+                .unwrap_or(name);
+            ArtifactName::parse(artifact).is_some() && !upstream.contains(artifact)
+        })
+        .map(|name| format!("{}/{}", dir, name))
+        .collect();
+
+    if dry_run {
+        for path in &stale {
+            println!("Would remove {}", path);
+        }
+        return Ok(stale.len());
+    }
+
+    // `--delete` was asked for, so only a person at a terminal gets a say.
+    if !stale.is_empty() && is_interactive() {
+        for path in &stale {
+            println!("  {}", path);
+        }
+        if !confirm(ctx, &format!("Remove {} files from {}?", stale.len(), dir)) {
+            eprintln!("Not pruning {}", dir);
+            return Ok(0);
+        }
+    }
+
+    for path in &stale {
+        dest.remove(path)?;
+        println!("Removed {}", path);
+    }
+    Ok(stale.len())
+}
+
+/// Prints the problems `sync --verify-only` found in a category and a summary
+/// line. Returns the number of problems.
+fn report_verification(category: &BuildCategory, entries: &[Entry], statuses: &[Status]) -> usize {
+    let mut problems = 0;
+    for (entry, status) in entries.iter().zip(statuses) {
+        if status.is_problem() {
+            eprintln!("  [{}] {}", status, entry.path);
+            problems += 1;
+        }
+    }
+
+    let verified = statuses.iter().filter(|s| **s == Status::Ok).count();
+    println!(
+        "{}: {} verified, {} without checksum, {} failed",
+        category,
+        verified,
+        statuses.len() - verified - problems,
+        problems
+    );
+    problems
+}
+
+/// Writes `index.json` (the upstream listing format, restricted to what was
+/// mirrored) and a browsable `index.html` into a category directory.
+fn write_category_index(
+    dest: &Destination,
+    category: &BuildCategory,
+    artifacts: &[SpcJsonResponse],
+) -> Result<(), Box<dyn Error>> {
+    let dir = category.url_path();
+    dest.write(
+        &format!("{}/index.json", dir),
+        serde_json::to_string_pretty(artifacts)?.as_bytes(),
+    )?;
+
+    let mut rows = String::new();
+    for artifact in artifacts {
+        rows.push_str(&format!(
+            "<tr><td><a href=\"{name}\">{name}</a></td><td>{size}</td><td>{modified}</td></tr>\n",
+            name = escape_html(&artifact.name),
+            size = escape_html(artifact.size()),
+            modified = artifact.last_modified().format("%Y-%m-%d %H:%M:%S"),
+        ));
+    }
+
+    let body = format!(
+        "<table>\n<tr><th>Name</th><th>Size</th><th>Last modified</th></tr>\n{}</table>",
+        rows
+    );
+    dest.write(
+        &format!("{}/index.html", dir),
+        html_page(&format!("Index of /{}", dir), &body).as_bytes(),
+    )
+}
+
+fn write_root_index(
+    dest: &Destination,
+    categories: &[BuildCategory],
+) -> Result<(), Box<dyn Error>> {
+    let mut items = String::new();
+    for category in categories {
+        items.push_str(&format!(
+            "<li><a href=\"{path}/\">{path}/</a></li>\n",
+            path = category.url_path()
+        ));
+    }
+
+    dest.write(
+        "index.html",
+        html_page("Index of /", &format!("<ul>\n{}</ul>", items)).as_bytes(),
+    )
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n{body}\n</body>\n</html>\n",
+        title = escape_html(title),
+        body = body
+    )
+}
+
+fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,98 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::spc::rate_limit_count;
+
+/// Limits how hard `sync` pulls from upstream: at most `concurrency` transfers
+/// at once, started at least `delay` apart. Concurrency is halved whenever the
+/// server starts answering with `429 Too Many Requests`.
+pub struct Throttle {
+    concurrency: AtomicUsize,
+    delay: Duration,
+    next_start: Mutex<Instant>,
+    rate_limits_seen: AtomicUsize,
+}
+
+impl Throttle {
+    pub fn new(concurrency: usize, delay: Duration) -> Self {
+        Self {
+            concurrency: AtomicUsize::new(concurrency.max(1)),
+            delay,
+            next_start: Mutex::new(Instant::now()),
+            rate_limits_seen: AtomicUsize::new(rate_limit_count()),
+        }
+    }
+
+    /// Runs `task` for every item and returns the results in item order.
+    pub fn run<T: Sync, R: Send>(&self, items: &[T], task: impl Fn(&T) -> R + Sync) -> Vec<R> {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+        let workers = self.concurrency.load(Ordering::Relaxed).min(items.len());
+
+        thread::scope(|scope| {
+            for worker in 0..workers {
+                let (next, results, task) = (&next, &results, &task);
+                scope.spawn(move || {
+                    // Workers above the current limit retire after their
+                    // transfer; the first one always stays to drain the queue.
+                    while worker < self.concurrency.load(Ordering::Relaxed) {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+
+                        self.wait_turn();
+                        let result = task(item);
+                        results.lock().unwrap()[index] = Some(result);
+                        self.back_off_if_rate_limited();
+                    }
+                });
+            }
+        });
+
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.expect("every item is processed"))
+            .collect()
+    }
+
+    fn wait_turn(&self) {
+        if self.delay.is_zero() {
+            return;
+        }
+
+        let mut next_start = self.next_start.lock().unwrap();
+        let now = Instant::now();
+        if *next_start > now {
+            thread::sleep(*next_start - now);
+        }
+        *next_start = Instant::now() + self.delay;
+    }
+
+    fn back_off_if_rate_limited(&self) {
+        let seen = rate_limit_count();
+        if self.rate_limits_seen.swap(seen, Ordering::Relaxed) >= seen {
+            return;
+        }
+
+        if let Ok(previous) =
+            self.concurrency
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                    (n > 1).then_some(n / 2)
+                })
+        {
+            eprintln!(
+                "Upstream is rate limiting, reducing concurrency to {}",
+                previous / 2
+            );
+        }
+    }
+}
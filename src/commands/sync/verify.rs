@@ -0,0 +1,122 @@
+use std::{
+    fmt,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use crate::{
+    commands::archive::sha256_file,
+    spc::{Api, parse_sha256},
+};
+
+use super::destination::Destination;
+
+/// Upper bound on files hashed at once, so verification doesn't starve the
+/// downloads running next to it.
+const MAX_WORKERS: usize = 4;
+
+/// A mirrored file to check: the artifact name upstream, its path in the
+/// mirror, and the size the listing advertises.
+pub struct Entry {
+    pub name: String,
+    pub path: String,
+    pub size: Option<u64>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Status {
+    /// Matches the published checksum
+    Ok,
+    /// Size matches but there is no checksum to compare against, or the
+    /// destination can't be hashed locally
+    Unverified,
+    Missing,
+    SizeMismatch,
+    ChecksumMismatch,
+}
+
+impl Status {
+    pub fn is_problem(&self) -> bool {
+        matches!(
+            self,
+            Status::Missing | Status::SizeMismatch | Status::ChecksumMismatch
+        )
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Ok => "ok",
+            Status::Unverified => "unverified",
+            Status::Missing => "missing",
+            Status::SizeMismatch => "size mismatch",
+            Status::ChecksumMismatch => "checksum mismatch",
+        })
+    }
+}
+
+/// Checks every entry on a small pool of threads. Results are in the order of
+/// `entries`.
+pub fn verify_all(dest: &Destination, api: &Api, entries: &[Entry]) -> Vec<Status> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![Status::Unverified; entries.len()]);
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_WORKERS)
+        .min(entries.len());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(entry) = entries.get(index) else {
+                        break;
+                    };
+                    let status = verify(dest, api, entry);
+                    results.lock().unwrap()[index] = status;
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
+
+fn verify(dest: &Destination, api: &Api, entry: &Entry) -> Status {
+    let Some(size) = dest.size(&entry.path) else {
+        return Status::Missing;
+    };
+    if entry.size.is_some_and(|expected| expected != size) {
+        return Status::SizeMismatch;
+    }
+
+    let Some(local) = dest.local_path(&entry.path) else {
+        return Status::Unverified;
+    };
+    let Some(expected) = expected_digest(dest, api, entry) else {
+        return Status::Unverified;
+    };
+
+    match sha256_file(&local) {
+        Ok(digest) if digest == expected => Status::Ok,
+        Ok(_) => Status::ChecksumMismatch,
+        Err(_) => Status::Missing,
+    }
+}
+
+/// The published SHA-256 of an artifact: the `.sha256` file already mirrored
+/// next to it, otherwise the one upstream.
+fn expected_digest(dest: &Destination, api: &Api, entry: &Entry) -> Option<String> {
+    let companion = format!("{}.sha256", entry.path);
+    let contents = dest
+        .local_path(&companion)
+        .and_then(|path| std::fs::read(path).ok())
+        .or_else(|| api.fetch_companion(&entry.name, ".sha256"))?;
+
+    parse_sha256(&contents)
+}
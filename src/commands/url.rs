@@ -0,0 +1,64 @@
+use serde_json::json;
+
+use crate::{
+    AppContext,
+    cli::UrlArgs,
+    commands::selection::{resolve_newest, select_targets},
+    config::Target,
+    spc::{ApiOptions, VersionSpec},
+};
+
+pub fn run(ctx: &AppContext, args: UrlArgs) {
+    let flags = Target {
+        category: args.category.clone(),
+        version: None,
+        os: args.os.clone(),
+        arch: args.arch.clone(),
+        build_type: args.build_type.clone(),
+        output: None,
+    };
+
+    let mut resolved = Vec::new();
+    for (name, target) in select_targets(ctx, flags, &args.targets, false) {
+        let spec = args.version.clone().or_else(|| target.version_spec());
+        let options = ApiOptions::new(
+            target.category.clone(),
+            spec.clone(),
+            target.os.clone(),
+            target.arch.clone(),
+            target.build_type.clone(),
+        );
+        let api = ctx
+            .api(options)
+            .with_no_cache(args.no_cache)
+            .with_explain(args.explain);
+
+        // Exact versions need no listing, so their URLs are known offline.
+        let version = match spec.as_ref().and_then(VersionSpec::exact) {
+            Some(version) => version.clone(),
+            None => resolve_newest(&api).version,
+        };
+        let url = api.download_url(&version);
+
+        if args.json {
+            resolved.push(json!({
+                "target": name,
+                "version": version.to_string(),
+                "name": api.artifact_name(&version),
+                "url": url,
+            }));
+        } else {
+            println!("{}", url);
+        }
+    }
+
+    if args.json {
+        match serde_json::to_string_pretty(&resolved) {
+            Ok(out) => println!("{}", out),
+            Err(e) => {
+                eprintln!("Failed to serialize URLs: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{
+    AppContext, cli::VerifyInstallArgs, commands::archive::sha256_file, receipts::Receipts,
+};
+
+pub fn run(ctx: &AppContext, args: VerifyInstallArgs) {
+    let store = if args.system {
+        Receipts::system()
+    } else {
+        ctx.receipts.clone()
+    };
+
+    let receipts = match &args.version {
+        Some(version) => store.load(version).map(|r| r.into_iter().collect()),
+        None => store.load_all(),
+    };
+    let receipts = receipts.unwrap_or_else(|e| {
+        eprintln!("Failed to read receipts: {}", e);
+        std::process::exit(1);
+    });
+
+    if receipts.is_empty() {
+        match &args.version {
+            Some(version) => {
+                eprintln!("No receipt for {}", version);
+                std::process::exit(1);
+            }
+            None => {
+                println!("No receipts in {}", store.dir().display());
+                return;
+            }
+        }
+    }
+
+    let mut problems = 0;
+    for receipt in &receipts {
+        println!(
+            "{} (installed {})",
+            receipt.version,
+            ctx.human.time(&receipt.installed_at, "%Y-%m-%d %H:%M")
+        );
+
+        for file in &receipt.files {
+            let status = match sha256_file(&file.path) {
+                Ok(digest) if digest == file.sha256 => "ok",
+                Ok(_) => "modified",
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => "missing",
+                Err(_) => "unreadable",
+            };
+            if status != "ok" {
+                problems += 1;
+            }
+            println!("  [{}] {}", status, file.path.display());
+        }
+    }
+
+    if problems > 0 {
+        eprintln!("{} file(s) no longer match their receipt", problems);
+        std::process::exit(1);
+    }
+}
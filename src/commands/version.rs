@@ -0,0 +1,62 @@
+use serde::Serialize;
+
+use crate::{cli::VersionArgs, spc::DEFAULT_BASE_URL};
+
+/// What a binary was built from, for bug reports from machines running
+/// different builds.
+#[derive(Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub commit: &'static str,
+    pub build_date: &'static str,
+    pub target: &'static str,
+    pub features: Vec<&'static str>,
+    pub default_base_url: &'static str,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "object-storage") {
+            features.push("object-storage");
+        }
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: env!("SPC_UTILS_GIT_COMMIT"),
+            build_date: env!("SPC_UTILS_BUILD_DATE"),
+            target: env!("SPC_UTILS_TARGET"),
+            features,
+            default_base_url: DEFAULT_BASE_URL,
+        }
+    }
+
+    /// The version followed by one `key: value` line per detail, as printed
+    /// by `--version`.
+    pub fn long_version(&self) -> String {
+        let features = if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        };
+
+        format!(
+            "{}\ncommit: {}\nbuilt: {}\ntarget: {}\nfeatures: {}\nbase URL: {}",
+            self.version,
+            self.commit,
+            self.build_date,
+            self.target,
+            features,
+            self.default_base_url
+        )
+    }
+}
+
+pub fn run(args: VersionArgs) {
+    let info = BuildInfo::current();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&info).unwrap());
+    } else {
+        println!("spc-utils {}", info.long_version());
+    }
+}
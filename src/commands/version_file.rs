@@ -0,0 +1,64 @@
+use std::{collections::BTreeMap, path::Path};
+
+use semver::Version;
+use serde::Serialize;
+
+/// What `--write-version-file` records about a resolved artifact. Its
+/// SHA-256 is only known once it has been downloaded.
+#[derive(Serialize)]
+pub(crate) struct VersionRecord {
+    pub version: Version,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl VersionRecord {
+    pub fn resolved(version: &Version, url: String) -> Self {
+        Self {
+            version: version.clone(),
+            url,
+            sha256: None,
+        }
+    }
+}
+
+/// Writes the resolved version for build systems. The format follows the file
+/// extension: `.json` and `.toml` include the download URL and, once
+/// downloaded, the SHA-256; anything else gets the bare version on a single
+/// line.
+pub(crate) fn write_version_file(path: &Path, record: &VersionRecord) {
+    let contents = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::to_string(record)
+            .map(|json| format!("{}\n", json))
+            .map_err(|e| e.to_string()),
+        Some("toml") => toml::to_string(record).map_err(|e| e.to_string()),
+        _ => Ok(format!("{}\n", record.version)),
+    };
+    save_version_file(path, contents);
+}
+
+/// Writes the version every downloaded target resolved to, keyed by target
+/// name: tables in `.json` and `.toml`, otherwise `<target> <version>` lines.
+pub(crate) fn write_target_versions(path: &Path, records: &BTreeMap<String, VersionRecord>) {
+    let contents = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::to_string(records)
+            .map(|json| format!("{}\n", json))
+            .map_err(|e| e.to_string()),
+        Some("toml") => toml::to_string(records).map_err(|e| e.to_string()),
+        _ => Ok(records
+            .iter()
+            .map(|(name, record)| format!("{} {}\n", name, record.version))
+            .collect()),
+    };
+    save_version_file(path, contents);
+}
+
+fn save_version_file(path: &Path, contents: Result<String, String>) {
+    if let Err(e) =
+        contents.and_then(|contents| std::fs::write(path, contents).map_err(|e| e.to_string()))
+    {
+        eprintln!("Failed to write version file {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+}
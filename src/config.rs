@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use semver::Version;
+use serde::Deserialize;
+
+use crate::{
+    cli::validate_version_spec,
+    signature,
+    spc::{self, VersionSpec},
+};
+
+pub const PROJECT_CONFIG_FILE: &str = ".spc-utils.toml";
+
+/// Project configuration read from `.spc-utils.toml` in the working directory.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Longest `Retry-After` delay, in seconds, honoured when rate limited.
+    pub max_retry_wait: Option<u64>,
+
+    /// Alternative location of php.net's release feed, used to flag
+    /// security releases.
+    pub php_releases_url: Option<String>,
+
+    /// Where cached listings and artifacts are kept instead of the platform's
+    /// cache directory.
+    pub cache_dir: Option<PathBuf>,
+
+    /// Where receipts and other installed state are kept instead of the
+    /// platform's data directory.
+    pub data_dir: Option<PathBuf>,
+
+    /// Build type used when none is requested, per OS (`linux`, `macos`,
+    /// `windows`), instead of `cli`.
+    #[serde(default)]
+    pub default_build_type: BTreeMap<String, String>,
+
+    /// Artifact naming scheme per category (`bulk`, `win-max`, ...), for when
+    /// detecting it from the listing isn't enough.
+    #[serde(default)]
+    pub naming_scheme: BTreeMap<String, spc::NamingScheme>,
+
+    /// Mirrors in order of preference. The first one replaces
+    /// dl.static-php.dev when no `--base-url` is given; `mirror bench
+    /// --write` reorders them.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+
+    /// Minisign public key that `download --verify-signature` trusts: the
+    /// base64 line of a `minisign.pub` file.
+    pub signing_key: Option<String>,
+
+    /// Days a release must have been out before `--channel stable` picks it.
+    pub stable_channel_days: Option<u32>,
+
+    /// Check once a day whether a newer spc-utils is out, and say so after
+    /// command output.
+    #[serde(default)]
+    pub self_update_check: bool,
+
+    #[serde(default)]
+    pub targets: BTreeMap<String, Target>,
+
+    #[serde(default)]
+    pub hooks: Hooks,
+}
+
+/// Shell commands run around downloads and installs. They receive `VERSION`
+/// and `PATH_TO_FILE`, and after the download also `SHA256`.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    pub pre_download: Option<String>,
+    pub post_download: Option<String>,
+    /// Run after `install` puts an artifact in place, with `PATH_TO_FILE`
+    /// being the directory it was unpacked to.
+    pub post_install: Option<String>,
+}
+
+/// A named artifact the project depends on, e.g. a `cli` and an `fpm` build.
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Target {
+    pub category: Option<spc::BuildCategory>,
+    pub version: Option<String>,
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    pub build_type: Option<String>,
+    pub output: Option<PathBuf>,
+}
+
+impl Target {
+    /// The pinned version as `check-update` compares it: partial versions
+    /// are filled with zeros, and `latest` pins nothing.
+    pub fn version(&self) -> Option<Version> {
+        match self.version_spec()? {
+            VersionSpec::Latest => None,
+            VersionSpec::Major(major) => Some(Version::new(major, 0, 0)),
+            VersionSpec::Minor(major, minor) => Some(Version::new(major, minor, 0)),
+            VersionSpec::Exact(version) => Some(version),
+        }
+    }
+
+    /// The version to download: the newest match unless it is exact.
+    pub fn version_spec(&self) -> Option<VersionSpec> {
+        self.version
+            .as_deref()
+            .map(|v| validate_version_spec(v).expect("target versions are validated on load"))
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, String> {
+        let path = PathBuf::from(PROJECT_CONFIG_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+
+        let config: Config =
+            toml::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (os, build_type) in &self.default_build_type {
+            if !spc::SPC_OS_OPTIONS.contains(&os.as_str()) {
+                return Err(format!("Unknown OS in default_build_type: {}", os));
+            }
+            if !spc::SPC_PHP_BUILD_TYPE_OPTIONS.contains(&build_type.as_str()) {
+                return Err(format!("Invalid default_build_type.{}: {}", os, build_type));
+            }
+        }
+
+        if let Some(key) = &self.signing_key {
+            signature::parse_public_key(key).map_err(|e| format!("signing_key: {}", e))?;
+        }
+
+        for category in self.naming_scheme.keys() {
+            if category.parse::<spc::BuildCategory>().is_err() {
+                return Err(format!("Unknown category in naming_scheme: {}", category));
+            }
+        }
+
+        for (name, target) in &self.targets {
+            let invalid = |field: &str, value: &str| {
+                Err(format!(
+                    "Target '{}' has an invalid {}: {}",
+                    name, field, value
+                ))
+            };
+
+            if let Some(version) = &target.version {
+                validate_version_spec(version)
+                    .map_err(|e| format!("Target '{}' has an invalid version: {}", name, e))?;
+            }
+            if let Some(os) = &target.os
+                && !spc::SPC_OS_OPTIONS.contains(&os.as_str())
+            {
+                return invalid("os", os);
+            }
+            if let Some(arch) = &target.arch
+                && !spc::SPC_ARCH_OPTIONS.contains(&arch.as_str())
+            {
+                return invalid("arch", arch);
+            }
+            if let Some(build_type) = &target.build_type
+                && !spc::SPC_PHP_BUILD_TYPE_OPTIONS.contains(&build_type.as_str())
+            {
+                return invalid("build_type", build_type);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The configured default build type for artifacts built for `os`, as
+    /// named in artifact file names (`win` for Windows).
+    pub fn default_build_type(&self, os: &str) -> Option<String> {
+        let os = if os == "win" { "windows" } else { os };
+        self.default_build_type.get(os).cloned()
+    }
+
+    /// The naming scheme configured for `category`, if any.
+    pub fn naming_scheme(&self, category: &spc::BuildCategory) -> Option<spc::NamingScheme> {
+        self.naming_scheme.get(&category.to_string()).copied()
+    }
+
+    /// The targets to operate on: all of them, or only the named subset.
+    pub fn select_targets(&self, names: &[String]) -> Result<Vec<(&String, &Target)>, String> {
+        if names.is_empty() {
+            return Ok(self.targets.iter().collect());
+        }
+
+        names
+            .iter()
+            .map(|name| {
+                self.targets
+                    .get_key_value(name)
+                    .ok_or_else(|| format!("Unknown target '{}' in {}", name, PROJECT_CONFIG_FILE))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::spc::CancelToken;
+
+/// Conventional exit status of a process stopped by SIGINT (128 + 2).
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Exit status when `--timeout-total` runs out, the same as `timeout(1)`.
+const TIMED_OUT_EXIT_CODE: i32 = 124;
+
+/// Files and scratch directories being written right now, removed if the
+/// user hits Ctrl-C.
+static PARTIAL_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Cancelled on Ctrl-C and at `--timeout-total`, so that threads still
+/// transferring stop writing before their partial files are removed.
+static CANCEL: LazyLock<CancelToken> = LazyLock::new(CancelToken::default);
+
+/// The token every API client of this process is cancelled through.
+pub fn cancel_token() -> CancelToken {
+    CANCEL.clone()
+}
+
+/// Installs a Ctrl-C handler that deletes partially written files before
+/// exiting with status 130.
+pub fn install() {
+    let result = ctrlc::set_handler(|| abort("\nInterrupted", INTERRUPTED_EXIT_CODE));
+
+    if let Err(e) = result {
+        eprintln!("Warning: Failed to install Ctrl-C handler: {}", e);
+    }
+}
+
+/// Aborts the process once `budget` has passed, cleaning up the same way as
+/// Ctrl-C but exiting with status 124.
+pub fn abort_after(budget: Duration) {
+    thread::spawn(move || {
+        thread::sleep(budget);
+        abort(
+            &format!("\nTimed out after {}s (--timeout-total)", budget.as_secs()),
+            TIMED_OUT_EXIT_CODE,
+        );
+    });
+}
+
+fn abort(message: &str, code: i32) -> ! {
+    CANCEL.cancel();
+    let files = PARTIAL_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    for path in files.iter() {
+        let _ = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+    }
+
+    eprintln!("{}", message);
+    std::process::exit(code);
+}
+
+/// Marks a file, or a scratch directory, as in progress for as long as the
+/// guard is alive.
+pub struct PartialFile {
+    path: PathBuf,
+}
+
+impl PartialFile {
+    pub fn track(path: &Path) -> Self {
+        let path = path.to_path_buf();
+        PARTIAL_FILES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(path.clone());
+        Self { path }
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        let mut files = PARTIAL_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = files.iter().position(|path| *path == self.path) {
+            files.remove(index);
+        }
+    }
+}
@@ -1,33 +1,133 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
+use std::{path::PathBuf, time::Duration};
 
 mod cli;
 mod commands;
+mod config;
+mod interrupt;
+mod manifest;
+mod platform;
+mod receipts;
+mod registry;
+mod requirements;
+mod self_check;
+mod signature;
 mod spc;
 
-use crate::{cli::{Cli, Commands}, spc::Cache};
+use crate::{
+    cli::{Cli, Commands},
+    commands::{HumanFormat, version::BuildInfo},
+    config::Config,
+    receipts::Receipts,
+    registry::Registry,
+    self_check::SelfCheck,
+    spc::{Api, ApiOptions, Cache, Source},
+};
+
+/// Exit status when a command succeeded on expired cached data because the
+/// server was unreachable.
+const STALE_EXIT_CODE: i32 = 3;
 
 fn main() {
-    let app = Cli::parse();
-    let ctx = AppContext::new();
+    platform::enable_utf8_console();
+    let long_version = BuildInfo::current().long_version();
+    let mut command = Cli::command().long_version(long_version);
+    if std::env::args_os().any(|arg| arg == "--examples") {
+        // `cache --examples` and the like have no subcommand to run.
+        command = command
+            .subcommand_required(false)
+            .mut_subcommands(|sub| sub.subcommand_required(false));
+    }
+    let matches = command.get_matches();
+    if matches.get_flag("examples") {
+        let command = matches.subcommand_name();
+        crate::commands::examples::print(command, false);
+        return;
+    }
+    let app = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    interrupt::install();
+    if let Some(budget) = app.timeout_total {
+        interrupt::abort_after(budget);
+    }
+
+    let ctx = AppContext::at(app.cache_dir.clone(), app.data_dir.clone())
+        .with_base_url(app.base_url.clone())
+        .with_source(app.source, app.github_repo.clone())
+        .with_max_cache_age(app.max_cache_age)
+        .with_assume_yes(app.assume_yes)
+        .with_utc(app.utc);
+
+    let self_check =
+        (ctx.config.self_update_check && !app.no_self_check).then(|| SelfCheck::start(&ctx.cache));
 
     match app.command {
-        Commands::Examples => crate::commands::examples::run(),
+        Commands::Examples(args) => crate::commands::examples::run(args),
+        Commands::Version(args) => crate::commands::version::run(args),
+        Commands::Parse(args) => crate::commands::parse::run(args),
+        Commands::Categories(args) => crate::commands::categories::run(args),
+        Commands::Resolve(args) => crate::commands::resolve::run(&ctx, args),
+        Commands::Compare(args) => crate::commands::compare::run(&ctx, args),
+        Commands::Migrate => crate::commands::migrate::run(&ctx),
+        Commands::VerifyInstall(args) => crate::commands::verify_install::run(&ctx, args),
         Commands::List(args) => crate::commands::list::run(&ctx, args),
         Commands::Latest(args) => crate::commands::latest::run(&ctx, args),
-        Commands::Download(args) => crate::commands::download::run(&ctx, args),
+        Commands::Download(args) => crate::commands::download::run(&ctx, *args),
+        Commands::Install(args) => crate::commands::install::run(&ctx, args),
+        Commands::ExportManifest(args) => crate::commands::export_manifest::run(&ctx, args),
+        Commands::FetchMatrix(args) => crate::commands::fetch_matrix::run(&ctx, args),
+        Commands::Url(args) => crate::commands::url::run(&ctx, args),
         Commands::Cache { action } => crate::commands::cache::run(&ctx, action),
+        Commands::Mirror { action } => crate::commands::mirror::run(&ctx, action),
+        Commands::Micro { action } => crate::commands::micro::run(&ctx, action),
+        Commands::History { action } => crate::commands::history::run(&ctx, action),
         Commands::CheckUpdate(args) => crate::commands::check_update::run(&ctx, args),
+        Commands::Outdated(args) => crate::commands::outdated::run(&ctx, args),
+        Commands::Sync(args) => crate::commands::sync::run(&ctx, args),
+        Commands::Api(args) => crate::commands::api::run(&ctx, args),
+        Commands::Doctor(args) => crate::commands::doctor::run(&ctx, args),
+        Commands::Gc(args) => crate::commands::gc::run(&ctx, args),
+        Commands::Serve(args) => crate::commands::serve::run(&ctx, args),
+    }
+
+    if let Some(self_check) = self_check {
+        self_check.finish();
     }
+
+    if spc::used_stale_cache() {
+        std::process::exit(STALE_EXIT_CODE);
+    }
+}
+
+/// The platform's data directory for spc-utils, e.g. `~/.local/share/spc-utils`.
+/// Installed state such as receipts lives here rather than in the cache, so
+/// clearing the cache never loses it.
+pub fn default_data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("spc-utils")
 }
 
 pub struct AppContext {
     pub cache: Cache,
+    pub config: Config,
+    pub data_dir: PathBuf,
+    pub receipts: Receipts,
+    pub registry: Registry,
+    pub base_url: Option<String>,
+    pub source: Source,
+    pub github_repo: Option<String>,
+    pub max_cache_age: Option<Duration>,
+    pub assume_yes: bool,
+    pub human: HumanFormat,
     pub active_os: &'static str,
     pub active_arch: &'static str,
 }
 
 impl AppContext {
-    pub fn new() -> Self {
+    /// Keeps the cache and installed state in the given directories. Without
+    /// them, the project config's `cache_dir`/`data_dir` are used, then the
+    /// platform defaults.
+    pub fn at(cache_dir: Option<PathBuf>, data_dir: Option<PathBuf>) -> Self {
         let active_os = std::env::consts::OS;
         let active_arch = std::env::consts::ARCH;
 
@@ -35,10 +135,90 @@ impl AppContext {
             panic!("Your OS {} is not supported", active_os)
         }
 
+        let config = Config::load().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+
+        let cache_dir = cache_dir
+            .or_else(|| config.cache_dir.clone())
+            .unwrap_or_else(Cache::default_dir);
+        let data_dir = data_dir
+            .or_else(|| config.data_dir.clone())
+            .unwrap_or_else(default_data_dir);
+
         AppContext {
-            cache: Cache::new(),
+            cache: Cache::at(cache_dir),
+            config,
+            receipts: Receipts::new(&data_dir),
+            registry: Registry::new(&data_dir.join("versions")),
+            data_dir,
+            base_url: None,
+            source: Source::Dl,
+            github_repo: None,
+            max_cache_age: None,
+            assume_yes: false,
+            human: HumanFormat::from_env(false),
             active_os,
             active_arch,
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_base_url(mut self, base_url: Option<String>) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_source(mut self, source: Source, github_repo: Option<String>) -> Self {
+        self.source = source;
+        self.github_repo = github_repo;
+        self
+    }
+
+    pub fn with_max_cache_age(mut self, max_cache_age: Option<Duration>) -> Self {
+        self.max_cache_age = max_cache_age;
+        self
+    }
+
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+
+    /// Shows the timestamps spc-utils records in UTC instead of local time.
+    pub fn with_utc(mut self, utc: bool) -> Self {
+        self.human = HumanFormat::from_env(utc);
+        self
+    }
+
+    /// Fills in defaults from the project config, such as the build type
+    /// for the target OS.
+    pub fn apply_defaults(&self, options: ApiOptions) -> ApiOptions {
+        let build_type = self.config.default_build_type(&options.os());
+        options.with_default_build_type(build_type)
+    }
+
+    /// Builds an API client carrying the global flags and project config.
+    pub fn api(&self, options: ApiOptions) -> Api {
+        let naming_scheme = self.config.naming_scheme(&options.category());
+        let mut api = Api::new(self.cache.clone(), self.apply_defaults(options))
+            .with_naming_scheme(naming_scheme)
+            .with_cancel(interrupt::cancel_token());
+        if let Some(base_url) = self.base_url.as_ref().or(self.config.mirrors.first()) {
+            api = api.with_base_url(base_url);
+        }
+        if self.source == Source::Github {
+            api = api.with_github_source(self.github_repo.clone());
+        }
+        if let Some(max_cache_age) = self.max_cache_age {
+            api = api.with_max_cache_age(max_cache_age);
+        }
+        if let Some(url) = &self.config.php_releases_url {
+            api = api.with_php_releases_url(url);
+        }
+        if let Some(seconds) = self.config.max_retry_wait {
+            api = api.with_max_retry_wait(Duration::from_secs(seconds));
+        }
+        api
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Local};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::Path};
+
+use crate::spc::BuildCategory;
+
+/// Everything that went into resolving a set of artifacts, so that another
+/// machine can fetch exactly the same files.
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub spc_utils_version: String,
+    pub generated_at: DateTime<Local>,
+    pub base_url: String,
+    pub github_repo: Option<String>,
+    /// The project's `.spc-utils.toml` as it was, if there was one.
+    pub config: Option<String>,
+    pub artifacts: Vec<ManifestArtifact>,
+}
+
+/// One resolved artifact: what was asked for and what it resolved to.
+#[derive(Serialize, Deserialize)]
+pub struct ManifestArtifact {
+    pub target: Option<String>,
+    pub category: BuildCategory,
+    /// The version as pinned, e.g. `8.4`; `None` when nothing was pinned.
+    pub requested: Option<String>,
+    pub version: Version,
+    pub os: String,
+    pub arch: String,
+    pub build_type: String,
+    pub name: String,
+    pub url: String,
+    pub sha256: String,
+    pub output: Option<std::path::PathBuf>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let manifest: Manifest = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))?;
+
+        let current = env!("CARGO_PKG_VERSION");
+        if manifest.spc_utils_version != current {
+            eprintln!(
+                "Warning: {} was written by spc-utils {}, this is {}",
+                path.display(),
+                manifest.spc_utils_version,
+                current
+            );
+        }
+        Ok(manifest)
+    }
+}
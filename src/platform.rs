@@ -0,0 +1,39 @@
+//! Windows console and path quirks. Everything here is a no-op elsewhere.
+
+use std::path::{Path, PathBuf};
+
+/// Paths this long or longer need the `\\?\` prefix on Windows.
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// Switches the console to UTF-8, so table borders and other non-ASCII output
+/// aren't garbled by the legacy code page.
+pub fn enable_utf8_console() {
+    #[cfg(windows)]
+    {
+        const CP_UTF8: u32 = 65001;
+        // SAFETY: takes a plain code page id; failure leaves the console as is.
+        unsafe {
+            windows_sys::Win32::System::Console::SetConsoleOutputCP(CP_UTF8);
+        }
+    }
+}
+
+/// `path` in a form Windows accepts beyond MAX_PATH: made absolute and given
+/// the `\\?\` (or `\\?\UNC\`) prefix when it is that long. Short paths, and
+/// every path on other platforms, are returned unchanged.
+pub fn long_path(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let text = absolute.to_string_lossy();
+        if text.len() >= MAX_PATH && !text.starts_with(r"\\?\") {
+            return match text.strip_prefix(r"\\") {
+                Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+                None => PathBuf::from(format!(r"\\?\{}", text)),
+            };
+        }
+    }
+
+    path.to_path_buf()
+}
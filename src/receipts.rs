@@ -0,0 +1,184 @@
+use chrono::{DateTime, Local};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::spc::sha256_file;
+
+/// What was installed for one PHP version, so the files can be checked
+/// against their hashes later.
+#[derive(Serialize, Deserialize)]
+pub struct Receipt {
+    pub version: Version,
+    pub installed_at: DateTime<Local>,
+    pub files: Vec<ReceiptFile>,
+    /// The one URL receipts recorded before each file kept its own; read
+    /// into files that lack one.
+    #[serde(default, rename = "url", skip_serializing)]
+    legacy_url: String,
+}
+
+/// A recorded file and the artifact it came from. Several artifacts of one
+/// version, such as its cli and fpm builds, share a receipt.
+#[derive(Serialize, Deserialize)]
+pub struct ReceiptFile {
+    pub path: PathBuf,
+    pub sha256: String,
+    #[serde(default)]
+    pub url: String,
+}
+
+/// Receipts kept as one JSON file per version. Changes are made under a lock
+/// on the directory and written atomically, so concurrent runs neither lose
+/// each other's updates nor read half-written receipts.
+#[derive(Clone)]
+pub struct Receipts {
+    dir: PathBuf,
+}
+
+impl Receipts {
+    /// Receipts kept under the data directory `data_dir`.
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            dir: data_dir.join("receipts"),
+        }
+    }
+
+    /// Receipts for `download --system` installs, shared by every user.
+    pub fn system() -> Self {
+        let dir = if cfg!(windows) {
+            std::env::var_os("ProgramData")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"))
+                .join("spc-utils")
+        } else {
+            PathBuf::from("/var/lib/spc-utils")
+        };
+
+        Self {
+            dir: dir.join("receipts"),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, version: &Version) -> PathBuf {
+        self.dir.join(format!("{}.json", version))
+    }
+
+    /// Waits for exclusive access to the receipts, held until the returned
+    /// file is dropped.
+    fn lock(&self) -> Result<fs::File, Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        let lock = fs::File::create(self.dir.join(".lock"))?;
+        lock.lock()?;
+        Ok(lock)
+    }
+
+    /// Hashes `files`, downloaded from or unpacked from `url`, and records
+    /// them in `version`'s receipt. Files already on the receipt under the
+    /// same path are replaced.
+    pub fn record(
+        &self,
+        version: &Version,
+        url: &str,
+        files: &[PathBuf],
+    ) -> Result<(), Box<dyn Error>> {
+        let _lock = self.lock()?;
+        let mut receipt = self.load(version)?.unwrap_or_else(|| Receipt {
+            version: version.clone(),
+            installed_at: Local::now(),
+            files: Vec::new(),
+            legacy_url: String::new(),
+        });
+        receipt.installed_at = Local::now();
+
+        for file in files {
+            let path = fs::canonicalize(file)?;
+            let sha256 = sha256_file(&path)?;
+            receipt.files.retain(|f| f.path != path);
+            receipt.files.push(ReceiptFile {
+                path,
+                sha256,
+                url: url.to_string(),
+            });
+        }
+
+        self.write(&receipt)
+    }
+
+    pub fn save(&self, receipt: &Receipt) -> Result<(), Box<dyn Error>> {
+        let _lock = self.lock()?;
+        self.write(receipt)
+    }
+
+    /// Replaces a receipt in one step; callers hold the lock.
+    fn write(&self, receipt: &Receipt) -> Result<(), Box<dyn Error>> {
+        let path = self.path(&receipt.version);
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_string_pretty(receipt)?)?;
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    pub fn load(&self, version: &Version) -> Result<Option<Receipt>, Box<dyn Error>> {
+        let path = self.path(version);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        let mut receipt: Receipt = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid receipt {}: {}", path.display(), e))?;
+        for file in &mut receipt.files {
+            if file.url.is_empty() {
+                file.url.clone_from(&receipt.legacy_url);
+            }
+        }
+        Ok(Some(receipt))
+    }
+
+    /// Drops the files under `dir` from `version`'s receipt, and the receipt
+    /// itself once nothing is left on it.
+    pub fn forget(&self, version: &Version, dir: &Path) -> Result<(), Box<dyn Error>> {
+        let _lock = self.lock()?;
+        let Some(mut receipt) = self.load(version)? else {
+            return Ok(());
+        };
+        receipt.files.retain(|file| !file.path.starts_with(dir));
+        if receipt.files.is_empty() {
+            fs::remove_file(self.path(version))?;
+            Ok(())
+        } else {
+            self.write(&receipt)
+        }
+    }
+
+    /// Every receipt, oldest version first.
+    pub fn load_all(&self) -> Result<Vec<Receipt>, Box<dyn Error>> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(Vec::new());
+        };
+
+        let mut receipts = Vec::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Some(version) = name
+                .strip_suffix(".json")
+                .and_then(|v| Version::parse(v).ok())
+                && let Some(receipt) = self.load(&version)?
+            {
+                receipts.push(receipt);
+            }
+        }
+
+        receipts.sort_by(|a, b| a.version.cmp(&b.version));
+        Ok(receipts)
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, Local};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::spc::ArtifactName;
+
+/// One artifact unpacked into the versions directory, under its name
+/// without extension.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Install {
+    pub version: Version,
+    pub build_type: Option<String>,
+    pub installed_at: DateTime<Local>,
+}
+
+/// What the versions directory holds, and which install of each build type
+/// is the active one: the one installed last, unless it is removed.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Installs {
+    pub installs: BTreeMap<String, Install>,
+    pub active: BTreeMap<String, String>,
+}
+
+impl Installs {
+    /// Records the install `name` and makes it the active one of its build
+    /// type.
+    pub fn add(&mut self, name: &str, artifact: &ArtifactName) {
+        let build_type = artifact.build_type.clone();
+        if let Some(build_type) = &build_type {
+            self.active.insert(build_type.clone(), name.to_string());
+        }
+        self.installs.insert(
+            name.to_string(),
+            Install {
+                version: artifact.version.clone(),
+                build_type,
+                installed_at: Local::now(),
+            },
+        );
+    }
+
+    /// Forgets the install `name`; no install of its build type is active
+    /// afterwards if it was.
+    pub fn remove(&mut self, name: &str) {
+        self.installs.remove(name);
+        self.active.retain(|_, active| active != name);
+    }
+
+    pub fn is_active(&self, name: &str) -> bool {
+        self.active.values().any(|active| active == name)
+    }
+}
+
+/// The registry of the versions directory, kept in `registry.json` inside
+/// it. Like receipts, it is changed under a lock and replaced atomically, and
+/// it is reconciled with the directories on disk whenever it is read.
+#[derive(Clone)]
+pub struct Registry {
+    dir: PathBuf,
+}
+
+impl Registry {
+    /// The registry of the versions directory `dir`.
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// The versions directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join("registry.json")
+    }
+
+    /// Waits for exclusive access to the registry, held until the returned
+    /// file is dropped.
+    fn lock(&self) -> Result<fs::File, Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        let lock = fs::File::create(self.dir.join(".lock"))?;
+        lock.lock()?;
+        Ok(lock)
+    }
+
+    /// The installs as they are on disk right now.
+    pub fn load(&self) -> Result<Installs, Box<dyn Error>> {
+        let path = self.path();
+        let mut installs: Installs = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("Invalid registry {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Installs::default(),
+            Err(e) => return Err(e.into()),
+        };
+        self.reconcile(&mut installs);
+        Ok(installs)
+    }
+
+    /// Applies `change` to the installs under the lock and saves the result,
+    /// so concurrent runs see each other's changes.
+    pub fn update<T>(&self, change: impl FnOnce(&mut Installs) -> T) -> Result<T, Box<dyn Error>> {
+        let _lock = self.lock()?;
+        let mut installs = self.load()?;
+        let result = change(&mut installs);
+
+        let path = self.path();
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_string_pretty(&installs)?)?;
+        fs::rename(&partial, &path)?;
+        Ok(result)
+    }
+
+    /// Drops installs whose directory is gone, and picks up directories that
+    /// were unpacked before the registry existed.
+    fn reconcile(&self, installs: &mut Installs) {
+        let gone: Vec<String> = installs
+            .installs
+            .keys()
+            .filter(|name| !self.dir.join(name).is_dir())
+            .cloned()
+            .collect();
+        for name in gone {
+            installs.remove(&name);
+        }
+
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            // Hidden directories are installs still being staged.
+            if name.starts_with('.')
+                || installs.installs.contains_key(&name)
+                || !entry.file_type().is_ok_and(|t| t.is_dir())
+            {
+                continue;
+            }
+            // Install directories are artifact names without the extension.
+            let Some(artifact) = ArtifactName::parse(&format!("{}.tar.gz", name)) else {
+                continue;
+            };
+            let installed_at = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map(DateTime::from)
+                .unwrap_or_else(|_| Local::now());
+            installs.installs.insert(
+                name,
+                Install {
+                    version: artifact.version,
+                    build_type: artifact.build_type,
+                    installed_at,
+                },
+            );
+        }
+    }
+}
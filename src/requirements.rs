@@ -0,0 +1,107 @@
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+use crate::{
+    cli::validate_version_spec,
+    spc::{CategoryProfile, VersionSpec},
+};
+
+/// What a project needs from its PHP build, usually read from a
+/// `requirements.toml`:
+///
+/// ```toml
+/// php = "8.3"
+/// extensions = ["gd", "redis"]
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Requirements {
+    pub php: Option<String>,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+}
+
+impl Requirements {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let requirements: Requirements =
+            toml::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+
+        if let Some(php) = &requirements.php {
+            validate_version_spec(php)
+                .map_err(|e| format!("Invalid php in {}: {}", path.display(), e))?;
+        }
+        Ok(requirements)
+    }
+
+    /// Requirements from an optional file plus extensions given as flags.
+    pub fn collect(path: Option<&Path>, extensions: &[String]) -> Result<Self, String> {
+        let mut requirements = match path {
+            Some(path) => Self::load(path)?,
+            None => Self::default(),
+        };
+        for extension in extensions {
+            if !requirements
+                .extensions
+                .iter()
+                .any(|ext| ext.eq_ignore_ascii_case(extension))
+            {
+                requirements.extensions.push(extension.clone());
+            }
+        }
+        Ok(requirements)
+    }
+
+    pub fn version_spec(&self) -> Option<VersionSpec> {
+        self.php
+            .as_deref()
+            .map(|v| validate_version_spec(v).expect("php is validated on load"))
+    }
+
+    fn extensions(&self) -> Vec<&str> {
+        self.extensions.iter().map(String::as_str).collect()
+    }
+
+    /// Fails when `profile` lacks any required extension, naming them.
+    pub fn check(&self, profile: &CategoryProfile) -> Result<(), String> {
+        let lacking = profile.lacking(&self.extensions());
+        if lacking.is_empty() {
+            return Ok(());
+        }
+
+        Err(format!(
+            "The {} category doesn't include {}; `spc-utils categories --with {}` names one that does",
+            profile.category,
+            lacking.join(", "),
+            self.extensions.join(",")
+        ))
+    }
+
+    /// The smallest category published for `os` that has every required
+    /// extension. The error names what each candidate lacks.
+    pub fn smallest_category(&self, os: &str) -> Result<&'static CategoryProfile, String> {
+        let required = self.extensions();
+        if let Some(profile) = CategoryProfile::smallest_with(&required, os) {
+            return Ok(profile);
+        }
+
+        let mut message = format!(
+            "No {} category includes all of: {}",
+            os,
+            required.join(", ")
+        );
+        // Categories without a known extension list can't be judged.
+        for profile in CategoryProfile::all()
+            .iter()
+            .filter(|profile| profile.is_available_on(os) && !profile.extensions().is_empty())
+        {
+            message.push_str(&format!(
+                "\n  {} lacks {}",
+                profile.category,
+                profile.lacking(&required).join(", ")
+            ));
+        }
+        Err(message)
+    }
+}
@@ -0,0 +1,104 @@
+//! Opt-in notice when a newer spc-utils release is out.
+
+use std::{
+    fs,
+    path::PathBuf,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::spc::Cache;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/jhavenz/spc-utils/releases/latest";
+const RELEASES_PAGE: &str = "https://github.com/jhavenz/spc-utils/releases";
+const STATE_FILE: &str = "self-check.json";
+const CHECK_INTERVAL: TimeDelta = TimeDelta::days(1);
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The outcome of the last check, kept in the cache directory.
+#[derive(Serialize, Deserialize)]
+struct State {
+    checked_at: DateTime<Utc>,
+    latest: Option<Version>,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+/// A check started before the command runs and reported after it.
+pub struct SelfCheck {
+    known: Option<State>,
+    refresh: Option<JoinHandle<Option<State>>>,
+}
+
+impl SelfCheck {
+    /// Uses the result cached by an earlier run, and refreshes it in the
+    /// background when it is more than a day old.
+    pub fn start(cache: &Cache) -> Self {
+        let path = cache.cache_dir().join(STATE_FILE);
+        let known: Option<State> = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+
+        let due = known
+            .as_ref()
+            .is_none_or(|state| Utc::now() - state.checked_at >= CHECK_INTERVAL);
+        let refresh = due.then(|| thread::spawn(move || refresh(path)));
+
+        Self { known, refresh }
+    }
+
+    /// Prints a one-line hint when a newer release is known. A refresh still
+    /// in flight is not waited for; its result shows on the next run.
+    pub fn finish(self) {
+        let fresh = self
+            .refresh
+            .filter(|refresh| refresh.is_finished())
+            .and_then(|refresh| refresh.join().ok().flatten());
+        let Some(latest) = fresh.or(self.known).and_then(|state| state.latest) else {
+            return;
+        };
+
+        let current = Version::parse(env!("CARGO_PKG_VERSION")).expect("crate version is semver");
+        if latest > current {
+            eprintln!(
+                "spc-utils {} is available (you have {}): {}",
+                latest, current, RELEASES_PAGE
+            );
+        }
+    }
+}
+
+/// Looks up the latest release and records it. Failed lookups are recorded
+/// too, so an unreachable server is retried daily rather than on every run.
+fn refresh(path: PathBuf) -> Option<State> {
+    let state = State {
+        checked_at: Utc::now(),
+        latest: fetch_latest().ok(),
+    };
+
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(&path, serde_json::to_vec(&state).ok()?);
+    Some(state)
+}
+
+fn fetch_latest() -> Result<Version, Box<dyn std::error::Error>> {
+    let release: Release = reqwest::blocking::Client::builder()
+        .user_agent(concat!("spc-utils/", env!("CARGO_PKG_VERSION")))
+        .timeout(TIMEOUT)
+        .build()?
+        .get(LATEST_RELEASE_URL)
+        .send()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(Version::parse(release.tag_name.trim_start_matches('v'))?)
+}
@@ -0,0 +1,49 @@
+//! Detached minisign signatures published next to artifacts, checked by
+//! `download --verify-signature`.
+
+use std::{error::Error, fs::File, io::Read, path::Path};
+
+use minisign_verify::{PublicKey, Signature};
+
+/// Extensions a signature may be published under, in the order they are tried.
+pub const SIGNATURE_EXTENSIONS: [&str; 2] = [".minisig", ".sig"];
+
+/// A minisign public key, given as the base64 line of a `minisign.pub` file
+/// or as the whole file.
+pub fn parse_public_key(key: &str) -> Result<PublicKey, String> {
+    let key = key.trim();
+    let parsed = match key.lines().count() {
+        1 => PublicKey::from_base64(key),
+        _ => PublicKey::decode(key),
+    };
+    parsed.map_err(|e| format!("Invalid minisign public key: {}", e))
+}
+
+/// Checks `path` against the detached `signature` made with `key`, and
+/// returns the signature's trusted comment.
+pub fn verify_file(
+    path: &Path,
+    signature: &str,
+    key: &PublicKey,
+) -> Result<String, Box<dyn Error>> {
+    let signature = Signature::decode(signature)
+        .map_err(|e| format!("the published signature can't be read: {}", e))?;
+    let mut verifier = key
+        .verify_stream(&signature)
+        .map_err(|e| format!("the signature doesn't fit the trusted key: {}", e))?;
+
+    let mut file = File::open(path)?;
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        verifier.update(&buf[..read]);
+    }
+    verifier
+        .finalize()
+        .map_err(|e| format!("the signature doesn't match: {}", e))?;
+
+    Ok(signature.trusted_comment().to_string())
+}
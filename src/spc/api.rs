@@ -1,21 +1,53 @@
-use reqwest::blocking;
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use reqwest::{
+    StatusCode, blocking,
+    header::{CONTENT_LENGTH, RANGE, RETRY_AFTER},
+};
 use semver::Version;
-use std::env::consts::{ARCH, OS};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap, hash_map::RandomState},
+    env::consts::{ARCH, OS},
+    error::Error,
+    fs,
+    hash::{BuildHasher, Hasher},
+    io::{self, IsTerminal, Read, Seek, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
-use super::{BuildCategory, Cache, SpcJsonResponse};
+use crate::{interrupt::PartialFile, platform::long_path};
+
+use super::{
+    ArtifactFilter, BuildCategory, Cache, CancelToken, Cancelled, Freshness, HistoryEvent,
+    ListingHistory, NamingScheme, Republication, Source, SpcJsonResponse, VersionSpec,
+    bundled::bundled_listing,
+    cache::summarize_changes,
+    digest::sha256_file,
+    expires_at,
+    progress::{copy_with_progress, format_mb},
+    security::{PhpRelease, php_releases_url, security_releases},
+    source::{github_listing, github_releases_url},
+};
 
 pub struct ApiOptions {
     category: Option<BuildCategory>,
-    version: Option<Version>,
+    version: Option<VersionSpec>,
     os: Option<String>,
     arch: Option<String>,
     build_type: Option<String>,
+    default_build_type: Option<String>,
 }
 
 impl ApiOptions {
     pub fn new(
         category: Option<BuildCategory>,
-        version: Option<Version>,
+        version: Option<VersionSpec>,
         os: Option<String>,
         arch: Option<String>,
         build_type: Option<String>,
@@ -26,25 +58,31 @@ impl ApiOptions {
             os,
             arch,
             build_type,
+            default_build_type: None,
         }
     }
 
+    /// Build type used when none is requested, instead of `cli`.
+    pub fn with_default_build_type(mut self, build_type: Option<String>) -> Self {
+        self.default_build_type = build_type;
+        self
+    }
+
     fn to_url(&self, base_url: &str) -> String {
         format!("{}/{}?format=json", base_url, self.category_path())
     }
 
-    fn to_download_url(&self, base_url: &str) -> String {
-        format!("{}/{}/{}", base_url, self.category_path(), self.file_name())
+    /// Listing file shipped by static mirrors, which can't answer `?format=json`.
+    fn to_index_url(&self, base_url: &str) -> String {
+        format!("{}/{}/index.json", base_url, self.category_path())
     }
 
-    fn category_path(&self) -> String {
-        match self.category() {
-            BuildCategory::Bulk => "bulk".to_string(),
-            BuildCategory::Common => "common".to_string(),
-            BuildCategory::Minimal => "minimal".to_string(),
-            BuildCategory::WinMin => "windows/spc-min".to_string(),
-            BuildCategory::WinMax => "windows/spc-max".to_string(),
-        }
+    fn to_artifact_url(&self, base_url: &str, name: &str) -> String {
+        format!("{}/{}/{}", base_url, self.category_path(), name)
+    }
+
+    fn category_path(&self) -> &'static str {
+        self.category().url_path()
     }
 
     pub fn category(&self) -> BuildCategory {
@@ -60,23 +98,23 @@ impl ApiOptions {
     /// common -> php-8.0.30-cli-linux-x86_64.tar.gz, php-8.1.23-fpm-linux-x86_64.tar.gz, php-8.1.25-micro-linux-aarch64.tar.gz
     /// bulk -> php-8.0.30-cli-linux-x86_64.tar.gz, php-8.1.26-fpm-linux-aarch64.tar.gz, php-8.1.27-micro-linux-aarch64.tar.gz
     ///
-    fn file_name(&self) -> String {
+    fn file_name(&self, scheme: NamingScheme) -> String {
         let version = self
             .version
             .as_ref()
+            .and_then(VersionSpec::exact)
             .map(|v| v.to_string())
             .unwrap_or_default();
         match self.category() {
             BuildCategory::WinMin | BuildCategory::WinMax => {
-                format!("php-{}-{}-win.zip", version, self.build_type())
-            }
-            BuildCategory::Bulk | BuildCategory::Common | BuildCategory::Minimal => format!(
-                "php-{}-{}-{}-{}.tar.gz",
-                version,
-                self.build_type(),
-                self.os(),
-                self.arch()
-            ),
+                scheme.file_name(&version, &self.build_type(), "win", "")
+            }
+            BuildCategory::Bulk
+            | BuildCategory::Common
+            | BuildCategory::Minimal
+            | BuildCategory::Frankenphp => {
+                scheme.file_name(&version, &self.build_type(), &self.os(), &self.arch())
+            }
         }
     }
 
@@ -89,10 +127,17 @@ impl ApiOptions {
     }
 
     pub fn build_type(&self) -> String {
-        self.build_type.clone().unwrap_or_else(|| "cli".to_string())
+        // The frankenphp category ships nothing else.
+        let category_default =
+            matches!(self.category(), BuildCategory::Frankenphp).then(|| "frankenphp".to_string());
+        self.build_type
+            .clone()
+            .or(category_default)
+            .or_else(|| self.default_build_type.clone())
+            .unwrap_or_else(|| "cli".to_string())
     }
 
-    pub fn version_bound(&self) -> Option<&Version> {
+    pub fn version_bound(&self) -> Option<&VersionSpec> {
         self.version.as_ref()
     }
 
@@ -108,114 +153,1283 @@ impl ApiOptions {
     fn with_version(&self, version: &Version) -> Self {
         Self {
             category: self.category.clone(),
-            version: Some(version.clone()),
+            version: Some(VersionSpec::Exact(version.clone())),
             os: self.os.clone(),
             arch: self.arch.clone(),
             build_type: self.build_type.clone(),
+            default_build_type: self.default_build_type.clone(),
         }
     }
 }
 
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+static USED_STALE_CACHE: AtomicBool = AtomicBool::new(false);
+
+/// Whether any listing in this run was served from an expired cache because
+/// the server could not be reached.
+pub fn used_stale_cache() -> bool {
+    USED_STALE_CACHE.load(Ordering::Relaxed)
+}
+
+/// Listings already resolved by this process, by listing URL, with when they
+/// stop being usable. Repeated queries for a category neither re-read the
+/// cache nor refetch.
+type Listings = BTreeMap<String, (Vec<SpcJsonResponse>, Freshness, DateTime<Local>)>;
+static LISTINGS: Mutex<Listings> = Mutex::new(BTreeMap::new());
+
+/// One lock per listing URL, held while it is fetched, so that threads
+/// asking for the same listing wait for a single fetch while others go on.
+static FETCHING: Mutex<BTreeMap<String, Arc<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+
+static RATE_LIMITED: AtomicUsize = AtomicUsize::new(0);
+
+/// How many responses in this run asked us to slow down.
+pub fn rate_limit_count() -> usize {
+    RATE_LIMITED.load(Ordering::Relaxed)
+}
+
+/// Checksum and signature files servers may publish next to an artifact.
+pub const COMPANION_EXTENSIONS: [&str; 2] = [".sha256", ".sig"];
+
+/// The build [`Api::resolve`] settled on, and how it got there.
+pub struct Resolution {
+    pub version: Version,
+    /// File name of the matched artifact, as listed
+    pub artifact: String,
+    pub url: String,
+    /// Listing entries that matched every filter
+    pub candidates: usize,
+    /// Whether the listing came from the server, the cache or the bundle
+    pub freshness: Freshness,
+    /// Filters that weren't requested, with the value used instead
+    pub defaults: Vec<(&'static str, String)>,
+}
+
+/// How a mirror answered [`Api::probe`].
+pub struct Probe {
+    /// Until the response started
+    pub latency: Duration,
+    pub bytes: u64,
+    /// Until the last byte arrived
+    pub elapsed: Duration,
+}
+
+impl Probe {
+    /// Transfer speed in bytes per second, counted from the first byte.
+    pub fn throughput(&self) -> f64 {
+        let transfer = (self.elapsed - self.latency).as_secs_f64().max(1e-6);
+        self.bytes as f64 / transfer
+    }
+}
+
+/// The digest in a published `.sha256` file: either just the digest or
+/// `sha256sum` output, `<digest>  <file>`.
+pub fn parse_sha256(contents: &[u8]) -> Option<String> {
+    let digest = String::from_utf8_lossy(contents)
+        .split_whitespace()
+        .next()?
+        .to_ascii_lowercase();
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit())).then_some(digest)
+}
+
+/// Where artifacts are resolved and downloaded from unless `--base-url` says otherwise.
+pub const DEFAULT_BASE_URL: &str = "https://dl.static-php.dev/static-php-cli";
+
+const DEFAULT_PHP_RELEASES_URL: &str = "https://www.php.net/releases/index.php";
+const DEFAULT_MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+pub const DEFAULT_DOWNLOAD_RETRIES: u32 = 3;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 pub struct Api {
     client: blocking::Client,
     base_url: String,
     options: ApiOptions,
     cache: Cache,
     no_cache: bool,
+    any_cache_age: bool,
+    copy: bool,
+    cancel: CancelToken,
+    explain: bool,
+    progress: bool,
+    verify: bool,
+    released_before: Option<DateTime<Utc>>,
+    max_retry_wait: Duration,
+    retries: u32,
+    retry_delay: Duration,
+    max_cache_age: Option<Duration>,
+    naming_scheme: Option<NamingScheme>,
+    source: Source,
+    github_repo: Option<String>,
+    php_releases_url: String,
 }
 
 impl Api {
     pub fn new(cache: Cache, options: ApiOptions) -> Self {
         Self {
             options,
-            client: blocking::Client::new(),
-            base_url: "https://dl.static-php.dev/static-php-cli".to_string(),
+            client: blocking::Client::builder()
+                .user_agent(concat!("spc-utils/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .expect("HTTP client configuration is valid"),
+            base_url: DEFAULT_BASE_URL.to_string(),
             cache,
             no_cache: false,
+            any_cache_age: false,
+            copy: false,
+            cancel: CancelToken::default(),
+            explain: false,
+            progress: true,
+            verify: true,
+            released_before: None,
+            max_retry_wait: DEFAULT_MAX_RETRY_WAIT,
+            retries: DEFAULT_DOWNLOAD_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            max_cache_age: None,
+            naming_scheme: None,
+            source: Source::Dl,
+            github_repo: None,
+            php_releases_url: DEFAULT_PHP_RELEASES_URL.to_string(),
         }
     }
 
+    /// Resolves from GitHub releases of `repo` (`owner/name`) instead of the
+    /// download server. Their listings are cached separately.
+    pub fn with_github_source(mut self, repo: Option<String>) -> Self {
+        self.source = Source::Github;
+        self.github_repo = repo;
+        self.cache = self.cache.scoped("github");
+        self
+    }
+
+    /// Points metadata and downloads at a mirror instead of dl.static-php.dev.
+    /// `file://` URLs read a local mirror produced by `sync`.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Where php.net's release feed is read from, e.g. a `file://` copy on
+    /// machines without internet access.
+    pub fn with_php_releases_url(mut self, url: &str) -> Self {
+        self.php_releases_url = url.to_string();
+        self
+    }
+
+    /// Caps how long a rate-limited request may wait before retrying.
+    pub fn with_max_retry_wait(mut self, max_retry_wait: Duration) -> Self {
+        self.max_retry_wait = max_retry_wait;
+        self
+    }
+
+    /// How often an interrupted download is retried, resuming where it left
+    /// off when the server supports ranges.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// The wait before the first download retry; later ones back off
+    /// exponentially, with jitter.
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Builds artifact names with `scheme` instead of detecting it from the
+    /// listing.
+    pub fn with_naming_scheme(mut self, scheme: Option<NamingScheme>) -> Self {
+        self.naming_scheme = scheme;
+        self
+    }
+
+    /// Treats cached data older than `max_cache_age` as expired.
+    pub fn with_max_cache_age(mut self, max_cache_age: Duration) -> Self {
+        self.max_cache_age = Some(max_cache_age);
+        self
+    }
+
     pub fn with_no_cache(mut self, no_cache: bool) -> Self {
         self.no_cache = no_cache;
         self
     }
 
-    pub fn fetch_latest_version(&self) -> (Version, bool) {
-        let os_needle = self.options.os();
-        let arch_needle = self.options.arch();
-        let build_type_needle = self.options.build_type();
-        let version_bound = self.options.version_bound();
+    /// Answers from a cached listing however old it is, only asking the
+    /// server when nothing is cached.
+    pub fn with_any_cache_age(mut self, any_cache_age: bool) -> Self {
+        self.any_cache_age = any_cache_age;
+        self
+    }
 
-        let (data, from_cache) = self.fetch_versions().unwrap();
-        let latest_version = data
-            .into_iter()
-            .filter(|resp| {
-                let version_match = if let Some(v) = resp.version() {
-                    if let Some(bound) = version_bound {
-                        v.major == bound.major && v.minor == bound.minor
-                    } else {
-                        true
-                    }
-                } else {
-                    false
-                };
+    /// Copies cached artifacts into place instead of hardlinking them.
+    pub fn with_copy(mut self, copy: bool) -> Self {
+        self.copy = copy;
+        self
+    }
 
-                let name_match = match self.options.category() {
-                    BuildCategory::WinMin | BuildCategory::WinMax => {
-                        resp.name.contains(&build_type_needle) && resp.name.ends_with("-win.zip")
-                    }
-                    _ => {
-                        resp.name.contains(&os_needle)
-                            && resp.name.contains(&arch_needle)
-                            && resp.name.contains(&build_type_needle)
+    /// Lets `cancel` stop downloads in progress, which then remove the file
+    /// they were writing.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Whether the operation this client serves has been cancelled, for
+    /// callers looping over many artifacts to stop between them.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Traces each step of version resolution to stderr.
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// Whether downloads draw a progress line. Turned off when several run at
+    /// once, since their lines would overwrite each other.
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Only consider artifacts published before `cutoff`, e.g. for the stable
+    /// channel.
+    pub fn with_released_before(mut self, cutoff: Option<DateTime<Utc>>) -> Self {
+        self.released_before = cutoff;
+        self
+    }
+
+    /// Whether downloads are checked against the `.sha256` files the server
+    /// publishes next to artifacts. On by default.
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Picks the newest build matching the options from the listing.
+    pub fn resolve(&self) -> Result<Resolution, Box<dyn Error>> {
+        let filter = ArtifactFilter::new(&self.options).with_released_before(self.released_before);
+        self.explain_options();
+
+        let (data, freshness) = self.fetch_versions()?;
+        self.explain(format!(
+            "Listing has {} entries{}",
+            data.len(),
+            freshness.annotation()
+        ));
+
+        // A single pass keeping the best match so far; rejection reasons are
+        // only formatted when they will be shown.
+        let mut rejections: BTreeMap<String, usize> = BTreeMap::new();
+        let mut matched = 0;
+        let mut best: Option<(Version, &str)> = None;
+        for resp in &data {
+            match filter.check(resp) {
+                Ok(version) => {
+                    matched += 1;
+                    if best.as_ref().is_none_or(|(newest, _)| version > *newest) {
+                        best = Some((version, &resp.name));
                     }
-                };
+                }
+                Err(reason) if self.explain => {
+                    *rejections.entry(reason.to_string()).or_default() += 1
+                }
+                Err(_) => {}
+            }
+        }
 
-                version_match && name_match
-            })
-            .filter_map(|resp| resp.version())
-            .max()
-            .expect("No spc versions found after fetching");
+        for (reason, count) in &rejections {
+            self.explain(format!("Rejected {} entries: {}", count, reason));
+        }
+        self.explain(format!("{} entries matched every filter", matched));
+
+        let Some((version, name)) = best else {
+            let options = &self.options;
+            let bound = options
+                .version
+                .as_ref()
+                .and_then(VersionSpec::bound)
+                .map(|bound| format!(" {}", bound))
+                .unwrap_or_default();
+            return Err(format!(
+                "No {} build for {}-{}{} in the {} listing",
+                options.build_type(),
+                options.os(),
+                options.arch(),
+                bound,
+                options.category()
+            )
+            .into());
+        };
+        self.explain(format!(
+            "Selected {} ({}), the highest matching version",
+            version, name
+        ));
+
+        let options = &self.options;
+        let defaults = [
+            (
+                "category",
+                options.category.is_none(),
+                options.category().to_string(),
+            ),
+            ("os", options.os.is_none(), options.os()),
+            ("arch", options.arch.is_none(), options.arch()),
+            (
+                "build_type",
+                options.build_type.is_none(),
+                options.build_type(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, defaulted, _)| *defaulted)
+        .map(|(field, _, value)| (field, value))
+        .collect();
+
+        Ok(Resolution {
+            url: self.artifact_url(name),
+            artifact: name.to_string(),
+            version,
+            candidates: matched,
+            freshness,
+            defaults,
+        })
+    }
+
+    /// Reports the filters in effect and which of them fell back to defaults.
+    fn explain_options(&self) {
+        let origin = |given: bool| if given { "requested" } else { "default" };
+        let options = &self.options;
+
+        self.explain(format!(
+            "Category: {} ({})",
+            options.category(),
+            origin(options.category.is_some())
+        ));
+        self.explain(format!(
+            "OS needle: {} ({})",
+            options.os(),
+            origin(options.os.is_some())
+        ));
+        self.explain(format!(
+            "Arch needle: {} ({})",
+            options.arch(),
+            origin(options.arch.is_some())
+        ));
+        let build_type_origin = match (&options.build_type, &options.default_build_type) {
+            (Some(_), _) => "requested",
+            (None, Some(_)) => "configured default",
+            (None, None) => "default",
+        };
+        self.explain(format!(
+            "Build type needle: {} ({})",
+            options.build_type(),
+            build_type_origin
+        ));
+        match options.version.as_ref().and_then(VersionSpec::bound) {
+            Some(bound) => self.explain(format!("Version bound: {}", bound)),
+            None => self.explain("Version bound: none (any version)".to_string()),
+        }
+        self.explain(format!("Listing URL: {}", self.listing_url()));
+    }
 
-        (latest_version, from_cache)
+    fn listing_url(&self) -> String {
+        match (self.source, &self.github_repo) {
+            (Source::Github, Some(repo)) => github_releases_url(repo),
+            _ => self.options.to_url(&self.base_url),
+        }
+    }
+
+    fn explain(&self, message: String) {
+        if self.explain {
+            eprintln!("explain: {}", message);
+        }
+    }
+
+    /// The active category's listing, from memory when this process already
+    /// resolved it, otherwise from the cache or the server.
+    pub fn fetch_versions(&self) -> Result<(Vec<SpcJsonResponse>, Freshness), Box<dyn Error>> {
+        let key = self.listing_url();
+        if let Some(resolved) = self.memoized(&key) {
+            return Ok(resolved);
+        }
+
+        let fetching = FETCHING
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let _fetching = fetching.lock().unwrap_or_else(|e| e.into_inner());
+        // Another thread may have fetched it while this one waited.
+        if let Some(resolved) = self.memoized(&key) {
+            return Ok(resolved);
+        }
+
+        let (data, freshness) = self.load_versions()?;
+        // A stale listing is worth another try on the next query.
+        let valid_until = match freshness {
+            Freshness::Fresh => self.cache_expiry(Local::now()),
+            Freshness::Cached(_, expires) => Some(expires),
+            Freshness::Stale(_) | Freshness::Bundled(_) => None,
+        };
+        if let Some(valid_until) = valid_until {
+            LISTINGS
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(key, (data.clone(), freshness, valid_until));
+        }
+        Ok((data, freshness))
+    }
+
+    /// The listing at `key` if this process resolved it and it is still
+    /// usable. The lock is only held to look it up.
+    fn memoized(&self, key: &str) -> Option<(Vec<SpcJsonResponse>, Freshness)> {
+        let listings = LISTINGS.lock().unwrap_or_else(|e| e.into_inner());
+        let (data, freshness, valid_until) = listings.get(key)?;
+        // `--no-cache` wants what the server says, not what it said earlier.
+        (*valid_until > Local::now() && (!self.no_cache || *freshness == Freshness::Fresh))
+            .then(|| (data.clone(), *freshness))
     }
 
-    pub fn fetch_versions(&self) -> Result<(Vec<SpcJsonResponse>, bool), reqwest::Error> {
+    fn load_versions(&self) -> Result<(Vec<SpcJsonResponse>, Freshness), Box<dyn Error>> {
         let category = self.options.category();
+        // Local mirrors are as fast as the cache, so they bypass it entirely.
+        let use_cache = !self.is_local();
 
-        if !self.no_cache
-            && self.cache.is_valid(&category)
+        if use_cache
+            && !self.no_cache
+            && let Some(fetched) = self.cache.fetched_at(&category)
             && let Some(cached_data) = self.cache.read(&category)
         {
-            return Ok((cached_data, true));
+            match self.cache_expiry(fetched) {
+                Some(expires) => return Ok((cached_data, Freshness::Cached(fetched, expires))),
+                None if self.any_cache_age => return Ok((cached_data, Freshness::Stale(fetched))),
+                None => {}
+            }
         }
 
-        let url = self.options.to_url(&self.base_url);
-        let response = self.client.get(url).send()?;
-        let data: Vec<SpcJsonResponse> = response.json()?;
+        let data = match self.fetch_listing() {
+            Ok(data) => data,
+            Err(e) => {
+                // An expired listing beats no listing when the server is down.
+                if use_cache
+                    && let Some(cached_data) = self.cache.read(&category)
+                    && let Some(fetched) = self.cache.fetched_at(&category)
+                {
+                    let freshness = Freshness::Stale(fetched);
+                    eprintln!(
+                        "Warning: {}; using the cached listing{}",
+                        e,
+                        freshness.annotation()
+                    );
+                    USED_STALE_CACHE.store(true, Ordering::Relaxed);
+                    return Ok((cached_data, freshness));
+                }
+                // First runs without network get the snapshot built into the
+                // binary, which only describes the official server.
+                if self.source == Source::Dl
+                    && self.base_url == DEFAULT_BASE_URL
+                    && let Some((data, taken)) = bundled_listing(&category)
+                {
+                    let freshness = Freshness::Bundled(taken);
+                    eprintln!(
+                        "Warning: {}; using the listing bundled with spc-utils{}",
+                        e,
+                        freshness.annotation()
+                    );
+                    USED_STALE_CACHE.store(true, Ordering::Relaxed);
+                    return Ok((data, freshness));
+                }
+                return Err(e);
+            }
+        };
 
-        if let Err(e) = self.cache.write(&category, &data) {
+        if use_cache {
+            let changes = self.store_listing(&category, &data);
+            if !changes.is_empty() {
+                eprintln!(
+                    "Refreshed the {} listing: {}",
+                    category,
+                    summarize_changes(&changes)
+                );
+            }
+        }
+
+        Ok((data, Freshness::Fresh))
+    }
+
+    /// Fetches the listing from the server whatever the cache holds, and
+    /// returns how many entries it has and what changed since the cached one.
+    pub fn refresh_listing(&self) -> Result<(usize, Vec<HistoryEvent>), Box<dyn Error>> {
+        let data = self.fetch_listing()?;
+        let changes = if self.is_local() {
+            Vec::new()
+        } else {
+            self.store_listing(&self.options.category(), &data)
+        };
+        Ok((data.len(), changes))
+    }
+
+    /// Replaces the cached listing, recording what changed on the way.
+    fn store_listing(
+        &self,
+        category: &BuildCategory,
+        data: &[SpcJsonResponse],
+    ) -> Vec<HistoryEvent> {
+        self.cache.detect_republications(category, data);
+        let changes = self.cache.record_history(category, data);
+
+        if let Err(e) = self.cache.write(category, data) {
+            eprintln!("Warning: Failed to write cache: {}", e);
+        }
+        changes
+    }
+
+    /// When a cache entry fetched at `fetched` stops being usable, or `None`
+    /// once it has: at the end of its day, or earlier with `--max-cache-age`.
+    fn cache_expiry(&self, fetched: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut expires = expires_at(fetched);
+        if let Some(max_age) = self
+            .max_cache_age
+            .and_then(|age| TimeDelta::from_std(age).ok())
+        {
+            expires = expires.min(fetched + max_age);
+        }
+
+        (expires > Local::now()).then_some(expires)
+    }
+
+    fn fetch_listing(&self) -> Result<Vec<SpcJsonResponse>, Box<dyn Error>> {
+        match self.source {
+            Source::Github => {
+                let repo = self
+                    .github_repo
+                    .as_deref()
+                    .ok_or("--source github requires --github-repo <owner/name>")?;
+                Ok(github_listing(self.get_json(&github_releases_url(repo))?))
+            }
+            Source::Dl => {
+                let url = self.options.to_url(&self.base_url);
+                match self.get_listing(&url) {
+                    Ok(data) => Ok(data),
+                    Err(e) => self
+                        .get_listing(&self.options.to_index_url(&self.base_url))
+                        .map_err(|_| e),
+                }
+            }
+        }
+    }
+
+    /// Fetches any JSON document under the base URL, sharing the listing cache
+    /// rules. Paths without a query string get `?format=json` appended.
+    pub fn fetch_raw(&self, path: &str) -> Result<(serde_json::Value, bool), Box<dyn Error>> {
+        let path = path.trim_start_matches('/');
+        let path = if path.contains('?') {
+            path.to_string()
+        } else {
+            format!("{}?format=json", path)
+        };
+
+        let use_cache = !self.is_local();
+
+        if use_cache
+            && !self.no_cache
+            && self
+                .cache
+                .raw_fetched_at(&path)
+                .and_then(|fetched| self.cache_expiry(fetched))
+                .is_some()
+            && let Some(cached) = self.cache.read_raw(&path)
+        {
+            return Ok((cached, true));
+        }
+
+        let url = format!("{}/{}", self.base_url, path);
+        let data: serde_json::Value = self.get_json(&url)?;
+
+        if use_cache && let Err(e) = self.cache.write_raw(&path, &data) {
             eprintln!("Warning: Failed to write cache: {}", e);
         }
 
         Ok((data, false))
     }
 
-    pub fn download(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let url = self.options.to_download_url(&self.base_url);
+    /// PHP releases of `major` that php.net tags as security releases, read
+    /// from its release feed and cached like the listings.
+    pub fn fetch_security_releases(&self, major: u64) -> Result<Vec<Version>, Box<dyn Error>> {
+        let key = format!("php.net/releases/{}", major);
+        let cached = if self.no_cache {
+            None
+        } else {
+            self.cache.read_raw(&key)
+        };
+
+        let feed = match cached {
+            Some(feed) => feed,
+            None => {
+                let url = php_releases_url(&self.php_releases_url, major);
+                let feed: serde_json::Value = self.get_json(&url)?;
+                if let Err(e) = self.cache.write_raw(&key, &feed) {
+                    eprintln!("Warning: Failed to write cache: {}", e);
+                }
+                feed
+            }
+        };
+
+        let releases: HashMap<String, PhpRelease> = serde_json::from_value(feed)?;
+        Ok(security_releases(releases))
+    }
+
+    /// Confirms the server answers for the active category's listing.
+    pub fn check_connectivity(&self) -> Result<(), Box<dyn Error>> {
+        let url = self.options.to_url(&self.base_url);
+        if let Some(path) = local_path(&url) {
+            if !path.is_dir() {
+                return Err(format!("{} is not a directory", path.display()).into());
+            }
+            return Ok(());
+        }
+
+        self.client.head(url).send()?.error_for_status()?;
+        Ok(())
+    }
+
+    fn is_local(&self) -> bool {
+        self.base_url.starts_with("file://")
+    }
+
+    /// Sends a GET request, waiting out `429 Too Many Requests` (and `503` with
+    /// a `Retry-After`) as instructed by the server, up to `max_retry_wait`.
+    fn get(&self, url: &str) -> Result<blocking::Response, reqwest::Error> {
+        self.get_from(url, 0)
+    }
+
+    /// Like `get`, but asks for the body from byte `offset` onwards. Servers
+    /// that ignore the range answer with the whole body.
+    fn get_from(&self, url: &str, offset: u64) -> Result<blocking::Response, reqwest::Error> {
+        let mut attempt = 0;
+
+        loop {
+            let mut request = self.client.get(url);
+            if offset > 0 {
+                request = request.header(RANGE, format!("bytes={}-", offset));
+            }
+            let response = request.send()?;
+            let status = response.status();
+            let retry_after = retry_after(&response);
+
+            let rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+                || (status == StatusCode::SERVICE_UNAVAILABLE && retry_after.is_some());
+            if rate_limited {
+                RATE_LIMITED.fetch_add(1, Ordering::Relaxed);
+            }
+            if !rate_limited || attempt >= MAX_RATE_LIMIT_RETRIES {
+                return response.error_for_status();
+            }
+
+            let wait = retry_after.unwrap_or_else(|| Duration::from_secs(1 << attempt));
+            if wait > self.max_retry_wait {
+                eprintln!(
+                    "Rate limited: server asked to wait {}s, more than the {}s ceiling",
+                    wait.as_secs(),
+                    self.max_retry_wait.as_secs()
+                );
+                return response.error_for_status();
+            }
+
+            eprintln!("Rate limited, retrying in {}s...", wait.as_secs());
+            std::thread::sleep(wait);
+            attempt += 1;
+        }
+    }
+
+    /// Reads a JSON document over HTTP, or from disk for `file://` URLs where a
+    /// directory stands for its `index.json` listing.
+    fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, Box<dyn Error>> {
+        if let Some(path) = local_path(url) {
+            let path = if path.is_dir() {
+                path.join("index.json")
+            } else {
+                path
+            };
+            let contents =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            return Ok(serde_json::from_str(&contents)?);
+        }
+
+        Ok(self.get(url)?.json()?)
+    }
+
+    /// Reads a listing entry by entry, so one malformed entry doesn't cost the
+    /// whole listing. Fails only when none of the entries can be read.
+    fn get_listing(&self, url: &str) -> Result<Vec<SpcJsonResponse>, Box<dyn Error>> {
+        let entries: Vec<serde_json::Value> = self.get_json(url)?;
+        let total = entries.len();
+
+        let mut first_error = None;
+        let listing: Vec<SpcJsonResponse> = entries
+            .into_iter()
+            .filter_map(|entry| match serde_json::from_value(entry) {
+                Ok(resp) => Some(resp),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                    None
+                }
+            })
+            .collect();
+
+        if let Some(e) = first_error {
+            if listing.is_empty() {
+                return Err(
+                    format!("none of the {} listing entries could be read: {}", total, e).into(),
+                );
+            }
+            eprintln!(
+                "Warning: skipped {} of {} listing entries that could not be read ({})",
+                total - listing.len(),
+                total,
+                e
+            );
+        }
+        Ok(listing)
+    }
+
+    /// Downloads the selected artifact into the artifact cache, then places it
+    /// at `output_path`. Artifacts already in the cache are not fetched again
+    /// unless they no longer check out. Without a writable cache, it is
+    /// downloaded straight to `output_path`. Returns the artifact's SHA-256.
+    pub fn download(&self, output_path: &str) -> Result<String, Box<dyn Error>> {
+        let name = self.options.file_name(self.naming_scheme());
+        let url = self.artifact_url(&name);
+        self.explain(format!("Artifact: {} from {}", name, url));
+
+        if self.no_cache || self.is_local() || self.cache.is_in_memory() {
+            let digest = self.download_url_to(&url, Path::new(output_path))?;
+            if let Err(e) = self.verify_published(&name, &digest) {
+                let _ = fs::remove_file(long_path(Path::new(output_path)));
+                return Err(e);
+            }
+            return Ok(digest);
+        }
+
+        // The output needs room for a copy as much as the cache does.
+        if let Some(size) = self.listed_size(&name) {
+            check_free_space(size, Path::new(output_path))?;
+        }
+        let cached = self.cache.artifact_path(&self.options.category(), &name);
+        let reused = match cached.exists() {
+            true => self.check_cached(&name, &cached)?,
+            false => None,
+        };
+        let digest = if let Some(digest) = reused {
+            println!("Using cached artifact: {}", cached.display());
+            digest
+        } else {
+            if let Some(dir) = cached.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let partial = cached.with_file_name(format!("{}.part", name));
+            // Only verified artifacts make it into the cache.
+            let digest = match self
+                .download_url_to(&url, &partial)
+                .and_then(|digest| self.verify_published(&name, &digest).map(|_| digest))
+            {
+                Ok(digest) => digest,
+                Err(e) => {
+                    let _ = fs::remove_file(&partial);
+                    return Err(e);
+                }
+            };
+            fs::rename(&partial, &cached)?;
+            digest
+        };
+
+        self.place(&cached, Path::new(output_path))?;
+        Ok(digest)
+    }
+
+    /// The SHA-256 of the cached copy of `name` if it still matches the
+    /// published checksum, or the listed size when none is published. A copy
+    /// that doesn't is removed, so that it is downloaded again.
+    fn check_cached(&self, name: &str, cached: &Path) -> Result<Option<String>, Box<dyn Error>> {
+        let digest = sha256_file(cached)?;
+        let checked = self.verify_published(name, &digest).and_then(|_| {
+            let len = fs::metadata(long_path(cached))?.len();
+            match self.listed_size(name) {
+                Some(listed) if listed != len => {
+                    Err(format!("{} bytes, but {} are listed", len, listed).into())
+                }
+                _ => Ok(()),
+            }
+        });
+        if let Err(e) = checked {
+            eprintln!(
+                "Warning: the cached {} is damaged ({}); downloading it again",
+                name, e
+            );
+            fs::remove_file(long_path(cached))?;
+            return Ok(None);
+        }
+        Ok(Some(digest))
+    }
+
+    /// Hardlinks a cached artifact to `output_path`, falling back to a copy
+    /// when asked to or when the two are on different filesystems. A linked
+    /// output that is edited in place no longer checks out, so the cache
+    /// fetches it again next time rather than handing it out.
+    fn place(&self, cached: &Path, output_path: &Path) -> Result<(), Box<dyn Error>> {
+        let target = long_path(output_path);
+        // Unlinked first: it may be a hardlink into the cache, which writing
+        // through would change.
+        if target.exists() {
+            fs::remove_file(&target)?;
+        }
+
+        if !self.copy && fs::hard_link(cached, &target).is_ok() {
+            println!("Linked to: {}", output_path.display());
+            return Ok(());
+        }
+
+        check_free_space(fs::metadata(cached)?.len(), output_path)?;
+        let _partial = PartialFile::track(&target);
+        fs::copy(cached, &target).map_err(|e| format!("{}: {}", output_path.display(), e))?;
+        println!("Copied to: {}", output_path.display());
+        Ok(())
+    }
+
+    /// Downloads a single artifact of the active category by its file name.
+    pub fn download_artifact(&self, name: &str, output_path: &Path) -> Result<(), Box<dyn Error>> {
+        let url = self.artifact_url(name);
+        self.download_url_to(&url, output_path).map(|_| ())
+    }
+
+    /// GitHub assets live at unpredictable URLs, so they are looked up in the
+    /// listing; the download server's URLs are derived from the file name.
+    fn artifact_url(&self, name: &str) -> String {
+        if self.source == Source::Github {
+            let asset = self
+                .fetch_versions()
+                .ok()
+                .and_then(|(data, _)| data.into_iter().find(|resp| resp.name == name));
+            return match asset {
+                Some(asset) => asset.full_path().to_string(),
+                None => format!(
+                    "https://github.com/{}/releases",
+                    self.github_repo.as_deref().unwrap_or_default()
+                ),
+            };
+        }
+
+        self.options.to_artifact_url(&self.base_url, name)
+    }
+
+    /// Downloads `url` to `output_path` and returns its SHA-256.
+    pub fn download_url_to(&self, url: &str, output_path: &Path) -> Result<String, Box<dyn Error>> {
+        let name = url.rsplit('/').next().unwrap_or(url);
+        let size = self.expected_size(url);
+        match size {
+            Some(size) => println!("Downloading {} ({})", name, format_mb(size)),
+            None => println!("Downloading {}", name),
+        }
         println!("Downloading from: {}", url);
+        if let Some(size) = size {
+            check_free_space(size, output_path)?;
+        }
+        self.cancel.check()?;
+        let target = long_path(output_path);
+        let _partial = PartialFile::track(&target);
+
+        if let Some(source) = local_path(url) {
+            fs::copy(&source, &target).map_err(|e| format!("{}: {}", source.display(), e))?;
+            println!("Downloaded to: {}", output_path.display());
+            return Ok(sha256_file(&target)?);
+        }
+
+        let file = std::fs::File::create(&target)
+            .map_err(|e| format!("{}: {}", output_path.display(), e))?;
+        let mut writer = HashingWriter::new(file);
+        let mut attempt = 0;
+        while let Err(e) = self.transfer(url, &mut writer, size) {
+            if self.cancel.is_cancelled() {
+                drop(writer);
+                let _ = fs::remove_file(&target);
+                return Err(Cancelled.into());
+            }
+            if attempt >= self.retries || !e.is_transient() {
+                return Err(e.into());
+            }
 
-        let mut response = self.client.get(url).send()?;
-        let mut file = std::fs::File::create(output_path)?;
-        std::io::copy(&mut response, &mut file)?;
+            attempt += 1;
+            let wait = backoff(self.retry_delay, attempt);
+            eprintln!(
+                "Download interrupted ({}), retrying in {:.1}s ({}/{})...",
+                e,
+                wait.as_secs_f64(),
+                attempt,
+                self.retries
+            );
+            std::thread::sleep(wait);
+        }
+
+        println!("Downloaded to: {}", output_path.display());
+        Ok(writer.finish())
+    }
 
-        println!("Downloaded to: {}", output_path);
+    /// One attempt at fetching `url` into `writer`, picking up after the bytes
+    /// an earlier attempt already wrote.
+    fn transfer(
+        &self,
+        url: &str,
+        writer: &mut HashingWriter<fs::File>,
+        size: Option<u64>,
+    ) -> Result<(), TransferError> {
+        let resumed = writer.written;
+        let mut response = self.get_from(url, resumed)?;
+        if resumed > 0 {
+            if response.status() == StatusCode::PARTIAL_CONTENT {
+                eprintln!("Resuming at {}", format_mb(resumed));
+            } else {
+                writer.restart().map_err(TransferError::Write)?;
+            }
+        }
+
+        let resumed = writer.written;
+        writer.failed_write = false;
+        let announced = response.content_length().map(|length| length + resumed);
+        // The server's own Content-Length beats the preflight's estimate.
+        let total = announced.or(size);
+        let show = self.progress && io::stderr().is_terminal();
+        let result = copy_with_progress(&mut response, writer, resumed, total, show, &self.cancel);
+        let written = result.map_err(|e| match writer.failed_write {
+            true => TransferError::Write(e),
+            false => TransferError::Read(e),
+        })?;
+
+        // A connection closed early without an error still leaves the file
+        // short of what the server announced.
+        match announced {
+            Some(total) if resumed + written < total => Err(TransferError::Read(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("got {} of {} bytes", resumed + written, total),
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a freshly downloaded artifact against the `.sha256` file the
+    /// server publishes next to it, if there is one.
+    fn verify_published(&self, name: &str, digest: &str) -> Result<(), Box<dyn Error>> {
+        if !self.verify {
+            return Ok(());
+        }
+        let Some(published) = self
+            .fetch_companion(name, ".sha256")
+            .and_then(|contents| parse_sha256(&contents))
+        else {
+            self.explain(format!("No published checksum for {}", name));
+            return Ok(());
+        };
+
+        if published != digest {
+            return Err(format!(
+                "SHA-256 mismatch for {}: the server publishes {}, got {}",
+                name, published, digest
+            )
+            .into());
+        }
+        println!("Verified against the published SHA-256");
         Ok(())
     }
 
-    pub fn download_url(&self, version: &Version) -> String {
+    /// Times reading the first `bytes` of `url` with a ranged request, to
+    /// compare mirrors without downloading whole artifacts.
+    pub fn probe(&self, url: &str, bytes: u64) -> Result<Probe, Box<dyn Error>> {
+        let start = Instant::now();
+        let reader: Box<dyn Read> = match local_path(url) {
+            Some(path) => Box::new(fs::File::open(long_path(&path))?),
+            None => Box::new(
+                self.client
+                    .get(url)
+                    .header(RANGE, format!("bytes=0-{}", bytes.saturating_sub(1)))
+                    .send()?
+                    .error_for_status()?,
+            ),
+        };
+        let latency = start.elapsed();
+
+        let read = io::copy(&mut reader.take(bytes), &mut io::sink())?;
+        Ok(Probe {
+            latency,
+            bytes: read,
+            elapsed: start.elapsed(),
+        })
+    }
+
+    /// The checksum or signature file `name` + `ext` published next to an
+    /// artifact, or `None` when the server doesn't have one.
+    pub fn fetch_companion(&self, name: &str, ext: &str) -> Option<Vec<u8>> {
+        let url = self.companion_url(&format!("{}{}", name, ext))?;
+        self.fetch_bytes(&url).ok()
+    }
+
+    fn companion_url(&self, companion: &str) -> Option<String> {
+        if self.source == Source::Github {
+            let (data, _) = self.fetch_versions().ok()?;
+            return data
+                .into_iter()
+                .find(|resp| resp.name == companion)
+                .map(|resp| resp.full_path().to_string());
+        }
+
+        Some(self.options.to_artifact_url(&self.base_url, companion))
+    }
+
+    fn fetch_bytes(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        if let Some(path) = local_path(url) {
+            return Ok(fs::read(path)?);
+        }
+
+        Ok(self.get(url)?.bytes()?.to_vec())
+    }
+
+    /// Size of the artifact at `url`, from a HEAD request and otherwise from
+    /// the listing. Local mirrors are trusted to list sizes correctly.
+    fn expected_size(&self, url: &str) -> Option<u64> {
+        let name = url.rsplit('/').next()?;
+        let listed = || self.listed_size(name);
+
+        if let Some(path) = local_path(url) {
+            return listed().or_else(|| fs::metadata(path).ok().map(|m| m.len()));
+        }
+
+        self.client
+            .head(url)
+            .send()
+            .ok()
+            .filter(|response| response.status().is_success())
+            .and_then(|response| {
+                response
+                    .headers()
+                    .get(CONTENT_LENGTH)?
+                    .to_str()
+                    .ok()?
+                    .parse()
+                    .ok()
+            })
+            .or_else(listed)
+    }
+
+    /// The active category.
+    pub fn category(&self) -> BuildCategory {
+        self.options.category()
+    }
+
+    /// Size of the artifact `name` as the listing gives it.
+    pub fn listed_size(&self, name: &str) -> Option<u64> {
+        let (data, _) = self.fetch_versions().ok()?;
+        data.iter()
+            .find(|resp| resp.name == name)
+            .and_then(|resp| resp.size().parse().ok())
+    }
+
+    /// Artifacts of the active category that upstream has been seen
+    /// re-publishing with different metadata, for the names `matches` picks,
+    /// that haven't been warned about yet. They are marked as warned about.
+    pub fn report_republications(&self, matches: impl Fn(&str) -> bool) -> Vec<Republication> {
+        self.cache
+            .report_republications(&self.options.category(), |r| matches(&r.name))
+    }
+
+    /// Every change observed in the active category's listing.
+    pub fn history(&self) -> ListingHistory {
+        self.cache.history(&self.options.category())
+    }
+
+    /// File name of the selected artifact at `version`.
+    pub fn artifact_name(&self, version: &Version) -> String {
         self.options
             .with_version(version)
-            .to_download_url(&self.base_url)
+            .file_name(self.naming_scheme())
+    }
+
+    pub fn download_url(&self, version: &Version) -> String {
+        self.artifact_url(&self.artifact_name(version))
+    }
+
+    /// The scheme artifact names are built with: the one configured for the
+    /// category, otherwise the one its listing uses when this process has
+    /// resolved it or it is cached, otherwise the current one.
+    fn naming_scheme(&self) -> NamingScheme {
+        if let Some(scheme) = self.naming_scheme {
+            return scheme;
+        }
+
+        let detect = |data: &[SpcJsonResponse]| {
+            NamingScheme::detect(data.iter().map(|resp| resp.name.as_str()))
+        };
+        let resolved = LISTINGS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&self.listing_url())
+            .and_then(|(data, _, _)| detect(data));
+        resolved
+            .or_else(|| {
+                self.cache
+                    .read(&self.options.category())
+                    .and_then(|data| detect(&data))
+            })
+            .unwrap_or_default()
     }
 }
+
+/// Passes writes through to a file while hashing them with SHA-256.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    written: u64,
+    /// Whether the last error came from `inner` rather than the source.
+    failed_write: bool,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            written: 0,
+            failed_write: false,
+        }
+    }
+
+    fn finish(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl HashingWriter<fs::File> {
+    /// Empties the file to start over, for servers that can't resume.
+    fn restart(&mut self) -> io::Result<()> {
+        self.inner.set_len(0)?;
+        self.inner.rewind()?;
+        self.hasher = Sha256::new();
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self
+            .inner
+            .write(buf)
+            .inspect_err(|_| self.failed_write = true)?;
+        self.hasher.update(&buf[..written]);
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Why a download attempt failed, split by whether trying again could help.
+#[derive(Debug)]
+enum TransferError {
+    Http(reqwest::Error),
+    Read(io::Error),
+    Write(io::Error),
+}
+
+impl TransferError {
+    /// Dropped connections, timeouts and server errors are worth retrying;
+    /// a 404 or a full disk won't go away by waiting.
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Http(e) => match e.status() {
+                Some(status) => status.is_server_error(),
+                None => e.is_connect() || e.is_timeout() || e.is_request() || e.is_body(),
+            },
+            Self::Read(_) => true,
+            Self::Write(_) => false,
+        }
+    }
+}
+
+impl std::fmt::Display for TransferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http(e) => e.fmt(f),
+            Self::Read(e) | Self::Write(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for TransferError {}
+
+impl From<reqwest::Error> for TransferError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e)
+    }
+}
+
+/// `delay` doubled for each retry after the first, scaled by a random factor
+/// between 0.5 and 1.5 so that parallel downloads don't retry in lockstep.
+fn backoff(delay: Duration, attempt: u32) -> Duration {
+    let base = delay.saturating_mul(1 << (attempt - 1).min(16));
+    let jitter = RandomState::new().build_hasher().finish() % 1000;
+    base.mul_f64(0.5 + jitter as f64 / 1000.0)
+}
+
+/// Fails early when `size` bytes are about to be written to `path`, a file or
+/// a directory that may not exist yet, and its filesystem has less free space.
+pub fn check_free_space(size: u64, path: &Path) -> Result<(), Box<dyn Error>> {
+    let dir = path
+        .ancestors()
+        .find(|dir| dir.is_dir())
+        .unwrap_or(Path::new("."));
+    let Ok(available) = fs4::available_space(dir) else {
+        return Ok(());
+    };
+
+    if size > available {
+        return Err(format!(
+            "Not enough disk space in {}: {} needed, {} available",
+            dir.display(),
+            format_mb(size),
+            format_mb(available)
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Maps a `file://` URL onto a local path, dropping any query string.
+fn local_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("file://")?;
+    let path = path.split('?').next().unwrap_or(path);
+
+    // file:///C:/mirror on Windows
+    let bytes = path.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        return Some(PathBuf::from(&path[1..]));
+    }
+
+    Some(PathBuf::from(path))
+}
+
+/// Parses `Retry-After` as either delay seconds or an HTTP date.
+fn retry_after(response: &blocking::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = at.with_timezone(&Utc) - Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
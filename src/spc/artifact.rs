@@ -0,0 +1,79 @@
+use semver::Version;
+use serde::Serialize;
+
+use super::{BuildCategory, NamingScheme};
+
+/// The fields encoded in an artifact file name, e.g.
+/// `php-8.3.12-fpm-linux-aarch64.tar.gz` or `php-8.3.12-cli-win.zip`, in any
+/// [`NamingScheme`].
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ArtifactName {
+    pub version: Version,
+    pub build_type: Option<String>,
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    pub extension: String,
+}
+
+impl ArtifactName {
+    pub fn parse(name: &str) -> Option<Self> {
+        NamingScheme::ALL
+            .iter()
+            .find_map(|scheme| scheme.parse(name))
+    }
+
+    /// The executable inside the archive, e.g. `php-fpm` for fpm builds and
+    /// `php.exe` for Windows cli builds.
+    pub fn binary_name(&self) -> Option<&'static str> {
+        let windows = self.os.as_deref() == Some("win");
+        match self.build_type.as_deref()? {
+            "cli" if windows => Some("php.exe"),
+            "cli" => Some("php"),
+            "fpm" => Some("php-fpm"),
+            "micro" => Some("micro.sfx"),
+            "frankenphp" => Some("frankenphp"),
+            _ => None,
+        }
+    }
+
+    /// The platform the artifact is built for, e.g. `linux-x86_64` or `win`.
+    pub fn platform(&self) -> String {
+        match (&self.os, &self.arch) {
+            (Some(os), Some(arch)) => format!("{}-{}", os, arch),
+            (Some(os), None) => os.clone(),
+            _ => "unknown".to_string(),
+        }
+    }
+
+    /// Whether the artifact is built for another OS or architecture than the
+    /// machine spc-utils runs on.
+    pub fn is_foreign(&self) -> bool {
+        let host_os = match std::env::consts::OS {
+            "windows" => "win",
+            os => os,
+        };
+        self.os.as_deref().is_some_and(|os| os != host_os)
+            || self
+                .arch
+                .as_deref()
+                .is_some_and(|arch| arch != std::env::consts::ARCH)
+    }
+
+    /// Categories whose naming scheme matches this artifact. The unix
+    /// categories share one scheme and so do the Windows ones, so a file name
+    /// alone can't tell them apart. FrankenPHP builds have a category of
+    /// their own.
+    pub fn candidate_categories(&self) -> Vec<BuildCategory> {
+        if self.build_type.as_deref() == Some("frankenphp") {
+            vec![BuildCategory::Frankenphp]
+        } else if self.os.as_deref() == Some("win") {
+            vec![BuildCategory::WinMin, BuildCategory::WinMax]
+        } else {
+            vec![
+                BuildCategory::Bulk,
+                BuildCategory::Common,
+                BuildCategory::Minimal,
+            ]
+        }
+    }
+}
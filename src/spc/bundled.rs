@@ -0,0 +1,44 @@
+use std::{collections::HashMap, io::Read, sync::OnceLock};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+use super::{BuildCategory, SpcJsonResponse};
+
+/// Listings snapshotted from the download server when this binary was built,
+/// see `bundled/README.md`.
+static BUNDLE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/bundled-listings.json.gz"));
+
+#[derive(Deserialize, Default)]
+struct Bundle {
+    date: String,
+    listings: HashMap<String, Vec<SpcJsonResponse>>,
+}
+
+fn bundle() -> &'static Bundle {
+    static PARSED: OnceLock<Bundle> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        let mut json = String::new();
+        GzDecoder::new(BUNDLE)
+            .read_to_string(&mut json)
+            .ok()
+            .and_then(|_| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    })
+}
+
+/// The bundled listing of `category` and the day it was snapshotted, for
+/// machines that have neither network access nor a cache yet.
+pub fn bundled_listing(
+    category: &BuildCategory,
+) -> Option<(Vec<SpcJsonResponse>, DateTime<Local>)> {
+    let bundle = bundle();
+    let listing = bundle.listings.get(&category.to_string())?;
+    let date = NaiveDate::parse_from_str(&bundle.date, "%Y-%m-%d")
+        .ok()?
+        .and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()?;
+    Some((listing.clone(), date))
+}
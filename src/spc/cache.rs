@@ -1,25 +1,226 @@
-use chrono::{DateTime, Local, NaiveTime};
+use chrono::{DateTime, Local, NaiveTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeSet, HashMap, HashSet},
     fs,
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
 };
 
-use super::{BuildCategory, SpcJsonResponse};
+use super::{BuildCategory, SpcJsonResponse, digest::sha256_file};
 
 const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub struct CacheFileInfo {
     pub category: BuildCategory,
+    pub path: PathBuf,
     pub size: u64,
     pub modified: DateTime<Local>,
     pub expires: DateTime<Local>,
     pub entry_count: usize,
 }
 
+/// An artifact that upstream served again under the same file name but with
+/// different metadata than what was previously cached.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Republication {
+    pub name: String,
+    pub previous_size: String,
+    pub current_size: String,
+    pub previous_modified: DateTime<Utc>,
+    pub current_modified: DateTime<Utc>,
+    pub detected_at: DateTime<Local>,
+    /// SHA-256 of the copy in the artifact cache, downloaded before it was
+    /// re-published.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_sha256: Option<String>,
+    /// Whether `list` or `check-update` has warned about it yet.
+    #[serde(default)]
+    pub reported: bool,
+}
+
+/// What happened to an artifact between two refreshes of a listing.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Appeared,
+    Changed,
+    Removed,
+}
+
+/// One change to a listing, as observed when it was refreshed. For removals,
+/// `size` and `last_modified` are what upstream last listed.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEvent {
+    pub detected_at: DateTime<Local>,
+    pub name: String,
+    pub kind: ChangeKind,
+    pub size: String,
+    pub last_modified: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_size: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_modified: Option<DateTime<Utc>>,
+}
+
+/// Every change observed in a category's listing since it was first fetched.
+#[derive(Serialize, Deserialize, Default)]
+pub struct ListingHistory {
+    pub since: Option<DateTime<Local>>,
+    pub events: Vec<HistoryEvent>,
+}
+
+/// How current a listing is.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Freshness {
+    /// Fetched from the server just now
+    Fresh,
+    /// Served from a cache entry that is still valid, with when it expires
+    Cached(DateTime<Local>, DateTime<Local>),
+    /// Served from an expired cache entry because the server was unreachable
+    Stale(DateTime<Local>),
+    /// Served from the snapshot built into spc-utils, taken on that day,
+    /// because the server was unreachable and nothing was cached
+    Bundled(DateTime<Local>),
+}
+
+impl Freshness {
+    /// Suffix appended to results, e.g. ` (stale, fetched 3 days ago)`.
+    pub fn annotation(&self) -> String {
+        match self {
+            Freshness::Fresh => String::new(),
+            Freshness::Cached(fetched, expires) => {
+                let now = Local::now();
+                format!(
+                    " (cached {} ago, expires in {})",
+                    format_age(now - *fetched),
+                    format_age(*expires - now)
+                )
+            }
+            Freshness::Stale(fetched) => {
+                format!(
+                    " (stale, fetched {} ago)",
+                    format_age(Local::now() - *fetched)
+                )
+            }
+            Freshness::Bundled(taken) => {
+                format!(
+                    " (bundled snapshot from {}, may be out of date)",
+                    taken.format("%Y-%m-%d")
+                )
+            }
+        }
+    }
+}
+
+/// Cached listings are valid until the end of the day they were fetched on.
+pub fn expires_at(fetched: DateTime<Local>) -> DateTime<Local> {
+    fetched
+        .date_naive()
+        .succ_opt()
+        .unwrap()
+        .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
+        .and_local_timezone(Local)
+        .unwrap()
+}
+
+/// The differences between two listings of the same category, in the order
+/// of the fresh listing followed by whatever it no longer contains.
+fn listing_changes(
+    previous: &[SpcJsonResponse],
+    fresh: &[SpcJsonResponse],
+    detected_at: DateTime<Local>,
+) -> Vec<HistoryEvent> {
+    let before: HashMap<&str, &SpcJsonResponse> =
+        previous.iter().map(|p| (p.name.as_str(), p)).collect();
+    let after: HashSet<&str> = fresh.iter().map(|c| c.name.as_str()).collect();
+
+    let event = |resp: &SpcJsonResponse, kind| HistoryEvent {
+        detected_at,
+        name: resp.name.clone(),
+        kind,
+        size: resp.size().to_string(),
+        last_modified: resp.last_modified(),
+        previous_size: None,
+        previous_modified: None,
+    };
+
+    let mut events = Vec::new();
+    for current in fresh {
+        match before.get(current.name.as_str()) {
+            None => events.push(event(current, ChangeKind::Appeared)),
+            Some(prev)
+                if prev.size() != current.size()
+                    || prev.last_modified() != current.last_modified() =>
+            {
+                events.push(HistoryEvent {
+                    previous_size: Some(prev.size().to_string()),
+                    previous_modified: Some(prev.last_modified()),
+                    ..event(current, ChangeKind::Changed)
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for prev in previous {
+        if !after.contains(prev.name.as_str()) {
+            events.push(event(prev, ChangeKind::Removed));
+        }
+    }
+    events
+}
+
+/// A one-line changelog of a refresh, such as
+/// `+ php-8.3.13-cli-linux-x86_64.tar.gz, 2 artifacts updated`. Long runs of
+/// additions or removals are counted instead of named.
+pub fn summarize_changes(events: &[HistoryEvent]) -> String {
+    let count = |kind| events.iter().filter(|e| e.kind == kind).count();
+    let artifacts = |n| if n == 1 { "artifact" } else { "artifacts" };
+
+    let mut parts = Vec::new();
+    for (kind, sign, verb) in [
+        (ChangeKind::Appeared, '+', "added"),
+        (ChangeKind::Removed, '-', "removed"),
+    ] {
+        match count(kind) {
+            0 => {}
+            n if n <= 3 => parts.extend(
+                events
+                    .iter()
+                    .filter(|e| e.kind == kind)
+                    .map(|e| format!("{} {}", sign, e.name)),
+            ),
+            n => parts.push(format!("{} {} {} {}", sign, n, artifacts(n), verb)),
+        }
+    }
+    let changed = count(ChangeKind::Changed);
+    if changed > 0 {
+        parts.push(format!("{} {} updated", changed, artifacts(changed)));
+    }
+    parts.join(", ")
+}
+
+/// Renders a duration the way a person would say it: `3 days`, `4h`, `12m`.
+pub fn format_age(age: TimeDelta) -> String {
+    match (age.num_days(), age.num_hours(), age.num_minutes()) {
+        (1, _, _) => "1 day".to_string(),
+        (days, _, _) if days > 1 => format!("{} days", days),
+        (_, hours, _) if hours > 0 => format!("{}h", hours),
+        (_, _, minutes) if minutes > 0 => format!("{}m", minutes),
+        _ => format!("{}s", age.num_seconds().max(0)),
+    }
+}
+
+/// Cache files kept for the rest of the run when the cache directory can't be
+/// written, with when they were stored.
+type MemoryFiles = Arc<Mutex<HashMap<PathBuf, (DateTime<Local>, String)>>>;
+
 #[derive(Clone)]
 pub struct Cache {
     cache_dir: PathBuf,
+    memory: Option<MemoryFiles>,
 }
 
 impl Default for Cache {
@@ -30,15 +231,95 @@ impl Default for Cache {
 
 impl Cache {
     pub fn new() -> Self {
-        let cache_dir = dirs::cache_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("spc-utils");
+        Self::at(Self::default_dir())
+    }
 
-        let cache = Self { cache_dir };
+    /// A cache in `cache_dir` instead of the platform's cache directory. When
+    /// the directory can't be written, e.g. on a read-only home directory,
+    /// this warns once and keeps whatever would have been written in memory
+    /// for the rest of the run.
+    pub fn at(cache_dir: PathBuf) -> Self {
+        let mut cache = Self {
+            cache_dir,
+            memory: None,
+        };
         cache.check_version();
+        if !cache.is_writable() {
+            eprintln!(
+                "Warning: cache directory {} is not writable, caching in memory for this run",
+                cache.cache_dir.display()
+            );
+            cache.memory = Some(MemoryFiles::default());
+        }
         cache
     }
 
+    /// The platform's cache directory for spc-utils, e.g. `~/.cache/spc-utils`.
+    pub fn default_dir() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("spc-utils")
+    }
+
+    /// A cache kept in its own subdirectory, for listings from another source
+    /// that must not mix with the default ones.
+    pub fn scoped(&self, scope: &str) -> Self {
+        Self {
+            cache_dir: self.sources_dir().join(scope),
+            memory: self.memory.clone(),
+        }
+    }
+
+    /// Whether the cache directory is read-only and files are only kept in
+    /// memory for this run.
+    pub fn is_in_memory(&self) -> bool {
+        self.memory.is_some()
+    }
+
+    /// Writes a cache file, or keeps it in memory when the directory can't be
+    /// written.
+    fn put(&self, path: &Path, contents: String) -> Result<(), std::io::Error> {
+        if let Some(memory) = &self.memory {
+            memory
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(path.to_path_buf(), (Local::now(), contents));
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)
+    }
+
+    /// Reads a cache file, preferring what this run kept in memory.
+    fn get(&self, path: &Path) -> Option<String> {
+        if let Some(memory) = &self.memory
+            && let Some((_, contents)) = memory.lock().unwrap_or_else(|e| e.into_inner()).get(path)
+        {
+            return Some(contents.clone());
+        }
+        fs::read_to_string(path).ok()
+    }
+
+    /// When a cache file was written.
+    fn modified(&self, path: &Path) -> Option<DateTime<Local>> {
+        if let Some(memory) = &self.memory
+            && let Some((stored, _)) = memory.lock().unwrap_or_else(|e| e.into_inner()).get(path)
+        {
+            return Some(*stored);
+        }
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()?;
+        Some(modified.into())
+    }
+
+    fn sources_dir(&self) -> PathBuf {
+        self.cache_dir.join("sources")
+    }
+
     fn version_file_path(&self) -> PathBuf {
         self.cache_dir.join(".version")
     }
@@ -77,27 +358,21 @@ impl Cache {
     }
 
     pub fn is_valid(&self, category: &BuildCategory) -> bool {
-        let path = self.cache_file_path(category);
-        if !path.exists() {
-            return false;
-        }
+        self.is_fresh(&self.cache_file_path(category))
+    }
 
-        if let Ok(metadata) = fs::metadata(&path)
-            && let Ok(modified) = metadata.modified()
-        {
-            let modified_time: DateTime<Local> = modified.into();
-            let now = Local::now();
-            return modified_time.date_naive() == now.date_naive();
-        }
+    fn is_fresh(&self, path: &Path) -> bool {
+        self.modified(path)
+            .is_some_and(|modified| modified.date_naive() == Local::now().date_naive())
+    }
 
-        false
+    /// When the category's listing was last written, i.e. fetched.
+    pub fn fetched_at(&self, category: &BuildCategory) -> Option<DateTime<Local>> {
+        self.modified(&self.cache_file_path(category))
     }
 
     pub fn read(&self, category: &BuildCategory) -> Option<Vec<SpcJsonResponse>> {
-        let path = self.cache_file_path(category);
-        let mut file = fs::File::open(&path).ok()?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).ok()?;
+        let contents = self.get(&self.cache_file_path(category))?;
         serde_json::from_str(&contents).ok()
     }
 
@@ -106,12 +381,243 @@ impl Cache {
         category: &BuildCategory,
         data: &[SpcJsonResponse],
     ) -> Result<(), std::io::Error> {
-        fs::create_dir_all(&self.cache_dir)?;
-        let path = self.cache_file_path(category);
-        let mut file = fs::File::create(&path)?;
         let json = serde_json::to_string_pretty(data)?;
-        file.write_all(json.as_bytes())?;
-        Ok(())
+        self.put(&self.cache_file_path(category), json)
+    }
+
+    fn raw_dir(&self) -> PathBuf {
+        self.cache_dir.join("raw")
+    }
+
+    /// Cache location for an arbitrary server path fetched through `spc-utils api`.
+    pub fn raw_file_path(&self, server_path: &str) -> PathBuf {
+        let key: String = server_path
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        self.raw_dir().join(format!("{}.json", key))
+    }
+
+    pub fn raw_fetched_at(&self, server_path: &str) -> Option<DateTime<Local>> {
+        self.modified(&self.raw_file_path(server_path))
+    }
+
+    pub fn read_raw(&self, server_path: &str) -> Option<serde_json::Value> {
+        let path = self.raw_file_path(server_path);
+        if !self.is_fresh(&path) {
+            return None;
+        }
+
+        let contents = self.get(&path)?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn write_raw(
+        &self,
+        server_path: &str,
+        data: &serde_json::Value,
+    ) -> Result<(), std::io::Error> {
+        let json = serde_json::to_string_pretty(data)?;
+        self.put(&self.raw_file_path(server_path), json)
+    }
+
+    fn artifacts_dir(&self) -> PathBuf {
+        self.cache_dir.join("artifacts")
+    }
+
+    /// Where a downloaded archive of a category is kept for reuse.
+    pub fn artifact_path(&self, category: &BuildCategory, name: &str) -> PathBuf {
+        self.artifacts_dir().join(category.url_path()).join(name)
+    }
+
+    fn micro_dir(&self) -> PathBuf {
+        self.cache_dir.join("micro")
+    }
+
+    /// Where the `micro.sfx` extracted from a micro artifact is kept for reuse.
+    pub fn micro_sfx_path(&self, category: &BuildCategory, name: &str) -> PathBuf {
+        self.micro_dir()
+            .join(category.url_path())
+            .join(name)
+            .join("micro.sfx")
+    }
+
+    fn republications_file_path(&self, category: &BuildCategory) -> PathBuf {
+        self.cache_dir.join(format!(
+            "{}.republished.json",
+            category.to_string().to_lowercase()
+        ))
+    }
+
+    /// Compares a freshly fetched listing against the previously cached one and
+    /// records every artifact whose size or modification time changed, once,
+    /// with the hash of the previous copy when the artifact cache has one.
+    pub fn detect_republications(
+        &self,
+        category: &BuildCategory,
+        fresh: &[SpcJsonResponse],
+    ) -> Vec<Republication> {
+        let Some(previous) = self.read(category) else {
+            return Vec::new();
+        };
+
+        let mut all = self.republications(category);
+        let detected_at = Local::now();
+        let found: Vec<Republication> = fresh
+            .iter()
+            .filter_map(|current| {
+                let prev = previous.iter().find(|p| p.name == current.name)?;
+                if prev.size() == current.size() && prev.last_modified() == current.last_modified()
+                {
+                    return None;
+                }
+                let recorded = all.iter().any(|r| {
+                    r.name == current.name
+                        && r.current_size == current.size()
+                        && r.current_modified == current.last_modified()
+                });
+                if recorded {
+                    return None;
+                }
+
+                let cached = self.artifact_path(category, &current.name);
+                Some(Republication {
+                    name: current.name.clone(),
+                    previous_size: prev.size().to_string(),
+                    current_size: current.size().to_string(),
+                    previous_modified: prev.last_modified(),
+                    current_modified: current.last_modified(),
+                    detected_at,
+                    previous_sha256: cached
+                        .is_file()
+                        .then(|| sha256_file(&cached).ok())
+                        .flatten(),
+                    reported: false,
+                })
+            })
+            .collect();
+
+        if !found.is_empty() {
+            all.extend(found.iter().cloned());
+            if let Err(e) = self.write_republications(category, &all) {
+                eprintln!("Warning: Failed to record re-published artifacts: {}", e);
+            }
+        }
+
+        found
+    }
+
+    /// The republications in `category` that `matches` picks and nobody has
+    /// been warned about yet, marked as reported so that the next run stays
+    /// quiet about them.
+    pub fn report_republications(
+        &self,
+        category: &BuildCategory,
+        matches: impl Fn(&Republication) -> bool,
+    ) -> Vec<Republication> {
+        let mut all = self.republications(category);
+        let mut unreported = Vec::new();
+        for record in all.iter_mut().filter(|r| !r.reported && matches(r)) {
+            record.reported = true;
+            unreported.push(record.clone());
+        }
+
+        if !unreported.is_empty()
+            && let Err(e) = self.write_republications(category, &all)
+        {
+            eprintln!("Warning: Failed to record re-published artifacts: {}", e);
+        }
+        unreported
+    }
+
+    pub fn republications(&self, category: &BuildCategory) -> Vec<Republication> {
+        self.get(&self.republications_file_path(category))
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_republications(
+        &self,
+        category: &BuildCategory,
+        records: &[Republication],
+    ) -> Result<(), std::io::Error> {
+        let json = serde_json::to_string_pretty(records)?;
+        self.put(&self.republications_file_path(category), json)
+    }
+
+    fn history_file_path(&self, category: &BuildCategory) -> PathBuf {
+        self.cache_dir.join(format!(
+            "{}.history.json",
+            category.to_string().to_lowercase()
+        ))
+    }
+
+    /// Compares a freshly fetched listing against the previously cached one and
+    /// appends what appeared, changed or disappeared to the category's history.
+    /// The first listing only marks where the history starts. Returns the
+    /// changes found this time.
+    pub fn record_history(
+        &self,
+        category: &BuildCategory,
+        fresh: &[SpcJsonResponse],
+    ) -> Vec<HistoryEvent> {
+        let mut history = self.history(category);
+        let detected_at = Local::now();
+        let started = history.since.is_none();
+        if started {
+            history.since = Some(detected_at);
+        }
+
+        // Without the previous listing there is nothing to compare against.
+        let events = match self.read(category) {
+            Some(previous) => listing_changes(&previous, fresh, detected_at),
+            None => Vec::new(),
+        };
+        if events.is_empty() && !started {
+            return events;
+        }
+
+        history.events.extend(events.iter().cloned());
+        if let Err(e) = self.write_history(category, &history) {
+            eprintln!("Warning: Failed to record listing history: {}", e);
+        }
+        events
+    }
+
+    pub fn history(&self, category: &BuildCategory) -> ListingHistory {
+        self.get(&self.history_file_path(category))
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_history(
+        &self,
+        category: &BuildCategory,
+        history: &ListingHistory,
+    ) -> Result<(), std::io::Error> {
+        let json = serde_json::to_string_pretty(history)?;
+        self.put(&self.history_file_path(category), json)
+    }
+
+    fn seen_file_path(&self, category: &BuildCategory) -> PathBuf {
+        self.cache_dir
+            .join(format!("{}.seen.json", category.to_string().to_lowercase()))
+    }
+
+    /// Artifact names recorded by the previous `list --new`, or `None` when
+    /// nothing has been recorded for the category yet.
+    pub fn read_seen(&self, category: &BuildCategory) -> Option<BTreeSet<String>> {
+        let contents = self.get(&self.seen_file_path(category))?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn write_seen(
+        &self,
+        category: &BuildCategory,
+        names: &BTreeSet<String>,
+    ) -> Result<(), std::io::Error> {
+        let json = serde_json::to_string_pretty(names)?;
+        self.put(&self.seen_file_path(category), json)
     }
 
     pub fn list_cached_files(&self) -> Vec<CacheFileInfo> {
@@ -127,18 +633,13 @@ impl Cache {
                     .map(|t| t.into())
                     .unwrap_or_else(|_| Local::now());
 
-                let expires = modified
-                    .date_naive()
-                    .succ_opt()
-                    .unwrap()
-                    .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
-                    .and_local_timezone(Local)
-                    .unwrap();
+                let expires = expires_at(modified);
 
                 let entry_count = self.read(&category).map(|v| v.len()).unwrap_or(0);
 
                 files.push(CacheFileInfo {
                     category,
+                    path,
                     size: metadata.len(),
                     modified,
                     expires,
@@ -150,25 +651,192 @@ impl Cache {
         files
     }
 
+    /// Listings and raw responses whose day is over, including those cached
+    /// for other sources, with their sizes.
+    pub fn expired_files(&self) -> Vec<(PathBuf, u64)> {
+        let mut dirs = vec![self.cache_dir.clone()];
+        if let Ok(entries) = fs::read_dir(self.sources_dir()) {
+            dirs.extend(entries.flatten().map(|entry| entry.path()));
+        }
+
+        let now = Local::now();
+        let mut expired = Vec::new();
+        for dir in dirs {
+            let cache = Self {
+                cache_dir: dir,
+                memory: None,
+            };
+            let mut paths: Vec<PathBuf> = BuildCategory::all()
+                .iter()
+                .map(|category| cache.cache_file_path(category))
+                .collect();
+            if let Ok(entries) = fs::read_dir(cache.raw_dir()) {
+                paths.extend(entries.flatten().map(|entry| entry.path()));
+            }
+
+            for path in paths {
+                if let Ok(metadata) = fs::metadata(&path)
+                    && let Ok(modified) = metadata.modified()
+                    && expires_at(modified.into()) <= now
+                {
+                    expired.push((path, metadata.len()));
+                }
+            }
+        }
+        expired
+    }
+
+    /// Every file in the artifact and `micro.sfx` caches, partial downloads
+    /// and extractions included, with its size and when it was last used.
+    pub fn artifact_files(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let mut files = Vec::new();
+        let mut dirs = vec![self.artifacts_dir(), self.micro_dir()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    dirs.push(entry.path());
+                } else if let Ok(used) = metadata.accessed().or_else(|_| metadata.modified()) {
+                    files.push((entry.path(), metadata.len(), used));
+                }
+            }
+        }
+        files
+    }
+
+    pub fn version_marker_matches(&self) -> bool {
+        fs::read_to_string(self.version_file_path())
+            .is_ok_and(|stored| stored.trim() == CRATE_VERSION)
+    }
+
+    /// Checks that the cache directory exists and accepts writes.
+    pub fn is_writable(&self) -> bool {
+        let probe = self.cache_dir.join(".write-probe");
+        let writable = fs::write(&probe, b"").is_ok();
+        let _ = fs::remove_file(&probe);
+        writable
+    }
+
+    pub fn ensure_dir(&self) -> Result<(), std::io::Error> {
+        fs::create_dir_all(&self.cache_dir)?;
+        self.write_version();
+        Ok(())
+    }
+
+    /// Cache files that exist but can no longer be parsed.
+    pub fn corrupt_files(&self) -> Vec<PathBuf> {
+        let mut corrupt = Vec::new();
+
+        for category in BuildCategory::all() {
+            let path = self.cache_file_path(&category);
+            if path.exists() && self.read(&category).is_none() {
+                corrupt.push(path);
+            }
+
+            let path = self.republications_file_path(&category);
+            if path.exists()
+                && fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|c| serde_json::from_str::<Vec<Republication>>(&c).ok())
+                    .is_none()
+            {
+                corrupt.push(path);
+            }
+
+            let path = self.history_file_path(&category);
+            if path.exists()
+                && fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|c| serde_json::from_str::<ListingHistory>(&c).ok())
+                    .is_none()
+            {
+                corrupt.push(path);
+            }
+
+            let path = self.seen_file_path(&category);
+            if path.exists() && self.read_seen(&category).is_none() {
+                corrupt.push(path);
+            }
+        }
+
+        if let Ok(entries) = fs::read_dir(self.raw_dir()) {
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+                    .is_none()
+                {
+                    corrupt.push(path);
+                }
+            }
+        }
+
+        corrupt
+    }
+
     pub fn clear(&self, category: Option<&BuildCategory>) -> Result<usize, std::io::Error> {
         let mut removed = 0;
 
         match category {
             Some(cat) => {
-                let path = self.cache_file_path(cat);
-                if path.exists() {
-                    fs::remove_file(&path)?;
-                    removed = 1;
+                for path in [
+                    self.cache_file_path(cat),
+                    self.republications_file_path(cat),
+                ] {
+                    if path.exists() {
+                        fs::remove_file(&path)?;
+                        removed += 1;
+                    }
                 }
             }
             None => {
                 for cat in BuildCategory::all() {
-                    let path = self.cache_file_path(&cat);
-                    if path.exists() {
-                        fs::remove_file(&path)?;
+                    for path in [
+                        self.cache_file_path(&cat),
+                        self.republications_file_path(&cat),
+                    ] {
+                        if path.exists() {
+                            fs::remove_file(&path)?;
+                            removed += 1;
+                        }
+                    }
+                }
+
+                if let Ok(entries) = fs::read_dir(self.raw_dir()) {
+                    for entry in entries.flatten() {
+                        fs::remove_file(entry.path())?;
                         removed += 1;
                     }
                 }
+
+                for cat in BuildCategory::all() {
+                    if let Ok(entries) = fs::read_dir(self.artifacts_dir().join(cat.url_path())) {
+                        for entry in entries.flatten() {
+                            fs::remove_file(entry.path())?;
+                            removed += 1;
+                        }
+                    }
+
+                    if let Ok(entries) = fs::read_dir(self.micro_dir().join(cat.url_path())) {
+                        for entry in entries.flatten() {
+                            fs::remove_dir_all(entry.path())?;
+                            removed += 1;
+                        }
+                    }
+                }
+
+                if let Ok(entries) = fs::read_dir(self.sources_dir()) {
+                    for entry in entries.flatten() {
+                        removed += self
+                            .scoped(&entry.file_name().to_string_lossy())
+                            .clear(None)?;
+                    }
+                }
             }
         }
 
@@ -12,6 +12,8 @@ pub enum BuildCategory {
     Minimal,
     WinMin,
     WinMax,
+    /// FrankenPHP servers with PHP embedded, for Linux and macOS
+    Frankenphp,
 }
 
 impl BuildCategory {
@@ -22,6 +24,23 @@ impl BuildCategory {
         }
     }
 
+    /// The directory this category lives under on the download server.
+    pub fn url_path(&self) -> &'static str {
+        match self {
+            BuildCategory::Bulk => "bulk",
+            BuildCategory::Common => "common",
+            BuildCategory::Minimal => "minimal",
+            BuildCategory::WinMin => "windows/spc-min",
+            BuildCategory::WinMax => "windows/spc-max",
+            BuildCategory::Frankenphp => "frankenphp",
+        }
+    }
+
+    pub fn from_url_path(path: &str) -> Option<Self> {
+        let path = path.trim_matches('/');
+        Self::all().into_iter().find(|c| c.url_path() == path)
+    }
+
     pub fn all() -> Vec<BuildCategory> {
         vec![
             BuildCategory::Bulk,
@@ -29,6 +48,7 @@ impl BuildCategory {
             BuildCategory::Minimal,
             BuildCategory::WinMin,
             BuildCategory::WinMax,
+            BuildCategory::Frankenphp,
         ]
     }
 }
@@ -0,0 +1,26 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::ValueEnum;
+
+/// How long a release must have been out before the stable channel picks it,
+/// unless `stable_channel_days` is configured.
+pub const DEFAULT_STABLE_CHANNEL_DAYS: u32 = 7;
+
+/// How new a release `latest` is willing to pick.
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum Channel {
+    /// The newest release that has been out for a number of days
+    Stable,
+    /// The newest release, however recent
+    #[default]
+    Fresh,
+}
+
+impl Channel {
+    /// The latest publication time a release may have to be on this channel.
+    pub fn cutoff(&self, stable_days: u32) -> Option<DateTime<Utc>> {
+        match self {
+            Channel::Stable => Some(Utc::now() - TimeDelta::days(stable_days.into())),
+            Channel::Fresh => None,
+        }
+    }
+}
@@ -1,287 +1,5 @@
-#![allow(dead_code)]
-
 pub const SPC_OS_OPTIONS: [&str; 3] = ["linux", "windows", "macos"];
 
 pub const SPC_ARCH_OPTIONS: [&str; 2] = ["x86_64", "aarch64"];
 
-pub const SPC_PHP_BUILD_TYPE_OPTIONS: [&str; 3] = ["micro", "fpm", "cli"];
-
-pub const SPC_MINIMAL_PHP_EXTENSIONS: [&str; 8] = [
-    "iconv",
-    "pcntl",
-    "posix",
-    "mbstring",
-    "filter",
-    "tokenizer",
-    "zlib",
-    "phar",
-];
-
-pub const SPC_MINIMAL_PHP_LIBRARIES: [&str; 6] =
-    ["lib-base", "libiconv", "micro", "frankenphp", "php", "zlib"];
-
-pub const SPC_COMMON_PHP_EXTENSIONS: [&str; 38] = [
-    "bcmath",
-    "bz2",
-    "calendar",
-    "ctype",
-    "curl",
-    "dom",
-    "exif",
-    "fileinfo",
-    "filter",
-    "ftp",
-    "zlib",
-    "gd",
-    "gmp",
-    "iconv",
-    "xml",
-    "mbstring",
-    "mbregex",
-    "mysqlnd",
-    "openssl",
-    "pcntl",
-    "pdo",
-    "pdo_mysql",
-    "sqlite3",
-    "pdo_sqlite",
-    "pgsql",
-    "pdo_pgsql",
-    "phar",
-    "posix",
-    "session",
-    "redis",
-    "simplexml",
-    "libxml",
-    "soap",
-    "sockets",
-    "tokenizer",
-    "xmlwriter",
-    "xmlreader",
-    "zip",
-];
-
-pub const SPC_COMMON_PHP_LIBRARIES: [&str; 42] = [
-    "lib-base",
-    "micro",
-    "frankenphp",
-    "attr",
-    "libacl",
-    "brotli",
-    "watcher",
-    "php",
-    "bzip2",
-    "zlib",
-    "openssl",
-    "libssh2",
-    "libiconv",
-    "xz",
-    "libxml2",
-    "nghttp3",
-    "ngtcp2",
-    "nghttp2",
-    "zstd",
-    "libcares",
-    "gmp",
-    "libsodium",
-    "ldap",
-    "ncurses",
-    "gettext",
-    "libunistring",
-    "idn2",
-    "libedit",
-    "krb5",
-    "curl",
-    "libpng",
-    "libavif",
-    "libwebp",
-    "libjpeg",
-    "freetype",
-    "onig",
-    "sqlite",
-    "icu",
-    "libxslt",
-    "postgresql",
-    "liblz4",
-    "libzip",
-];
-
-pub const SPC_BULK_PHP_EXTENSIONS: [&str; 57] = [
-    "apcu",
-    "bcmath",
-    "bz2",
-    "calendar",
-    "ctype",
-    "curl",
-    "dba",
-    "dom",
-    "zlib",
-    "openssl",
-    "sockets",
-    "event",
-    "exif",
-    "fileinfo",
-    "filter",
-    "ftp",
-    "gd",
-    "gmp",
-    "iconv",
-    "imagick",
-    "imap",
-    "intl",
-    "mbstring",
-    "mbregex",
-    "mysqlnd",
-    "mysqli",
-    "opcache",
-    "opentelemetry",
-    "pcntl",
-    "pdo",
-    "pdo_mysql",
-    "pgsql",
-    "phar",
-    "posix",
-    "protobuf",
-    "readline",
-    "session",
-    "redis",
-    "shmop",
-    "simplexml",
-    "xml",
-    "libxml",
-    "soap",
-    "sodium",
-    "sqlite3",
-    "swoole-hook-pgsql",
-    "swoole-hook-mysql",
-    "swoole-hook-sqlite",
-    "swoole",
-    "sysvmsg",
-    "sysvsem",
-    "sysvshm",
-    "tokenizer",
-    "xmlreader",
-    "xmlwriter",
-    "xsl",
-    "zip",
-];
-
-pub const SPC_BULK_PHP_LIBRARIES: [&str; 54] = [
-    "lib-base",
-    "micro",
-    "frankenphp",
-    "attr",
-    "libacl",
-    "brotli",
-    "watcher",
-    "php",
-    "bzip2",
-    "zlib",
-    "openssl",
-    "libssh2",
-    "libiconv",
-    "xz",
-    "libxml2",
-    "nghttp3",
-    "ngtcp2",
-    "nghttp2",
-    "zstd",
-    "libcares",
-    "gmp",
-    "libsodium",
-    "ldap",
-    "ncurses",
-    "gettext",
-    "libunistring",
-    "idn2",
-    "libedit",
-    "krb5",
-    "curl",
-    "qdbm",
-    "libevent",
-    "libpng",
-    "libavif",
-    "libwebp",
-    "libjpeg",
-    "freetype",
-    "libjxl",
-    "lerc",
-    "jbig",
-    "libtiff",
-    "libde265",
-    "libaom",
-    "libheif",
-    "libzip",
-    "imagemagick",
-    "imap",
-    "icu",
-    "onig",
-    "libxslt",
-    "postgresql",
-    "liblz4",
-    "sqlite",
-    "liburing",
-];
-
-pub const SPC_WINDOWS_MIN_EXTENSIONS: [&str; 7] = [
-    "ctype",
-    "fileinfo",
-    "filter",
-    "iconv",
-    "mbstring",
-    "tokenizer",
-    "phar",
-];
-
-pub const SPC_WINDOWS_MAX_EXTENSIONS: [&str; 50] = [
-    "amqp",
-    "apcu",
-    "bcmath",
-    "bz2",
-    "calendar",
-    "ctype",
-    "curl",
-    "dba",
-    "dom",
-    "ds",
-    "exif",
-    "ffi",
-    "fileinfo",
-    "filter",
-    "ftp",
-    "gd",
-    "iconv",
-    "igbinary",
-    "libxml",
-    "mbregex",
-    "mbstring",
-    "mysqli",
-    "mysqlnd",
-    "opcache",
-    "openssl",
-    "pdo",
-    "pdo_mysql",
-    "pdo_sqlite",
-    "pdo_sqlsrv",
-    "phar",
-    "rar",
-    "redis",
-    "session",
-    "shmop",
-    "simdjson",
-    "simplexml",
-    "soap",
-    "sockets",
-    "sqlite3",
-    "sqlsrv",
-    "ssh2",
-    "sysvshm",
-    "tokenizer",
-    "xml",
-    "xmlreader",
-    "xmlwriter",
-    "yac",
-    "yaml",
-    "zip",
-    "zlib",
-];
+pub const SPC_PHP_BUILD_TYPE_OPTIONS: [&str; 4] = ["micro", "fpm", "cli", "frankenphp"];
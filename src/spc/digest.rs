@@ -0,0 +1,13 @@
+use std::{fs::File, io, path::Path};
+
+use sha2::{Digest, Sha256};
+
+use crate::platform::long_path;
+
+/// Hex encoded SHA-256 digest of a file.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(long_path(path))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
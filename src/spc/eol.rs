@@ -0,0 +1,26 @@
+use chrono::NaiveDate;
+use semver::Version;
+
+/// End of security support per PHP minor, from https://www.php.net/supported-versions.php.
+const SECURITY_EOL: [(u64, u64, &str); 6] = [
+    (8, 0, "2023-11-26"),
+    (8, 1, "2025-12-31"),
+    (8, 2, "2026-12-31"),
+    (8, 3, "2027-12-31"),
+    (8, 4, "2028-12-31"),
+    (8, 5, "2029-12-31"),
+];
+
+/// The day security support for `version`'s minor ends, if known.
+pub fn eol_date(version: &Version) -> Option<NaiveDate> {
+    SECURITY_EOL
+        .iter()
+        .find(|(major, minor, _)| (*major, *minor) == (version.major, version.minor))
+        .and_then(|(_, _, date)| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
+
+/// Whether `version`'s minor still receives security fixes on `today`, or
+/// `None` for a minor the bundled schedule doesn't know.
+pub fn is_supported(version: &Version, today: NaiveDate) -> Option<bool> {
+    eol_date(version).map(|eol| today <= eol)
+}
@@ -0,0 +1,278 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use semver::Version;
+
+use super::{ApiOptions, BuildCategory, SpcJsonResponse, VersionSpec};
+
+/// The kind of archive an artifact ships in: unix builds are tarballs and
+/// Windows builds are zips.
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ArtifactType {
+    /// Linux and macOS tarballs
+    #[value(name = "tar.gz")]
+    TarGz,
+    /// Windows zips
+    Zip,
+    /// Both
+    All,
+}
+
+impl ArtifactType {
+    fn extension(self) -> Option<&'static str> {
+        match self {
+            ArtifactType::TarGz => Some(".tar.gz"),
+            ArtifactType::Zip => Some(".zip"),
+            ArtifactType::All => None,
+        }
+    }
+}
+
+/// Decides which entries of a listing a command considers, so that `latest`,
+/// `list` and `check-update` agree on what matches.
+///
+/// Unix artifacts are matched on OS, arch and build type in the file name.
+/// Windows artifacts (`php-8.3.12-cli-win.zip`) carry no arch, so only the
+/// build type is matched, and the file must be a Windows zip.
+#[derive(Clone, Default)]
+pub struct ArtifactFilter {
+    windows: bool,
+    os: Option<String>,
+    arch: Option<String>,
+    build_type: Option<String>,
+    version: Option<VersionSpec>,
+    released_before: Option<DateTime<Utc>>,
+    extension: Option<&'static str>,
+}
+
+impl ArtifactFilter {
+    /// The artifacts `options` select, with defaults filled in for the
+    /// current platform.
+    pub fn new(options: &ApiOptions) -> Self {
+        let windows = matches!(
+            options.category(),
+            BuildCategory::WinMin | BuildCategory::WinMax
+        );
+
+        Self {
+            windows,
+            os: (!windows).then(|| options.os()),
+            arch: (!windows).then(|| options.arch()),
+            build_type: Some(options.build_type()),
+            version: options.version_bound().cloned(),
+            released_before: None,
+            extension: None,
+        }
+    }
+
+    /// Matches only the fields that are given, e.g. for mirroring every
+    /// platform at once.
+    pub fn only(
+        os: Option<String>,
+        arch: Option<String>,
+        build_type: Option<String>,
+        version: Option<VersionSpec>,
+    ) -> Self {
+        Self {
+            os,
+            arch,
+            build_type,
+            version,
+            ..Self::default()
+        }
+    }
+
+    /// Also rejects artifacts that aren't shipped as `artifact_type`.
+    pub fn with_artifact_type(mut self, artifact_type: ArtifactType) -> Self {
+        self.extension = artifact_type.extension();
+        self
+    }
+
+    /// Also rejects artifacts published after `cutoff`.
+    pub fn with_released_before(mut self, cutoff: Option<DateTime<Utc>>) -> Self {
+        self.released_before = cutoff;
+        self
+    }
+
+    /// The artifact's version when it passes every filter, otherwise the
+    /// reason it doesn't. Names are checked before the version is parsed, so
+    /// other platforms' artifacts are rejected cheaply.
+    pub fn check(&self, resp: &SpcJsonResponse) -> Result<Version, Rejection<'_>> {
+        if self.windows && !resp.name.ends_with("-win.zip") {
+            return Err(Rejection::NotWindowsZip);
+        }
+        if let Some(extension) = self.extension
+            && !resp.name.ends_with(extension)
+        {
+            return Err(Rejection::NotType(extension));
+        }
+
+        let needles = [
+            ("os", &self.os),
+            ("arch", &self.arch),
+            ("build type", &self.build_type),
+        ];
+        for (field, needle) in needles {
+            if let Some(needle) = needle
+                && !resp.name.contains(needle.as_str())
+            {
+                return Err(Rejection::Lacks(field, needle));
+            }
+        }
+
+        let version = resp.version().ok_or(Rejection::NotPhp)?;
+        if let Some(spec) = &self.version
+            && !spec.matches(&version)
+        {
+            return Err(Rejection::OutOfBounds(spec));
+        }
+
+        if let Some(cutoff) = self.released_before
+            && resp.last_modified() > cutoff
+        {
+            return Err(Rejection::TooRecent(cutoff));
+        }
+
+        Ok(version)
+    }
+
+    pub fn matches(&self, resp: &SpcJsonResponse) -> bool {
+        self.check(resp).is_ok()
+    }
+}
+
+/// Why an artifact was filtered out, for `--explain`. Only formatted when
+/// someone reads it.
+pub enum Rejection<'a> {
+    NotPhp,
+    NotWindowsZip,
+    NotType(&'static str),
+    Lacks(&'static str, &'a str),
+    OutOfBounds(&'a VersionSpec),
+    TooRecent(DateTime<Utc>),
+}
+
+impl fmt::Display for Rejection<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::NotPhp => write!(f, "not a PHP artifact name"),
+            Rejection::NotWindowsZip => write!(f, "not a Windows zip"),
+            Rejection::NotType(extension) => write!(f, "not a {} file", extension),
+            Rejection::Lacks(field, needle) => write!(f, "name lacks {} '{}'", field, needle),
+            Rejection::OutOfBounds(spec) => {
+                write!(f, "version is not {}", spec.bound().unwrap_or_default())
+            }
+            Rejection::TooRecent(cutoff) => {
+                write!(f, "published after {}", cutoff.format("%Y-%m-%d %H:%M UTC"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn entry(name: &str) -> SpcJsonResponse {
+        let published = Utc.with_ymd_and_hms(2024, 10, 1, 12, 0, 0).unwrap();
+        SpcJsonResponse::new(name.to_string(), format!("/bulk/{}", name), 1, published, 0)
+    }
+
+    fn filter(category: BuildCategory, version: Option<VersionSpec>) -> ArtifactFilter {
+        ArtifactFilter::new(&ApiOptions::new(
+            Some(category),
+            version,
+            Some("linux".to_string()),
+            Some("x86_64".to_string()),
+            Some("cli".to_string()),
+        ))
+    }
+
+    fn rejection(filter: &ArtifactFilter, name: &str) -> String {
+        match filter.check(&entry(name)) {
+            Ok(version) => panic!("{} matched as {}", name, version),
+            Err(rejection) => rejection.to_string(),
+        }
+    }
+
+    #[test]
+    fn windows_categories_match_zips_by_build_type_only() {
+        let filter = filter(BuildCategory::WinMax, None);
+
+        assert_eq!(
+            filter.check(&entry("php-8.3.12-cli-win.zip")).ok(),
+            Some(Version::new(8, 3, 12))
+        );
+        assert_eq!(
+            rejection(&filter, "php-8.3.12-cli-linux-x86_64.tar.gz"),
+            "not a Windows zip"
+        );
+        assert_eq!(
+            rejection(&filter, "php-8.3.12-micro-win.zip"),
+            "name lacks build type 'cli'"
+        );
+    }
+
+    #[test]
+    fn unix_categories_match_os_arch_and_build_type() {
+        let filter = filter(BuildCategory::Bulk, None);
+
+        assert_eq!(
+            filter
+                .check(&entry("php-8.3.12-cli-linux-x86_64.tar.gz"))
+                .ok(),
+            Some(Version::new(8, 3, 12))
+        );
+        assert_eq!(
+            rejection(&filter, "php-8.3.12-cli-win.zip"),
+            "name lacks os 'linux'"
+        );
+        assert_eq!(
+            rejection(&filter, "php-8.3.12-cli-linux-aarch64.tar.gz"),
+            "name lacks arch 'x86_64'"
+        );
+        assert_eq!(
+            rejection(&filter, "php-8.3.12-fpm-linux-x86_64.tar.gz"),
+            "name lacks build type 'cli'"
+        );
+        assert_eq!(
+            rejection(&filter, "cli-linux-x86_64.tar.gz"),
+            "not a PHP artifact name"
+        );
+    }
+
+    #[test]
+    fn version_type_and_date_bounds_reject_with_reasons() {
+        let name = "php-8.3.12-cli-linux-x86_64.tar.gz";
+
+        let bounded = filter(BuildCategory::Bulk, Some(VersionSpec::Minor(8, 4)));
+        assert_eq!(rejection(&bounded, name), "version is not 8.4.x");
+
+        let zips = filter(BuildCategory::Bulk, None).with_artifact_type(ArtifactType::Zip);
+        assert_eq!(rejection(&zips, name), "not a .zip file");
+
+        let cutoff = Utc.with_ymd_and_hms(2024, 9, 1, 0, 0, 0).unwrap();
+        let dated = filter(BuildCategory::Bulk, None).with_released_before(Some(cutoff));
+        assert_eq!(
+            rejection(&dated, name),
+            "published after 2024-09-01 00:00 UTC"
+        );
+    }
+
+    #[test]
+    fn version_specs_bound_the_search() {
+        let version = Version::new(8, 3, 12);
+
+        assert!(VersionSpec::Latest.matches(&version));
+        assert!(VersionSpec::Major(8).matches(&version));
+        assert!(!VersionSpec::Major(9).matches(&version));
+        assert!(VersionSpec::Minor(8, 3).matches(&version));
+        assert!(!VersionSpec::Minor(8, 4).matches(&version));
+        // A full version still looks for the newest patch of its minor.
+        assert!(VersionSpec::Exact(Version::new(8, 3, 0)).matches(&version));
+        assert!(!VersionSpec::Exact(Version::new(8, 2, 12)).matches(&version));
+    }
+}
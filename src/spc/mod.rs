@@ -1,11 +1,40 @@
 mod api;
+mod artifact;
+mod bundled;
 mod cache;
+mod cancel;
 mod category;
+mod channel;
 mod constants;
+mod digest;
+mod eol;
+mod filter;
+mod naming;
+mod profile;
+mod progress;
 mod response;
+mod security;
+mod source;
+mod version;
 
-pub use api::{Api, ApiOptions};
-pub use cache::Cache;
+pub use api::{
+    Api, ApiOptions, COMPANION_EXTENSIONS, DEFAULT_BASE_URL, DEFAULT_DOWNLOAD_RETRIES, Probe,
+    Resolution, check_free_space, parse_sha256, rate_limit_count, used_stale_cache,
+};
+pub use artifact::ArtifactName;
+pub use cache::{
+    Cache, CacheFileInfo, ChangeKind, Freshness, HistoryEvent, ListingHistory, Republication,
+    expires_at, summarize_changes,
+};
+pub use cancel::{CancelToken, Cancelled};
 pub use category::BuildCategory;
+pub use channel::{Channel, DEFAULT_STABLE_CHANNEL_DAYS};
 pub use constants::*;
+pub use digest::sha256_file;
+pub use eol::{eol_date, is_supported};
+pub use filter::{ArtifactFilter, ArtifactType};
+pub use naming::NamingScheme;
+pub use profile::CategoryProfile;
 pub use response::SpcJsonResponse;
+pub use source::Source;
+pub use version::{VersionChange, VersionSpec};
@@ -0,0 +1,86 @@
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use super::ArtifactName;
+
+/// How artifact files are named. Upstream has renamed artifacts before, so
+/// names are read with whichever scheme fits, and built with the scheme a
+/// category is configured for or its listing turns out to use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum NamingScheme {
+    /// `php-8.3.12-cli-linux-x86_64.tar.gz`, `php-8.3.12-cli-win.zip`
+    #[default]
+    Current,
+    /// The build type before the version: `php-cli-8.3.12-linux-x86_64.tar.gz`,
+    /// `php-cli-8.3.12-win.zip`
+    Legacy,
+}
+
+impl NamingScheme {
+    /// Every scheme, in the order names are tried against them.
+    pub const ALL: [NamingScheme; 2] = [NamingScheme::Current, NamingScheme::Legacy];
+
+    /// The fields of `name` if it follows this scheme.
+    pub fn parse(self, name: &str) -> Option<ArtifactName> {
+        let (stem, extension) = [".tar.gz", ".zip"]
+            .iter()
+            .find_map(|ext| name.strip_suffix(ext).map(|stem| (stem, &ext[1..])))?;
+
+        let mut parts = stem.split('-');
+        parts.next()?;
+        let (version, build_type) = match self {
+            NamingScheme::Current => {
+                let version = Version::parse(parts.next()?).ok()?;
+                (version, parts.next())
+            }
+            NamingScheme::Legacy => {
+                let build_type = parts.next();
+                (Version::parse(parts.next()?).ok()?, build_type)
+            }
+        };
+        let mut rest = parts.map(str::to_string);
+
+        Some(ArtifactName {
+            version,
+            build_type: build_type.map(str::to_string),
+            os: rest.next(),
+            arch: rest.next(),
+            extension: extension.to_string(),
+        })
+    }
+
+    /// The file name of an artifact. Windows builds are zips without an arch.
+    pub fn file_name(self, version: &str, build_type: &str, os: &str, arch: &str) -> String {
+        let platform = match os {
+            "win" => "win.zip".to_string(),
+            _ => format!("{}-{}.tar.gz", os, arch),
+        };
+        match self {
+            NamingScheme::Current => format!("php-{}-{}-{}", version, build_type, platform),
+            NamingScheme::Legacy => format!("php-{}-{}-{}", build_type, version, platform),
+        }
+    }
+
+    /// The scheme most of `names` follow, or `None` when none of them is an
+    /// artifact name.
+    pub fn detect<'a>(names: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        let mut counts = [0usize; Self::ALL.len()];
+        for name in names {
+            if let Some(index) = Self::ALL
+                .iter()
+                .position(|scheme| scheme.parse(name).is_some())
+            {
+                counts[index] += 1;
+            }
+        }
+
+        let (index, count) = counts
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, count)| (**count, std::cmp::Reverse(*index)))?;
+        (*count > 0).then_some(Self::ALL[index])
+    }
+}
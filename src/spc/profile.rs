@@ -0,0 +1,414 @@
+use std::collections::BTreeSet;
+
+use super::BuildCategory;
+
+/// What a build category ships: the PHP extensions compiled in, the libraries
+/// they are built against, the platforms it is published for and the build
+/// types published for them.
+pub struct CategoryProfile {
+    pub category: BuildCategory,
+    pub platforms: &'static [&'static str],
+    pub build_types: &'static [&'static str],
+    pub notes: &'static str,
+    extensions: &'static [&'static str],
+    libraries: &'static [&'static str],
+}
+
+static PROFILES: [CategoryProfile; 6] = [
+    CategoryProfile {
+        category: BuildCategory::Minimal,
+        platforms: &["linux", "macos"],
+        build_types: &["cli", "fpm", "micro"],
+        notes: "Minimal set of core extensions",
+        extensions: MINIMAL_EXTENSIONS,
+        libraries: MINIMAL_LIBRARIES,
+    },
+    CategoryProfile {
+        category: BuildCategory::Common,
+        platforms: &["linux", "macos"],
+        build_types: &["cli", "fpm", "micro"],
+        notes: "Common extensions for typical web applications",
+        extensions: COMMON_EXTENSIONS,
+        libraries: COMMON_LIBRARIES,
+    },
+    CategoryProfile {
+        category: BuildCategory::Bulk,
+        platforms: &["linux", "macos"],
+        build_types: &["cli", "fpm", "micro"],
+        notes: "Full-featured build with many extensions",
+        extensions: BULK_EXTENSIONS,
+        libraries: BULK_LIBRARIES,
+    },
+    CategoryProfile {
+        category: BuildCategory::WinMin,
+        platforms: &["windows"],
+        build_types: &["cli", "micro"],
+        notes: "Windows minimal build",
+        extensions: WINDOWS_MIN_EXTENSIONS,
+        libraries: &[],
+    },
+    CategoryProfile {
+        category: BuildCategory::WinMax,
+        platforms: &["windows"],
+        build_types: &["cli", "micro"],
+        notes: "Windows full build",
+        extensions: WINDOWS_MAX_EXTENSIONS,
+        libraries: &[],
+    },
+    CategoryProfile {
+        category: BuildCategory::Frankenphp,
+        platforms: &["linux", "macos"],
+        build_types: &["frankenphp"],
+        notes: "FrankenPHP server with PHP embedded; extensions not catalogued",
+        extensions: &[],
+        libraries: &[],
+    },
+];
+
+impl CategoryProfile {
+    pub fn of(category: &BuildCategory) -> &'static CategoryProfile {
+        let index = match category {
+            BuildCategory::Minimal => 0,
+            BuildCategory::Common => 1,
+            BuildCategory::Bulk => 2,
+            BuildCategory::WinMin => 3,
+            BuildCategory::WinMax => 4,
+            BuildCategory::Frankenphp => 5,
+        };
+        &PROFILES[index]
+    }
+
+    /// Every profile, smallest first within each platform family.
+    pub fn all() -> &'static [CategoryProfile] {
+        &PROFILES
+    }
+
+    pub fn extensions(&self) -> BTreeSet<&'static str> {
+        self.extensions.iter().copied().collect()
+    }
+
+    pub fn libraries(&self) -> BTreeSet<&'static str> {
+        self.libraries.iter().copied().collect()
+    }
+
+    pub fn is_available_on(&self, os: &str) -> bool {
+        self.platforms.contains(&os)
+    }
+
+    pub fn has_build_type(&self, build_type: &str) -> bool {
+        self.build_types.contains(&build_type)
+    }
+
+    /// Extension names are matched case-insensitively, as PHP does.
+    pub fn includes_extension(&self, name: &str) -> bool {
+        self.extensions
+            .iter()
+            .any(|ext| ext.eq_ignore_ascii_case(name))
+    }
+
+    /// Extensions in this category that `other` lacks.
+    pub fn missing_from(&self, other: &CategoryProfile) -> BTreeSet<&'static str> {
+        self.extensions()
+            .into_iter()
+            .filter(|ext| !other.includes_extension(ext))
+            .collect()
+    }
+
+    /// The required extensions this category doesn't include.
+    pub fn lacking<'a>(&self, required: &[&'a str]) -> Vec<&'a str> {
+        required
+            .iter()
+            .copied()
+            .filter(|ext| !self.includes_extension(ext))
+            .collect()
+    }
+
+    /// The category with the fewest extensions that still includes every one
+    /// of `required`, among those published for `os`.
+    pub fn smallest_with(required: &[&str], os: &str) -> Option<&'static CategoryProfile> {
+        PROFILES
+            .iter()
+            .filter(|profile| profile.is_available_on(os))
+            .filter(|profile| profile.lacking(required).is_empty())
+            .min_by_key(|profile| profile.extensions.len())
+    }
+}
+
+const MINIMAL_EXTENSIONS: &[&str] = &[
+    "iconv",
+    "pcntl",
+    "posix",
+    "mbstring",
+    "filter",
+    "tokenizer",
+    "zlib",
+    "phar",
+];
+
+const MINIMAL_LIBRARIES: &[&str] = &["lib-base", "libiconv", "micro", "frankenphp", "php", "zlib"];
+
+const COMMON_EXTENSIONS: &[&str] = &[
+    "bcmath",
+    "bz2",
+    "calendar",
+    "ctype",
+    "curl",
+    "dom",
+    "exif",
+    "fileinfo",
+    "filter",
+    "ftp",
+    "zlib",
+    "gd",
+    "gmp",
+    "iconv",
+    "xml",
+    "mbstring",
+    "mbregex",
+    "mysqlnd",
+    "openssl",
+    "pcntl",
+    "pdo",
+    "pdo_mysql",
+    "sqlite3",
+    "pdo_sqlite",
+    "pgsql",
+    "pdo_pgsql",
+    "phar",
+    "posix",
+    "session",
+    "redis",
+    "simplexml",
+    "libxml",
+    "soap",
+    "sockets",
+    "tokenizer",
+    "xmlwriter",
+    "xmlreader",
+    "zip",
+];
+
+const COMMON_LIBRARIES: &[&str] = &[
+    "lib-base",
+    "micro",
+    "frankenphp",
+    "attr",
+    "libacl",
+    "brotli",
+    "watcher",
+    "php",
+    "bzip2",
+    "zlib",
+    "openssl",
+    "libssh2",
+    "libiconv",
+    "xz",
+    "libxml2",
+    "nghttp3",
+    "ngtcp2",
+    "nghttp2",
+    "zstd",
+    "libcares",
+    "gmp",
+    "libsodium",
+    "ldap",
+    "ncurses",
+    "gettext",
+    "libunistring",
+    "idn2",
+    "libedit",
+    "krb5",
+    "curl",
+    "libpng",
+    "libavif",
+    "libwebp",
+    "libjpeg",
+    "freetype",
+    "onig",
+    "sqlite",
+    "icu",
+    "libxslt",
+    "postgresql",
+    "liblz4",
+    "libzip",
+];
+
+const BULK_EXTENSIONS: &[&str] = &[
+    "apcu",
+    "bcmath",
+    "bz2",
+    "calendar",
+    "ctype",
+    "curl",
+    "dba",
+    "dom",
+    "zlib",
+    "openssl",
+    "sockets",
+    "event",
+    "exif",
+    "fileinfo",
+    "filter",
+    "ftp",
+    "gd",
+    "gmp",
+    "iconv",
+    "imagick",
+    "imap",
+    "intl",
+    "mbstring",
+    "mbregex",
+    "mysqlnd",
+    "mysqli",
+    "opcache",
+    "opentelemetry",
+    "pcntl",
+    "pdo",
+    "pdo_mysql",
+    "pgsql",
+    "phar",
+    "posix",
+    "protobuf",
+    "readline",
+    "session",
+    "redis",
+    "shmop",
+    "simplexml",
+    "xml",
+    "libxml",
+    "soap",
+    "sodium",
+    "sqlite3",
+    "swoole-hook-pgsql",
+    "swoole-hook-mysql",
+    "swoole-hook-sqlite",
+    "swoole",
+    "sysvmsg",
+    "sysvsem",
+    "sysvshm",
+    "tokenizer",
+    "xmlreader",
+    "xmlwriter",
+    "xsl",
+    "zip",
+];
+
+const BULK_LIBRARIES: &[&str] = &[
+    "lib-base",
+    "micro",
+    "frankenphp",
+    "attr",
+    "libacl",
+    "brotli",
+    "watcher",
+    "php",
+    "bzip2",
+    "zlib",
+    "openssl",
+    "libssh2",
+    "libiconv",
+    "xz",
+    "libxml2",
+    "nghttp3",
+    "ngtcp2",
+    "nghttp2",
+    "zstd",
+    "libcares",
+    "gmp",
+    "libsodium",
+    "ldap",
+    "ncurses",
+    "gettext",
+    "libunistring",
+    "idn2",
+    "libedit",
+    "krb5",
+    "curl",
+    "qdbm",
+    "libevent",
+    "libpng",
+    "libavif",
+    "libwebp",
+    "libjpeg",
+    "freetype",
+    "libjxl",
+    "lerc",
+    "jbig",
+    "libtiff",
+    "libde265",
+    "libaom",
+    "libheif",
+    "libzip",
+    "imagemagick",
+    "imap",
+    "icu",
+    "onig",
+    "libxslt",
+    "postgresql",
+    "liblz4",
+    "sqlite",
+    "liburing",
+];
+
+const WINDOWS_MIN_EXTENSIONS: &[&str] = &[
+    "ctype",
+    "fileinfo",
+    "filter",
+    "iconv",
+    "mbstring",
+    "tokenizer",
+    "phar",
+];
+
+const WINDOWS_MAX_EXTENSIONS: &[&str] = &[
+    "amqp",
+    "apcu",
+    "bcmath",
+    "bz2",
+    "calendar",
+    "ctype",
+    "curl",
+    "dba",
+    "dom",
+    "ds",
+    "exif",
+    "ffi",
+    "fileinfo",
+    "filter",
+    "ftp",
+    "gd",
+    "iconv",
+    "igbinary",
+    "libxml",
+    "mbregex",
+    "mbstring",
+    "mysqli",
+    "mysqlnd",
+    "opcache",
+    "openssl",
+    "pdo",
+    "pdo_mysql",
+    "pdo_sqlite",
+    "pdo_sqlsrv",
+    "phar",
+    "rar",
+    "redis",
+    "session",
+    "shmop",
+    "simdjson",
+    "simplexml",
+    "soap",
+    "sockets",
+    "sqlite3",
+    "sqlsrv",
+    "ssh2",
+    "sysvshm",
+    "tokenizer",
+    "xml",
+    "xmlreader",
+    "xmlwriter",
+    "yac",
+    "yaml",
+    "zip",
+    "zlib",
+];
@@ -0,0 +1,115 @@
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use super::CancelToken;
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+pub(super) fn format_mb(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// Copies `reader` into `writer`, with `show` drawing how much has been
+/// transferred, the speed and, when `total` is known, an ETA on stderr.
+/// `resumed` bytes were written by an earlier attempt and count towards the
+/// total but not the speed. Stops between chunks once `cancel` is cancelled.
+pub(super) fn copy_with_progress(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    resumed: u64,
+    total: Option<u64>,
+    show: bool,
+    cancel: &CancelToken,
+) -> io::Result<u64> {
+    let started = Instant::now();
+    let mut drawn = started;
+    let mut buf = vec![0; 64 * 1024];
+    let mut done = resumed;
+
+    loop {
+        if let Err(e) = cancel.check() {
+            if show {
+                eprint!("\r\x1b[K");
+            }
+            return Err(e);
+        }
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..read])?;
+        done += read as u64;
+
+        if show && drawn.elapsed() >= REDRAW_INTERVAL {
+            drawn = Instant::now();
+            eprint!(
+                "\r\x1b[K{}",
+                status(done, resumed, total, started.elapsed())
+            );
+        }
+    }
+
+    if show {
+        eprint!("\r\x1b[K");
+    }
+    Ok(done - resumed)
+}
+
+/// e.g. `45.2 MB / 142.0 MB (31%), 12.3 MB/s, ETA 8s`
+fn status(done: u64, resumed: u64, total: Option<u64>, elapsed: Duration) -> String {
+    let rate = (done - resumed) as f64 / elapsed.as_secs_f64().max(0.001);
+
+    let mut line = match total {
+        Some(total) => format!(
+            "{} / {} ({}%)",
+            format_mb(done),
+            format_mb(total),
+            done.saturating_mul(100) / total.max(1)
+        ),
+        None => format_mb(done),
+    };
+    line.push_str(&format!(", {}/s", format_mb(rate as u64)));
+
+    if let Some(total) = total
+        && total > done
+        && rate > 0.0
+    {
+        let eta = Duration::from_secs_f64((total - done) as f64 / rate);
+        line.push_str(&format!(", ETA {}", format_eta(eta)));
+    }
+    line
+}
+
+fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    match secs {
+        3600.. => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+        60.. => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}s", secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_stops_once_cancelled() {
+        let cancel = CancelToken::default();
+        let mut copied = Vec::new();
+        let written = copy_with_progress(&mut &b"data"[..], &mut copied, 0, None, false, &cancel);
+        assert_eq!(written.unwrap(), 4);
+        assert_eq!(copied, b"data");
+
+        cancel.clone().cancel();
+        let mut copied = Vec::new();
+        let error = copy_with_progress(&mut &b"data"[..], &mut copied, 0, None, false, &cancel)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "cancelled");
+        assert!(copied.is_empty());
+    }
+}
@@ -2,7 +2,9 @@ use chrono::{DateTime, NaiveDateTime, Utc};
 use semver::Version;
 use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use super::ArtifactName;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SpcJsonResponse {
     is_dir: bool,
     full_path: String,
@@ -17,19 +19,46 @@ pub struct SpcJsonResponse {
 }
 
 impl SpcJsonResponse {
+    pub fn new(
+        name: String,
+        full_path: String,
+        size: u64,
+        last_modified: DateTime<Utc>,
+        download_count: u32,
+    ) -> Self {
+        Self {
+            is_dir: false,
+            full_path,
+            name,
+            size: size.to_string(),
+            last_modified,
+            download_count,
+            is_parent: false,
+        }
+    }
+
     pub fn version(&self) -> Option<Version> {
-        let expected_extensions = [".tar.gz", ".zip"];
+        self.artifact().map(|artifact| artifact.version)
+    }
 
-        if !expected_extensions
-            .iter()
-            .any(|ext| self.name.ends_with(ext))
-        {
-            return None;
-        }
+    pub fn artifact(&self) -> Option<ArtifactName> {
+        ArtifactName::parse(&self.name)
+    }
+
+    pub fn full_path(&self) -> &str {
+        &self.full_path
+    }
 
-        let version_str = self.name.split('-').nth(1)?;
+    pub fn size(&self) -> &str {
+        &self.size
+    }
+
+    pub fn last_modified(&self) -> DateTime<Utc> {
+        self.last_modified
+    }
 
-        Version::parse(version_str).ok()
+    pub fn download_count(&self) -> u32 {
+        self.download_count
     }
 }
 
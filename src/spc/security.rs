@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+use semver::Version;
+use serde::Deserialize;
+
+/// An entry of php.net's release feed, keyed by version.
+#[derive(Deserialize)]
+pub(super) struct PhpRelease {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+pub(super) fn php_releases_url(base: &str, major: u64) -> String {
+    format!("{}?json&max=-1&version={}", base, major)
+}
+
+/// Versions php.net tags as security releases.
+pub(super) fn security_releases(releases: HashMap<String, PhpRelease>) -> Vec<Version> {
+    let mut versions: Vec<Version> = releases
+        .into_iter()
+        .filter(|(_, release)| release.tags.iter().any(|tag| tag == "security"))
+        .filter_map(|(version, _)| Version::parse(&version).ok())
+        .collect();
+    versions.sort();
+    versions
+}
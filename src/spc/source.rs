@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use super::SpcJsonResponse;
+
+/// Where listings and artifacts are resolved from.
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum Source {
+    /// dl.static-php.dev, or the mirror given by `--base-url`
+    #[default]
+    Dl,
+    /// GitHub releases of a repository publishing the same artifacts
+    Github,
+}
+
+#[derive(Deserialize)]
+pub(super) struct GithubRelease {
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    size: u64,
+    updated_at: DateTime<Utc>,
+    download_count: u32,
+    browser_download_url: String,
+}
+
+pub(super) fn github_releases_url(repo: &str) -> String {
+    format!(
+        "https://api.github.com/repos/{}/releases?per_page=100",
+        repo
+    )
+}
+
+/// Flattens release assets into the upstream listing shape, keeping the
+/// asset's download URL as `full_path`.
+pub(super) fn github_listing(releases: Vec<GithubRelease>) -> Vec<SpcJsonResponse> {
+    releases
+        .into_iter()
+        .flat_map(|release| release.assets)
+        .map(|asset| {
+            SpcJsonResponse::new(
+                asset.name,
+                asset.browser_download_url,
+                asset.size,
+                asset.updated_at,
+                asset.download_count,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+use clap::ValueEnum;
+use semver::Version;
+use serde::Serialize;
+use strum::Display;
+
+/// The most significant version component that differs between two versions,
+/// ordered from the least significant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Display, Serialize, ValueEnum)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum VersionChange {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl VersionChange {
+    pub fn between(from: &Version, to: &Version) -> Option<Self> {
+        if from.major != to.major {
+            Some(VersionChange::Major)
+        } else if from.minor != to.minor {
+            Some(VersionChange::Minor)
+        } else if from.patch != to.patch {
+            Some(VersionChange::Patch)
+        } else {
+            None
+        }
+    }
+}
+
+/// A version asked for with `-V`: `latest`, a bare major such as `8` (the
+/// newest 8.x), a major.minor such as `8.4`, or a full version.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VersionSpec {
+    Latest,
+    Major(u64),
+    Minor(u64, u64),
+    Exact(Version),
+}
+
+impl VersionSpec {
+    /// Whether `version` satisfies the spec when looking for the newest match.
+    /// A full version bounds the search to its minor, as before.
+    pub fn matches(&self, version: &Version) -> bool {
+        match self {
+            VersionSpec::Latest => true,
+            VersionSpec::Major(major) => version.major == *major,
+            VersionSpec::Minor(major, minor) => version.major == *major && version.minor == *minor,
+            VersionSpec::Exact(exact) => {
+                version.major == exact.major && version.minor == exact.minor
+            }
+        }
+    }
+
+    /// The full version, when one was given.
+    pub fn exact(&self) -> Option<&Version> {
+        match self {
+            VersionSpec::Exact(version) => Some(version),
+            _ => None,
+        }
+    }
+
+    /// The range matched, e.g. `8.x` or `8.4.x`; `None` for `latest`.
+    pub fn bound(&self) -> Option<String> {
+        match self {
+            VersionSpec::Latest => None,
+            VersionSpec::Major(major) => Some(format!("{}.x", major)),
+            VersionSpec::Minor(major, minor) => Some(format!("{}.{}.x", major, minor)),
+            VersionSpec::Exact(v) => Some(format!("{}.{}.x", v.major, v.minor)),
+        }
+    }
+}
+
+/// As written with `-V`, e.g. `latest`, `8` or `8.4`.
+impl std::fmt::Display for VersionSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionSpec::Latest => write!(f, "latest"),
+            VersionSpec::Major(major) => write!(f, "{}", major),
+            VersionSpec::Minor(major, minor) => write!(f, "{}.{}", major, minor),
+            VersionSpec::Exact(version) => write!(f, "{}", version),
+        }
+    }
+}
+
+impl From<Version> for VersionSpec {
+    fn from(version: Version) -> Self {
+        VersionSpec::Exact(version)
+    }
+}
@@ -1,13 +1,41 @@
 use assert_cmd::{Command, cargo::cargo_bin_cmd};
 use predicates::prelude::*;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::tempdir;
 
 fn cmd() -> Command {
     cargo_bin_cmd!("spc-utils")
 }
 
+/// Builds a listing in the upstream JSON format from `(name, size)` pairs.
+fn listing(entries: &[(&str, u64)]) -> String {
+    let items: Vec<String> = entries
+        .iter()
+        .map(|(name, size)| {
+            format!(
+                r#"{{"is_dir":false,"full_path":"/static-php-cli/{name}","name":"{name}","size":{size},"last_modified":"2025-01-15 10:30:00","download_count":"","is_parent":false}}"#
+            )
+        })
+        .collect();
+    format!("[{}]", items.join(","))
+}
+
+/// Points the binary at a private cache directory pre-seeded with listings,
+/// so commands resolve offline against known data.
+fn cmd_with_cache(cache_home: &Path, listings: &[(&str, String)]) -> Command {
+    let cache_dir = cache_home.join("spc-utils");
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join(".version"), env!("CARGO_PKG_VERSION")).unwrap();
+    for (category, json) in listings {
+        fs::write(cache_dir.join(format!("{}.json", category)), json).unwrap();
+    }
+
+    let mut cmd = cmd();
+    cmd.env("XDG_CACHE_HOME", cache_home);
+    cmd
+}
+
 #[test]
 fn latest_retuns_valid_version() {
     cmd()
@@ -270,3 +298,4342 @@ fn cache_clears_on_different_spc_utils_version() {
     assert_ne!(new_version.trim(), "0.0.0-old");
     assert!(!dummy_cache.exists());
 }
+
+#[test]
+fn serve_fails_on_invalid_address() {
+    cmd()
+        .args(["serve", "--addr", "not-an-address"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to bind"));
+}
+
+/// Sends a GET for `path` to the server on `port`, waiting for it to come up,
+/// and returns the status code and body.
+fn http_get(port: u16, path: &str) -> (u16, String) {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    let mut stream = (0..100)
+        .find_map(|_| {
+            TcpStream::connect(("127.0.0.1", port)).ok().or_else(|| {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                None
+            })
+        })
+        .expect("server never came up");
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        path
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
+
+#[test]
+fn serve_answers_listings_and_artifacts() {
+    let cache_home = tempdir().unwrap();
+    let artifacts = tempdir().unwrap();
+    let name = "php-8.4.10-cli-linux-x86_64.tar.gz";
+    fs::create_dir_all(artifacts.path().join("bulk")).unwrap();
+    fs::write(artifacts.path().join("bulk").join(name), "artifact").unwrap();
+    // Only sets up the cache.
+    cmd_with_cache(cache_home.path(), &[("bulk", listing(&[(name, 8)]))]);
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut server = std::process::Command::new(env!("CARGO_BIN_EXE_spc-utils"))
+        .env("XDG_CACHE_HOME", cache_home.path())
+        .args(["--base-url", "http://127.0.0.1:9", "serve", "--addr"])
+        .arg(format!("127.0.0.1:{}", port))
+        .arg("--artifacts-dir")
+        .arg(artifacts.path())
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let (status, body) = http_get(port, "/bulk");
+    assert_eq!(status, 200);
+    assert!(body.contains(name));
+
+    assert_eq!(
+        http_get(port, &format!("/bulk/{}", name)),
+        (200, "artifact".to_string())
+    );
+    assert_eq!(http_get(port, "/bulk/../secret").0, 404);
+
+    // Listings that can't be fetched are reported as JSON.
+    let (status, body) = http_get(port, "/common");
+    assert_eq!(status, 502);
+    let error: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert!(!error["error"].as_str().unwrap().is_empty());
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+}
+
+#[test]
+fn sync_emit_index_writes_listing_and_pages() {
+    let cache_home = tempdir().unwrap();
+    let dest = tempdir().unwrap();
+    let name = "php-8.4.10-cli-linux-x86_64.tar.gz";
+
+    fs::create_dir_all(dest.path().join("bulk")).unwrap();
+    fs::write(dest.path().join("bulk").join(name), "abc").unwrap();
+
+    cmd_with_cache(cache_home.path(), &[("bulk", listing(&[(name, 3)]))])
+        .args(["sync", "-C", "bulk", "--emit-index", "--dest"])
+        .arg(dest.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bulk: 0 downloaded, 1 up to date"));
+
+    let index = fs::read_to_string(dest.path().join("bulk").join("index.json")).unwrap();
+    assert!(index.contains(name));
+    let page = fs::read_to_string(dest.path().join("bulk").join("index.html")).unwrap();
+    assert!(page.contains(&format!("<a href=\"{}\">", name)));
+    assert!(dest.path().join("index.html").exists());
+}
+
+#[test]
+fn latest_writes_version_file() {
+    let cache_home = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let plain = out.path().join("php-version");
+    let json = out.path().join("php-version.json");
+    let bulk = listing(&[
+        ("php-8.4.10-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.3.20-cli-linux-x86_64.tar.gz", 10),
+    ]);
+
+    for path in [&plain, &json] {
+        cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())])
+            .args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+            .arg("--write-version-file")
+            .arg(path)
+            .assert()
+            .success();
+    }
+
+    assert_eq!(fs::read_to_string(&plain).unwrap(), "8.4.12\n");
+    let json = fs::read_to_string(&json).unwrap();
+    assert!(json.contains(r#""version":"8.4.12""#));
+    assert!(json.contains("/bulk/php-8.4.12-cli-linux-x86_64.tar.gz"));
+}
+
+#[test]
+fn download_writes_version_file_with_sha256() {
+    use sha2::{Digest, Sha256};
+
+    let mirror = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    let cli = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let fpm = "php-8.4.12-fpm-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[cli, fpm]);
+    let download = || {
+        let mut cmd = cmd();
+        cmd.current_dir(project.path())
+            .args(["download", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+            .args(["--force", "--base-url", &base_url, "--write-version-file"]);
+        cmd
+    };
+
+    download()
+        .args(["php-version.toml", "-B", "cli", "-o", "php.tar.gz"])
+        .assert()
+        .success();
+    let toml = fs::read_to_string(project.path().join("php-version.toml")).unwrap();
+    assert!(toml.contains("version = \"8.4.12\""));
+    assert!(toml.contains(&format!("/bulk/{}", cli)));
+    assert!(toml.contains(&format!(
+        "sha256 = \"{}\"",
+        hex::encode(Sha256::digest(cli))
+    )));
+
+    // Configured targets are each recorded under their name.
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        "[targets.cli]\nbuild_type = \"cli\"\noutput = \"php-cli.tar.gz\"\n\n\
+         [targets.fpm]\nbuild_type = \"fpm\"\noutput = \"php-fpm.tar.gz\"\n",
+    )
+    .unwrap();
+    download().arg("versions.json").assert().success();
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(project.path().join("versions.json")).unwrap())
+            .unwrap();
+    assert_eq!(json["cli"]["version"], "8.4.12");
+    assert_eq!(
+        json["fpm"]["sha256"],
+        hex::encode(Sha256::digest(fpm)).as_str()
+    );
+
+    download().arg("versions").assert().success();
+    assert_eq!(
+        fs::read_to_string(project.path().join("versions")).unwrap(),
+        "cli 8.4.12\nfpm 8.4.12\n"
+    );
+}
+
+#[test]
+fn api_serves_raw_path_from_cache() {
+    let cache_home = tempdir().unwrap();
+    let raw_dir = cache_home.path().join("spc-utils").join("raw");
+    fs::create_dir_all(&raw_dir).unwrap();
+    fs::write(
+        raw_dir.join("windows_spc_max_format_json.json"),
+        r#"{"hello":"world"}"#,
+    )
+    .unwrap();
+
+    cmd_with_cache(cache_home.path(), &[])
+        .args(["api", "/windows/spc-max"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""hello": "world""#))
+        .stderr(predicate::str::contains("(cached)"));
+}
+
+/// Listing entries for every unix platform, so host-defaulted lookups resolve.
+fn unix_listing(versions: &[&str]) -> String {
+    let names: Vec<String> = versions
+        .iter()
+        .flat_map(|version| {
+            [
+                "linux-x86_64",
+                "linux-aarch64",
+                "macos-x86_64",
+                "macos-aarch64",
+            ]
+            .map(|platform| format!("php-{}-cli-{}.tar.gz", version, platform))
+        })
+        .collect();
+    let entries: Vec<(&str, u64)> = names.iter().map(|n| (n.as_str(), 10)).collect();
+    listing(&entries)
+}
+
+#[test]
+fn outdated_scans_workspace_pins() {
+    let cache_home = tempdir().unwrap();
+    let workspace = tempdir().unwrap();
+    let api = workspace.path().join("api");
+    let web = workspace.path().join("web");
+    let vendored = workspace.path().join("web").join("vendor").join("pkg");
+    fs::create_dir_all(&api).unwrap();
+    fs::create_dir_all(&vendored).unwrap();
+    fs::write(api.join(".spc-version"), "8.3.20\n").unwrap();
+    fs::write(
+        web.join("composer.json"),
+        r#"{"config":{"platform":{"php":"8.4.10"}}}"#,
+    )
+    .unwrap();
+    fs::write(vendored.join(".spc-version"), "8.2.0").unwrap();
+    let legacy = workspace.path().join("legacy");
+    fs::create_dir_all(&legacy).unwrap();
+    fs::write(legacy.join(".spc-version"), "8.1.2\n").unwrap();
+
+    cmd_with_cache(
+        cache_home.path(),
+        &[("bulk", unix_listing(&["8.3.20", "8.4.10", "8.4.12"]))],
+    )
+    .args(["outdated", "-C", "bulk", "--workspace"])
+    .arg(workspace.path())
+    .assert()
+    .success()
+    .stdout(
+        predicate::str::is_match(r"api .*\.spc-version .*8\.3\.20 .*8\.3\.20 .*up to date")
+            .unwrap(),
+    )
+    .stdout(
+        predicate::str::is_match(r"web .*composer\.json .*8\.4\.10 .*8\.4\.12 .*outdated").unwrap(),
+    )
+    .stdout(predicate::str::is_match(r"legacy .*\.spc-version .*8\.1\.2 .*- .*unresolved").unwrap())
+    .stdout(predicate::str::contains("vendor").not());
+}
+
+#[test]
+fn check_update_runs_for_config_targets() {
+    let cache_home = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        r#"
+[targets.cli]
+category = "bulk"
+version = "8.4.10"
+os = "linux"
+arch = "x86_64"
+
+[targets.fpm]
+category = "bulk"
+version = "8.3.20"
+os = "linux"
+arch = "x86_64"
+build_type = "fpm"
+"#,
+    )
+    .unwrap();
+    let bulk = listing(&[
+        ("php-8.4.10-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.3.20-fpm-linux-x86_64.tar.gz", 10),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())])
+        .current_dir(project.path())
+        .args(["check-update"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[cli] Update available: 8.4.10 -> 8.4.12",
+        ))
+        .stdout(predicate::str::contains(
+            "[fpm] You have the latest version: 8.3.20",
+        ));
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .current_dir(project.path())
+        .args(["check-update", "--target", "fpm"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[cli]").not());
+}
+
+#[test]
+fn unknown_config_target_fails() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        "[targets.cli]\noutput = \"php\"\n",
+    )
+    .unwrap();
+
+    cmd()
+        .current_dir(project.path())
+        .args(["download", "--target", "fpm"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown target 'fpm'"));
+}
+
+#[test]
+fn doctor_fix_removes_corrupt_cache_files() {
+    let cache_home = tempdir().unwrap();
+    let corrupt = cache_home.path().join("spc-utils").join("common.json");
+
+    cmd_with_cache(cache_home.path(), &[("common", "not json".to_string())])
+        .args(["doctor"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Corrupt cache files"))
+        .stdout(predicate::str::contains("fixable with --fix"));
+    assert!(corrupt.exists());
+
+    cmd_with_cache(cache_home.path(), &[])
+        .args(["doctor", "--fix"])
+        .assert()
+        .stdout(predicate::str::contains("[fixed] Corrupt cache files"));
+    assert!(!corrupt.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn gc_reclaims_expired_cache_artifacts_and_old_installs() {
+    use std::time::{Duration, SystemTime};
+
+    let cache_home = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    let temp = tempdir().unwrap();
+    let cache_dir = cache_home.path().join("spc-utils");
+    let artifacts = cache_dir.join("artifacts").join("bulk");
+    let micro = cache_dir
+        .join("micro")
+        .join("bulk")
+        .join("php-8.4.12-micro-linux-x86_64.tar.gz");
+    fs::create_dir_all(&artifacts).unwrap();
+    fs::create_dir_all(&micro).unwrap();
+
+    let age = |path: &Path, hours: u64| {
+        let when = SystemTime::now() - Duration::from_secs(hours * 60 * 60);
+        fs::File::open(path)
+            .unwrap()
+            .set_times(fs::FileTimes::new().set_accessed(when).set_modified(when))
+            .unwrap();
+    };
+    let old = artifacts.join("php-8.3.10-cli-linux-x86_64.tar.gz");
+    let recent = artifacts.join("php-8.4.12-cli-linux-x86_64.tar.gz");
+    let partial = artifacts.join("php-8.4.11-cli-linux-x86_64.tar.gz.part");
+    let micro_partial = micro.join("micro.sfx.part");
+    for (path, hours) in [(&old, 72), (&recent, 1), (&partial, 3), (&micro_partial, 3)] {
+        fs::write(path, vec![0; 1000]).unwrap();
+        age(path, hours);
+    }
+
+    // Scratch left in the temp directory by interrupted runs, and one that
+    // may still be in use.
+    let scratch = temp.path().join("spc-utils-4242");
+    fs::create_dir_all(&scratch).unwrap();
+    fs::write(scratch.join("php-8.4.12-cli-linux-x86_64.tar.gz"), "x").unwrap();
+    age(&scratch, 3);
+    let mirror_check = temp
+        .path()
+        .join("spc-utils-4243-php-8.4.12-cli-linux-x86_64.tar.gz.mirror-check");
+    fs::write(&mirror_check, "x").unwrap();
+    age(&mirror_check, 3);
+    let running = temp.path().join("spc-utils-4244");
+    fs::create_dir_all(&running).unwrap();
+
+    // Four installed versions: the oldest is the active cli install, and
+    // another also has a plain download recorded on its receipt.
+    let receipts = data.path().join("receipts");
+    let versions = data.path().join("versions");
+    fs::create_dir_all(&receipts).unwrap();
+    let download = project.path().join("php.tar.gz");
+    fs::write(&download, "download").unwrap();
+    for version in ["8.2.5", "8.3.10", "8.4.11", "8.4.12"] {
+        let dir = versions.join(format!("php-{}-cli-linux-x86_64", version));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("php"), version).unwrap();
+        let mut files = vec![dir.join("php").canonicalize().unwrap()];
+        if version == "8.3.10" {
+            files.push(download.canonicalize().unwrap());
+        }
+        let files: Vec<String> = files
+            .iter()
+            .map(|path| format!(r#"{{"path":"{}","sha256":""}}"#, path.display()))
+            .collect();
+        fs::write(
+            receipts.join(format!("{}.json", version)),
+            format!(
+                r#"{{"version":"{}","url":"https://example.com/php","installed_at":"2025-01-15T10:30:00+00:00","files":[{}]}}"#,
+                version,
+                files.join(",")
+            ),
+        )
+        .unwrap();
+    }
+    fs::write(
+        versions.join("registry.json"),
+        r#"{"installs":{},"active":{"cli":"php-8.2.5-cli-linux-x86_64"}}"#,
+    )
+    .unwrap();
+    let half_written = receipts.join("8.4.12.json.tmp");
+    fs::write(&half_written, "{").unwrap();
+    age(&half_written, 3);
+
+    let gc = |dry_run: bool| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", listing(&[]))]);
+        age(&cache_dir.join("bulk.json"), 48);
+        cmd.env("TMPDIR", temp.path())
+            .arg("--data-dir")
+            .arg(data.path())
+            .args([
+                "gc",
+                "--max-artifact-cache",
+                "1500",
+                "--keep-installed",
+                "2",
+            ]);
+        if dry_run {
+            cmd.arg("--dry-run");
+        }
+        cmd
+    };
+
+    gc(true)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Would remove {}",
+            old.display()
+        )))
+        .stdout(predicate::str::contains("PHP 8.3.10 ("))
+        .stdout(predicate::str::contains("PHP 8.2.5").not())
+        .stdout(predicate::str::contains("Would reclaim"));
+    assert!(old.exists() && partial.exists() && scratch.exists());
+    assert!(versions.join("php-8.3.10-cli-linux-x86_64").exists());
+
+    gc(false)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Expired cache entries: 1 file"))
+        .stdout(predicate::str::contains(
+            "Leftovers of interrupted runs: 5 files",
+        ))
+        .stdout(predicate::str::contains("Reclaimed"));
+    assert!(!cache_dir.join("bulk.json").exists());
+    assert!(!old.exists() && !partial.exists() && !micro_partial.exists());
+    assert!(!scratch.exists() && !mirror_check.exists() && !half_written.exists());
+    assert!(running.exists());
+    assert!(recent.exists());
+
+    // Only the install directory goes; the download elsewhere stays on the
+    // receipt.
+    assert!(!versions.join("php-8.3.10-cli-linux-x86_64").exists());
+    assert!(download.exists());
+    let receipt = fs::read_to_string(receipts.join("8.3.10.json")).unwrap();
+    assert!(receipt.contains("php.tar.gz") && !receipt.contains("versions"));
+    assert!(versions.join("php-8.4.11-cli-linux-x86_64").exists());
+    assert!(versions.join("php-8.2.5-cli-linux-x86_64").exists());
+    assert!(receipts.join("8.4.12.json").exists());
+    let registry = fs::read_to_string(versions.join("registry.json")).unwrap();
+    assert!(!registry.contains("php-8.3.10") && registry.contains("php-8.4.11"));
+}
+
+#[cfg(not(feature = "object-storage"))]
+#[test]
+fn sync_to_bucket_requires_object_storage_feature() {
+    cmd()
+        .args(["sync", "--dest", "s3://bucket/mirror"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("object-storage"));
+}
+
+/// Lays out a `sync`-style mirror: each artifact file plus the category's
+/// `index.json` listing.
+fn local_mirror(root: &Path, category_path: &str, names: &[&str]) -> String {
+    let dir = root.join(category_path);
+    fs::create_dir_all(&dir).unwrap();
+    for name in names {
+        fs::write(dir.join(name), name.as_bytes()).unwrap();
+    }
+
+    let entries: Vec<(&str, u64)> = names.iter().map(|n| (*n, n.len() as u64)).collect();
+    fs::write(dir.join("index.json"), listing(&entries)).unwrap();
+
+    format!("file://{}", root.display())
+}
+
+/// Writes a gzipped tarball at `path` holding `(name, contents)` files.
+fn tarball(path: &Path, files: &[(&str, &str)]) {
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(
+        fs::File::create(path).unwrap(),
+        flate2::Compression::default(),
+    ));
+    for (name, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, name, contents.as_bytes())
+            .unwrap();
+    }
+    archive.into_inner().unwrap().finish().unwrap();
+}
+
+#[test]
+fn file_base_url_resolves_and_downloads_from_local_mirror() {
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &[
+            "php-8.4.10-cli-linux-x86_64.tar.gz",
+            "php-8.4.12-cli-linux-x86_64.tar.gz",
+        ],
+    );
+
+    cmd()
+        .args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", &base_url])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("Latest Version: 8.4.12\n"));
+
+    cmd()
+        .args([
+            "download", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-o",
+        ])
+        .arg(&output_path)
+        .args(["--base-url", &base_url])
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "php-8.4.12-cli-linux-x86_64.tar.gz"
+    );
+}
+
+#[test]
+fn download_writes_to_paths_longer_than_max_path() {
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let deep_dir = (0..12).fold(out.path().to_path_buf(), |dir, depth| {
+        dir.join(format!("nested-directory-level-{:02}", depth))
+    });
+    fs::create_dir_all(&deep_dir).unwrap();
+    let output_path = deep_dir.join("php.tar.gz");
+    assert!(output_path.as_os_str().len() > 260);
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &["php-8.4.12-cli-linux-x86_64.tar.gz"],
+    );
+
+    cmd()
+        .args([
+            "download", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-o",
+        ])
+        .arg(&output_path)
+        .args(["--base-url", &base_url])
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "php-8.4.12-cli-linux-x86_64.tar.gz"
+    );
+}
+
+#[test]
+fn assume_yes_answers_the_overwrite_prompt() {
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+
+    let download = || {
+        let mut cmd = cmd();
+        cmd.args(["download", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+            .args(["--base-url", &base_url, "-o"])
+            .arg(&output_path);
+        cmd
+    };
+
+    fs::write(&output_path, "existing").unwrap();
+    cmd()
+        .args([
+            "-y", "download", "-C", "bulk", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--base-url", &base_url, "-o"])
+        .arg(&output_path)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), name);
+
+    fs::write(&output_path, "existing").unwrap();
+    download().arg("--yes").assert().success();
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), name);
+
+    fs::write(&output_path, "existing").unwrap();
+    download()
+        .env("SPC_UTILS_ASSUME_YES", "1")
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), name);
+
+    fs::write(&output_path, "existing").unwrap();
+    download()
+        .env("SPC_UTILS_ASSUME_YES", "0")
+        .assert()
+        .failure();
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "existing");
+}
+
+#[test]
+fn mirror_bench_ranks_configured_mirrors() {
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let good = tempdir().unwrap();
+    let broken = tempdir().unwrap();
+    let good_url = local_mirror(good.path(), "bulk", &[name]);
+    let broken_url = local_mirror(broken.path(), "bulk", &[name]);
+    fs::remove_file(broken.path().join("bulk").join(name)).unwrap();
+
+    let project = tempdir().unwrap();
+    let config = project.path().join(".spc-utils.toml");
+    fs::write(
+        &config,
+        format!(
+            "# fastest first\nmirrors = [\"{}\", \"{}\"]\n\n[targets.cli]\ncategory = \"bulk\"\n",
+            broken_url, good_url
+        ),
+    )
+    .unwrap();
+
+    cmd()
+        .current_dir(project.path())
+        .args([
+            "mirror", "bench", "-C", "bulk", "-O", "linux", "-A", "x86_64",
+        ])
+        .arg("--write")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("bytes of {}", name)))
+        .stdout(predicate::str::contains("failed"))
+        .stdout(predicate::str::contains("Reordered mirrors"));
+
+    let written = fs::read_to_string(&config).unwrap();
+    assert!(written.starts_with("# fastest first\n"));
+    assert!(written.contains(&format!("mirrors = [\"{}\", \"{}\"]", good_url, broken_url)));
+    assert!(written.contains("[targets.cli]"));
+
+    // The first mirror now serves downloads without --base-url.
+    let out = tempdir().unwrap();
+    cmd()
+        .current_dir(project.path())
+        .args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64", "-o",
+        ])
+        .arg(out.path().join("php.tar.gz"))
+        .assert()
+        .success();
+
+    let empty = tempdir().unwrap();
+    cmd()
+        .current_dir(empty.path())
+        .args(["mirror", "bench"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("No mirrors to compare"));
+}
+
+#[test]
+fn frankenphp_category_resolves_and_downloads() {
+    let name = "php-8.4.12-frankenphp-linux-x86_64.tar.gz";
+    let mirror = tempdir().unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "frankenphp",
+        &["php-8.4.10-frankenphp-linux-x86_64.tar.gz", name],
+    );
+
+    cmd()
+        .args(["latest", "-C", "frankenphp", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", &base_url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("8.4.12"));
+
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("frankenphp.tar.gz");
+    cmd()
+        .args([
+            "download",
+            "-C",
+            "frankenphp",
+            "-O",
+            "linux",
+            "-A",
+            "x86_64",
+        ])
+        .args(["--base-url", &base_url, "-o"])
+        .arg(&output_path)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), name);
+}
+
+#[test]
+fn download_mirror_check_requires_identical_artifacts() {
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let primary = tempdir().unwrap();
+    let same = tempdir().unwrap();
+    let tampered = tempdir().unwrap();
+    let base_url = local_mirror(primary.path(), "bulk", &[name]);
+    let same_url = local_mirror(same.path(), "bulk", &[name]);
+    let tampered_url = local_mirror(tampered.path(), "bulk", &[name]);
+    fs::write(tampered.path().join("bulk").join(name), "something else").unwrap();
+
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let download = |mirror: &str| {
+        let mut cmd = cmd();
+        cmd.args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--base-url", &base_url, "--mirror-check", mirror, "-o"])
+        .arg(&output_path);
+        cmd
+    };
+
+    download(&same_url)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{} matches on {}",
+            name, same_url
+        )));
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), name);
+
+    download(&tampered_url)
+        .arg("--force")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "{} differs on {}",
+            name, tampered_url
+        )));
+    assert!(!output_path.exists());
+    assert_eq!(fs::read_dir(out.path()).unwrap().count(), 0);
+
+    download("ftp://elsewhere")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid mirror"));
+}
+
+#[test]
+fn concurrent_downloads_all_land_on_the_receipt() {
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let mirror = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+
+    let runs: Vec<_> = (0..8)
+        .map(|i| {
+            // Spawned rather than run one after another, so they overlap.
+            std::process::Command::new(env!("CARGO_BIN_EXE_spc-utils"))
+                .args([
+                    "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+                ])
+                .args(["--base-url", &base_url, "--data-dir"])
+                .arg(data.path())
+                .arg("-o")
+                .arg(out.path().join(format!("php-{}.tar.gz", i)))
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut run in runs {
+        assert!(run.wait().unwrap().success());
+    }
+
+    let receipt: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(data.path().join("receipts").join("8.4.12.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(receipt["files"].as_array().unwrap().len(), 8);
+}
+
+#[test]
+fn concurrent_installs_all_land_in_the_registry() {
+    let incoming = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let archives: Vec<PathBuf> = (0..6)
+        .map(|patch| {
+            let archive = incoming
+                .path()
+                .join(format!("php-8.3.{}-cli-linux-x86_64.tar.gz", patch));
+            tarball(&archive, &[("php", "PHP")]);
+            archive
+        })
+        .collect();
+
+    let runs: Vec<_> = archives
+        .iter()
+        .map(|archive| {
+            std::process::Command::new(env!("CARGO_BIN_EXE_spc-utils"))
+                .arg("--data-dir")
+                .arg(data.path())
+                .args(["install", "--from-file"])
+                .arg(archive)
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut run in runs {
+        assert!(run.wait().unwrap().success());
+    }
+
+    let registry: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(data.path().join("versions").join("registry.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(registry["installs"].as_object().unwrap().len(), 6);
+    assert_eq!(
+        registry["installs"]["php-8.3.4-cli-linux-x86_64"]["version"],
+        "8.3.4"
+    );
+    let active = registry["active"]["cli"].as_str().unwrap();
+    assert!(registry["installs"].get(active).is_some());
+}
+
+#[test]
+fn download_refuses_to_overwrite_without_force() {
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    fs::write(&output_path, "existing").unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &["php-8.4.12-cli-linux-x86_64.tar.gz"],
+    );
+
+    let download = || {
+        let mut cmd = cmd();
+        cmd.args([
+            "download", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-o",
+        ])
+        .arg(&output_path)
+        .args(["--base-url", &base_url]);
+        cmd
+    };
+
+    download()
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "existing");
+
+    download().arg("--force").assert().success();
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "php-8.4.12-cli-linux-x86_64.tar.gz"
+    );
+}
+
+#[test]
+fn download_skip_existing_leaves_matching_files_alone() {
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let fpm = "php-8.4.12-fpm-linux-x86_64.tar.gz";
+    let mirror = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let base_url = local_mirror(mirror.path(), "bulk", &[name, fpm]);
+    fs::write(&output_path, "existing").unwrap();
+
+    let download = |output: &Path| {
+        let mut cmd = cmd();
+        cmd.args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .arg("--data-dir")
+        .arg(data.path())
+        .args(["--base-url", &base_url, "--skip-existing", "-o"])
+        .arg(output);
+        cmd
+    };
+
+    // A different file is only replaced under the usual overwrite rules.
+    download(&output_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+    download(&output_path).arg("--force").assert().success();
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), name);
+    // The fpm build of the same version shares the receipt.
+    download(&out.path().join("php-fpm.tar.gz"))
+        .args(["-B", "fpm"])
+        .assert()
+        .success();
+
+    // The receipt's SHA-256 proves it; the mirror isn't read again.
+    fs::remove_file(mirror.path().join("bulk").join(name)).unwrap();
+    download(&output_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is up to date (SHA-256 matches)"));
+
+    // Without a receipt, the listed size decides.
+    let elsewhere = out.path().join("php-copy.tar.gz");
+    fs::write(&elsewhere, name).unwrap();
+    download(&elsewhere)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is up to date (size matches)"));
+}
+
+#[cfg(unix)]
+#[test]
+fn download_links_checked_artifacts_from_the_cache() {
+    use std::os::unix::fs::MetadataExt;
+
+    let cache_home = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let cached = cache_home
+        .path()
+        .join("spc-utils/artifacts/bulk")
+        .join(name);
+    fs::create_dir_all(cached.parent().unwrap()).unwrap();
+    fs::write(&cached, "cached").unwrap();
+    let bulk = listing(&[(name, 6)]);
+
+    // The server is unreachable, so only the cache can answer.
+    let download = |output: &Path| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())]);
+        cmd.args(["download", "-C", "bulk", "-V", "8.4.12", "-O", "linux"])
+            .args(["-A", "x86_64", "-B", "cli", "--retries", "0"])
+            .args(["--base-url", "http://127.0.0.1:9", "-o"])
+            .arg(output);
+        cmd
+    };
+
+    let linked = out.path().join("linked.tar.gz");
+    download(&linked)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using cached artifact"))
+        .stdout(predicate::str::contains("Linked to:"));
+    assert_eq!(
+        fs::metadata(&linked).unwrap().ino(),
+        fs::metadata(&cached).unwrap().ino()
+    );
+
+    let copied = out.path().join("copied.tar.gz");
+    download(&copied)
+        .arg("--copy")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Copied to:"));
+    assert_ne!(
+        fs::metadata(&copied).unwrap().ino(),
+        fs::metadata(&cached).unwrap().ino()
+    );
+    fs::write(&copied, "edited").unwrap();
+    assert_eq!(fs::read_to_string(&cached).unwrap(), "cached");
+
+    // Downloading over a linked output replaces the link, not the cache.
+    download(&linked)
+        .args(["--copy", "--force"])
+        .assert()
+        .success();
+    assert_ne!(
+        fs::metadata(&linked).unwrap().ino(),
+        fs::metadata(&cached).unwrap().ino()
+    );
+
+    // A cached copy that no longer matches the listing is fetched again.
+    fs::write(&cached, "damaged!").unwrap();
+    download(&out.path().join("damaged.tar.gz"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "the cached {} is damaged (8 bytes, but 6 are listed)",
+            name
+        )));
+    assert!(!cached.exists());
+}
+
+#[test]
+fn list_formats_artifacts_as_csv() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[
+        ("php-8.3.9-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 200),
+        ("php-8.4.12-fpm-linux-x86_64.tar.gz", 300),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args([
+            "list", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-B", "cli",
+        ])
+        .args(["--format", "csv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "version,build_type,os,arch,size,date,downloads\r\n\
+             8.4.12,cli,linux,x86_64,200,2025-01-15 10:30:00,0\r\n\
+             8.3.9,cli,linux,x86_64,100,2025-01-15 10:30:00,0\r\n",
+        ));
+}
+
+#[test]
+fn list_formats_artifacts_as_markdown() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[("php-8.4.12-cli-linux-x86_64.tar.gz", 200)]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args([
+            "list", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-B", "cli",
+        ])
+        .args(["--format", "markdown"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "| Version | Build type | OS | Arch | Size | Date | Downloads |\n\
+             | --- | --- | --- | --- | --- | --- | --- |\n\
+             | 8.4.12 | cli | linux | x86_64 | 200 | 2025-01-15 10:30:00 | 0 |\n",
+        ));
+}
+
+#[test]
+fn human_output_groups_digits_by_locale_and_keeps_machine_output_raw() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[("php-8.4.12-cli-linux-x86_64.tar.gz", 1234567)]);
+    let list = |lang: &str, format: &str| {
+        cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())])
+            .env_remove("LC_ALL")
+            .env_remove("LC_NUMERIC")
+            .env("LANG", lang)
+            .args([
+                "list", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-B", "cli",
+            ])
+            .args(["--format", format])
+            .assert()
+            .success()
+    };
+
+    list("de_DE.UTF-8", "markdown").stdout(predicate::str::contains("| 1.234.567 |"));
+    list("en_US.UTF-8", "markdown").stdout(predicate::str::contains("| 1,234,567 |"));
+    list("C", "markdown").stdout(predicate::str::contains("| 1234567 |"));
+    list("de_DE.UTF-8", "csv").stdout(predicate::str::contains(",1234567,"));
+}
+
+#[test]
+fn utc_prints_recorded_times_in_utc() {
+    let cache_home = tempdir().unwrap();
+    let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", unix_listing(&["8.4.1"]))]);
+    fs::OpenOptions::new()
+        .write(true)
+        .open(cache_home.path().join("spc-utils/bulk.json"))
+        .unwrap()
+        .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_736_937_000))
+        .unwrap();
+    cmd.env("TZ", "Asia/Tokyo")
+        .args(["cache", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2025-01-15 19:30"));
+
+    cmd_with_cache(cache_home.path(), &[])
+        .env("TZ", "Asia/Tokyo")
+        .args(["cache", "list", "--utc"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2025-01-15 10:30"));
+}
+
+#[test]
+fn latest_explain_traces_resolution_on_stderr() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[
+        ("php-8.3.9-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 200),
+        ("php-8.4.12-fpm-linux-x86_64.tar.gz", 300),
+        ("php-8.4.12-cli-linux-aarch64.tar.gz", 400),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args([
+            "latest",
+            "-C",
+            "bulk",
+            "-O",
+            "linux",
+            "-A",
+            "x86_64",
+            "--explain",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"^Latest Version: 8\.4\.12 \(cached \d+s ago, expires in \d+[hms]\)\n$",
+            )
+            .unwrap(),
+        )
+        .stderr(predicate::str::contains("Build type needle: cli (default)"))
+        .stderr(predicate::str::contains("Listing has 4 entries (cached "))
+        .stderr(predicate::str::contains(
+            "Rejected 1 entries: name lacks arch 'x86_64'",
+        ))
+        .stderr(predicate::str::contains(
+            "Rejected 1 entries: name lacks build type 'cli'",
+        ))
+        .stderr(predicate::str::contains(
+            "Selected 8.4.12 (php-8.4.12-cli-linux-x86_64.tar.gz)",
+        ));
+}
+
+#[test]
+fn list_new_shows_artifacts_added_since_previous_run() {
+    let cache_home = tempdir().unwrap();
+    let list_new = |bulk: String, build_type: &str| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", bulk)]);
+        cmd.args(["list", "-C", "bulk", "-O", "linux", "-A", "x86_64", "--new"])
+            .args(["-B", build_type]);
+        cmd
+    };
+
+    // The snapshot holds every artifact, not only the cli ones asked for.
+    list_new(
+        listing(&[
+            ("php-8.4.11-cli-linux-x86_64.tar.gz", 100),
+            ("php-8.4.11-fpm-linux-x86_64.tar.gz", 100),
+        ]),
+        "cli",
+    )
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("Recorded 2 artifacts"));
+
+    let bulk = listing(&[
+        ("php-8.4.11-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.11-fpm-linux-x86_64.tar.gz", 100),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 200),
+    ]);
+    list_new(bulk.clone(), "cli")
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "NEW php-8.4.12-cli-linux-x86_64.tar.gz\n",
+        ));
+    list_new(bulk.clone(), "cli")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    list_new(bulk, "fpm")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn list_print0_terminates_entries_with_nul() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[
+        ("php-8.3.9-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 200),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args(["list", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("8.4.12\08.3.9\0"));
+}
+
+#[test]
+fn dropped_download_is_retried_from_where_it_stopped() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    // The first response is cut off halfway; the retry must ask for the rest.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let ranges = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&ranges);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let read = stream.read(&mut request).unwrap_or(0);
+            let request = String::from_utf8_lossy(&request[..read]).to_lowercase();
+            // No checksum is published, so only the artifact is served.
+            if !request.starts_with("get /bulk/php-") || request.contains(".sha256 ") {
+                let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+                continue;
+            }
+
+            let range = request
+                .lines()
+                .find_map(|line| line.strip_prefix("range: "))
+                .map(str::to_string);
+            seen.lock().unwrap().push(range.clone());
+            let response: &[u8] = match range.as_deref() {
+                None => b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nfirst",
+                Some("bytes=5-") => {
+                    b"HTTP/1.1 206 Partial Content\r\nContent-Length: 5\r\n\r\nhalf."
+                }
+                Some(_) => b"HTTP/1.1 416 Range Not Satisfiable\r\nContent-Length: 0\r\n\r\n",
+            };
+            let _ = stream.write_all(response);
+        }
+    });
+
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    cmd()
+        .args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args([
+            "--no-cache",
+            "--base-url",
+            &base_url,
+            "--retry-delay",
+            "10ms",
+        ])
+        .arg("-o")
+        .arg(&output_path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("retrying in"));
+
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "firsthalf.");
+    assert_eq!(
+        *ranges.lock().unwrap(),
+        [None, Some("bytes=5-".to_string())]
+    );
+
+    // Without retries the first cut-off is final.
+    cmd()
+        .args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--no-cache", "--base-url", &base_url, "--retries", "0"])
+        .arg("-o")
+        .arg(out.path().join("again.tar.gz"))
+        .assert()
+        .failure();
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupted_download_removes_partial_file() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    // A server that starts a large artifact response and then stalls, and
+    // has nothing else to offer.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let read = stream.read(&mut request).unwrap_or(0);
+            if request[..read].starts_with(b"GET /bulk/php-") {
+                let _ =
+                    stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 1000000\r\n\r\npartial");
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_secs(30));
+                    drop(stream);
+                });
+            } else {
+                let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+            }
+        }
+    });
+
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_spc-utils"))
+        .args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--no-cache", "--base-url", &base_url, "-o"])
+        .arg(&output_path)
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let started = Instant::now();
+    while !output_path.exists() {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "download never started"
+        );
+        thread::sleep(Duration::from_millis(20));
+    }
+    thread::sleep(Duration::from_millis(200));
+
+    std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+
+    assert_eq!(child.wait().unwrap().code(), Some(130));
+    assert!(!output_path.exists());
+}
+
+#[test]
+fn timeout_total_aborts_a_stalled_download() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    // A server that lists one artifact, then starts sending it and stalls.
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let body = listing(&[(name, 1000000)]);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let read = stream.read(&mut request).unwrap_or(0);
+            if !request[..read].starts_with(b"GET /bulk/php-") {
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                continue;
+            }
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 1000000\r\n\r\npartial");
+            thread::spawn(move || {
+                thread::sleep(Duration::from_secs(30));
+                drop(stream);
+            });
+        }
+    });
+
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let started = Instant::now();
+    cmd()
+        .args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args([
+            "--no-cache",
+            "--timeout-total",
+            "2s",
+            "--base-url",
+            &base_url,
+            "-o",
+        ])
+        .arg(&output_path)
+        .assert()
+        .code(124)
+        .stdout(predicate::str::contains("Downloading from"))
+        .stderr(predicate::str::contains("Timed out after 2s"));
+
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert!(!output_path.exists());
+}
+
+#[test]
+fn unwritable_cache_dir_falls_back_to_memory() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let body = listing(&[(name, name.len() as u64)]);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let read = stream.read(&mut request).unwrap_or(0);
+            let contents = if request[..read].starts_with(b"GET /bulk/php-") {
+                name
+            } else {
+                body.as_str()
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                contents.len(),
+                contents
+            );
+        }
+    });
+
+    // Not even root can create a directory below a regular file.
+    let home = tempdir().unwrap();
+    let blocker = home.path().join("blocker");
+    fs::write(&blocker, "").unwrap();
+    let cache_dir = blocker.join("spc-utils");
+    let output_path = home.path().join("php.tar.gz");
+
+    let output = cmd()
+        .args(["download", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", &base_url, "--cache-dir"])
+        .arg(&cache_dir)
+        .arg("-o")
+        .arg(&output_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.matches("Warning").count(), 1, "{}", stderr);
+    assert!(stderr.contains("not writable, caching in memory"));
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), name);
+}
+
+#[test]
+fn listing_is_fetched_once_per_invocation() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        thread,
+    };
+
+    let body = listing(&[
+        ("php-8.3.20-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 100),
+    ]);
+    let requests = Arc::new(AtomicUsize::new(0));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let counter = Arc::clone(&requests);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+
+    // Each target resolves both its newest patch and the newest release
+    // overall, all from the same listing.
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        r#"
+[targets.cli]
+category = "bulk"
+version = "8.4.12"
+os = "linux"
+arch = "x86_64"
+
+[targets.legacy]
+category = "bulk"
+version = "8.3.20"
+os = "linux"
+arch = "x86_64"
+"#,
+    )
+    .unwrap();
+
+    let cache_home = tempdir().unwrap();
+    cmd_with_cache(cache_home.path(), &[])
+        .current_dir(project.path())
+        .args(["check-update", "--no-cache", "--base-url", &base_url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("newest is 8.4.12"));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn malformed_listing_entries_are_skipped() {
+    let mirror = tempdir().unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &[
+            "php-8.4.10-cli-linux-x86_64.tar.gz",
+            "php-8.4.12-cli-linux-x86_64.tar.gz",
+        ],
+    );
+    let index = mirror.path().join("bulk/index.json");
+    let valid = fs::read_to_string(&index).unwrap();
+    fs::write(
+        &index,
+        valid.replacen(
+            '[',
+            r#"[{"name":"php-8.4.13-cli-linux-x86_64.tar.gz","size":"huge"},"#,
+            1,
+        ),
+    )
+    .unwrap();
+
+    cmd()
+        .args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", &base_url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Latest Version: 8.4.12"))
+        .stderr(predicate::str::contains(
+            "Warning: skipped 1 of 3 listing entries that could not be read",
+        ));
+
+    fs::write(&index, r#"[{"name":"php-8.4.13-cli-linux-x86_64.tar.gz"}]"#).unwrap();
+    cmd()
+        .args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", &base_url])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "none of the 1 listing entries could be read",
+        ));
+}
+
+#[test]
+fn download_fails_early_without_enough_disk_space() {
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+    fs::write(
+        mirror.path().join("bulk/index.json"),
+        listing(&[(name, u64::MAX / 2)]),
+    )
+    .unwrap();
+
+    cmd()
+        .args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--base-url", &base_url, "-o"])
+        .arg(&output_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not enough disk space"));
+    assert!(!output_path.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn download_runs_hooks_with_artifact_env() {
+    let mirror = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        r#"
+[hooks]
+pre_download = "echo pre $VERSION $PATH_TO_FILE >> hooks.log"
+post_download = "echo post $SHA256 >> hooks.log"
+"#,
+    )
+    .unwrap();
+
+    cmd()
+        .current_dir(project.path())
+        .args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--base-url", &base_url, "-o", "php.tar.gz"])
+        .assert()
+        .success();
+
+    // sha256 of the artifact's contents, which local_mirror sets to its name
+    assert_eq!(
+        fs::read_to_string(project.path().join("hooks.log")).unwrap(),
+        "pre 8.4.12 php.tar.gz\n\
+         post 820968ef0733c17a73adb6c822bc6a689c399ee2623afe337d73ae39a4b06105\n"
+    );
+}
+
+#[test]
+fn failing_pre_download_hook_skips_download() {
+    let mirror = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &["php-8.4.12-cli-linux-x86_64.tar.gz"],
+    );
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        "[hooks]\npre_download = \"exit 3\"\n",
+    )
+    .unwrap();
+
+    cmd()
+        .current_dir(project.path())
+        .args([
+            "download", "-C", "bulk", "-V", "8.4.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--base-url", &base_url, "-o", "php.tar.gz"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pre_download hook failed"));
+    assert!(!project.path().join("php.tar.gz").exists());
+}
+
+#[test]
+fn latest_falls_back_to_stale_cache_when_offline() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[("php-8.4.12-cli-linux-x86_64.tar.gz", 200)]);
+    let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", bulk)]);
+
+    let two_days_ago =
+        std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 86400 + 60);
+    fs::File::options()
+        .write(true)
+        .open(cache_home.path().join("spc-utils/bulk.json"))
+        .unwrap()
+        .set_modified(two_days_ago)
+        .unwrap();
+
+    cmd.args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", "http://127.0.0.1:9"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::diff(
+            "Latest Version: 8.4.12 (stale, fetched 2 days ago)\n",
+        ))
+        .stderr(predicate::str::contains("using the cached listing"));
+}
+
+#[test]
+fn max_cache_age_refetches_older_cache() {
+    let cache_home = tempdir().unwrap();
+    let stale = listing(&[("php-8.4.10-cli-linux-x86_64.tar.gz", 200)]);
+
+    let latest = |extra: &[&str]| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", stale.clone())]);
+        fs::File::options()
+            .write(true)
+            .open(cache_home.path().join("spc-utils/bulk.json"))
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(120))
+            .unwrap();
+        cmd.args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+            .args(["--base-url", "http://127.0.0.1:9"])
+            .args(extra);
+        cmd
+    };
+
+    latest(&["--max-cache-age", "1h"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("8.4.10 (cached 2m ago"));
+
+    // Too old for this run: the refetch fails, so the stale entry is used.
+    latest(&["--max-cache-age", "1m"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("using the cached listing"));
+}
+
+#[test]
+fn invalid_max_cache_age_is_rejected() {
+    cmd()
+        .args(["latest", "--max-cache-age", "5y"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid duration unit"));
+
+    cmd()
+        .args(["latest", "--max-cache-age", "18446744073709551d"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("is too long"));
+}
+
+#[test]
+fn list_does_not_page_when_stdout_is_not_a_terminal() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[("php-8.4.12-cli-linux-x86_64.tar.gz", 200)]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .env("PAGER", "false")
+        .args(["list", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("8.4.12\n"));
+}
+
+#[test]
+fn check_update_classifies_update() {
+    let cache_home = tempdir().unwrap();
+    let bulk = unix_listing(&["8.3.10", "8.3.12", "8.4.1"]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())])
+        .args(["check-update", "-C", "bulk", "-V", "8.3.10"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Update available: 8.3.10 -> 8.3.12 (patch update)",
+        ));
+
+    let output = cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args(["check-update", "-C", "bulk", "-V", "8.3.12", "--json"])
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["current"], "8.3.12");
+    assert_eq!(report["latest"], "8.3.12");
+    assert_eq!(report["update_available"], false);
+    assert_eq!(report["update"], serde_json::Value::Null);
+}
+
+#[test]
+fn check_update_confirm_online_revalidates_only_apparent_updates() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        thread,
+        time::{Duration, SystemTime},
+    };
+
+    let body = unix_listing(&["8.4.10", "8.4.12", "8.4.13"]);
+    let requests = Arc::new(AtomicUsize::new(0));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let counter = Arc::clone(&requests);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+
+    // A listing from last week that predates 8.4.13.
+    let cache_home = tempdir().unwrap();
+    let cached = unix_listing(&["8.4.10", "8.4.12"]);
+    let check = |version: &str| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", cached.clone())]);
+        let last_week = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(cache_home.path().join("spc-utils/bulk.json"))
+            .unwrap()
+            .set_modified(last_week)
+            .unwrap();
+        cmd.args([
+            "check-update",
+            "-C",
+            "bulk",
+            "-V",
+            version,
+            "--confirm-online",
+        ])
+        .args(["--base-url", &base_url]);
+        cmd
+    };
+
+    check("8.4.12")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "You have the latest version: 8.4.12 (stale, fetched 7 days ago)",
+        ));
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+
+    check("8.4.10")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Update available: 8.4.10 -> 8.4.13",
+        ));
+    assert!(requests.load(Ordering::SeqCst) > 0);
+}
+
+#[test]
+fn compare_reports_relation_availability_and_changelogs() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[
+        ("php-8.3.10-cli-linux-x86_64.tar.gz", 2 * 1024 * 1024),
+        ("php-8.3.12-cli-linux-x86_64.tar.gz", 2 * 1024 * 1024),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args([
+            "compare", "8.3.10", "8.4.1", "-C", "bulk", "-O", "linux", "-A", "x86_64",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "8.3.10 is older than 8.4.1 (minor difference)\n\
+             \x20 major: 8 (same)\n\
+             \x20 minor: 3 -> 4\n\
+             \x20 patch: 10 -> 1\n\
+             \n\
+             Availability (bulk):\n\
+             \x20 8.3.10     php-8.3.10-cli-linux-x86_64.tar.gz  2.0 MB\n\
+             \x20 8.4.1      not available\n\
+             \n\
+             Changelogs:\n\
+             \x20 https://www.php.net/ChangeLog-8.php#8.3.12\n\
+             \x20 https://www.php.net/ChangeLog-8.php#8.4.1\n",
+        ));
+}
+
+#[test]
+fn end_of_life_versions_are_flagged() {
+    let cache_home = tempdir().unwrap();
+    let bulk = unix_listing(&["8.0.30"]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())])
+        .args(["latest", "-C", "bulk", "-V", "8.0"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "PHP 8.0 reached end of life on 2023-11-26",
+        ));
+
+    let output = cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args(["check-update", "-C", "bulk", "-V", "8.0.30", "--json"])
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["eol_date"], "2023-11-26");
+    assert_eq!(report["supported"], false);
+}
+
+#[test]
+fn check_update_flags_security_releases() {
+    let cache_home = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    let feed = project.path().join("releases.json");
+    fs::write(
+        &feed,
+        r#"{"8.3.12":{"tags":["security"]},"8.3.11":{"tags":[]},"8.3.10":{"tags":["security"]}}"#,
+    )
+    .unwrap();
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        format!("php_releases_url = \"file://{}\"\n", feed.display()),
+    )
+    .unwrap();
+    let bulk = unix_listing(&["8.3.10", "8.3.11", "8.3.12"]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())])
+        .current_dir(project.path())
+        .args(["check-update", "-C", "bulk", "-V", "8.3.10"])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::contains(
+            "8.3.10 -> 8.3.12 (patch update, security)",
+        ));
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .current_dir(project.path())
+        .args(["check-update", "-C", "bulk", "-V", "8.3.12"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn download_with_checksums_saves_sibling_files() {
+    let cache_home = tempdir().unwrap();
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+    fs::write(
+        mirror.path().join("bulk").join(format!("{}.sha256", name)),
+        "deadbeef",
+    )
+    .unwrap();
+    let output_path = out.path().join("php.tar.gz");
+
+    cmd()
+        .env("XDG_CACHE_HOME", cache_home.path())
+        .args(["download", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--with-checksums", "--base-url", &base_url, "-o"])
+        .arg(&output_path)
+        .assert()
+        .success();
+
+    let checksum = out.path().join("php.tar.gz.sha256");
+    assert_eq!(fs::read_to_string(checksum).unwrap(), "deadbeef");
+    assert!(!out.path().join("php.tar.gz.sig").exists());
+}
+
+#[test]
+fn verify_install_detects_modified_download() {
+    let cache_home = tempdir().unwrap();
+    let data_home = tempdir().unwrap();
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &["php-8.4.12-cli-linux-x86_64.tar.gz"],
+    );
+    let output_path = out.path().join("php.tar.gz");
+
+    cmd()
+        .env("XDG_CACHE_HOME", cache_home.path())
+        .env("XDG_DATA_HOME", data_home.path())
+        .args(["download", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", &base_url, "-o"])
+        .arg(&output_path)
+        .assert()
+        .success();
+
+    cmd()
+        .env("XDG_DATA_HOME", data_home.path())
+        .args(["verify-install", "8.4.12"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[ok]"));
+
+    fs::write(&output_path, "tampered").unwrap();
+
+    cmd()
+        .env("XDG_DATA_HOME", data_home.path())
+        .arg("verify-install")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("[modified]"));
+}
+
+#[test]
+fn download_prefix_requires_system() {
+    cmd()
+        .args(["download", "--prefix", "/opt/php"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--system"));
+}
+
+#[test]
+fn data_dir_can_be_overridden_from_the_environment() {
+    let data_dir = tempdir().unwrap();
+
+    cmd()
+        .env("SPC_UTILS_DATA_DIR", data_dir.path())
+        .arg("verify-install")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            data_dir.path().join("receipts").display().to_string(),
+        ));
+}
+
+#[test]
+fn migrate_moves_data_dir_and_updates_config() {
+    let project = tempdir().unwrap();
+    let cache_dir = tempdir().unwrap();
+    let state = tempdir().unwrap();
+    let old = state.path().join("old");
+    let new = state.path().join("new");
+
+    fs::create_dir_all(old.join("receipts")).unwrap();
+    fs::write(
+        old.join("receipts").join("8.4.12.json"),
+        r#"{"version":"8.4.12","url":"","installed_at":"2025-01-15T10:30:00+00:00","files":[]}"#,
+    )
+    .unwrap();
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        format!("data_dir = {:?}\n", old.display().to_string()),
+    )
+    .unwrap();
+
+    cmd()
+        .current_dir(project.path())
+        .env("SPC_UTILS_CACHE_DIR", cache_dir.path())
+        .env_remove("SPC_UTILS_DATA_DIR")
+        .arg("migrate")
+        .arg("--data-dir")
+        .arg(&new)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved data directory"));
+
+    assert!(!old.exists());
+    assert!(new.join("receipts").join("8.4.12.json").exists());
+    let config = fs::read_to_string(project.path().join(".spc-utils.toml")).unwrap();
+    assert!(config.contains(&new.display().to_string()));
+}
+
+#[test]
+fn config_default_build_type_applies_per_os() {
+    let cache_home = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        "[default_build_type]\nlinux = \"fpm\"\n",
+    )
+    .unwrap();
+    let bulk = listing(&[
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.10-fpm-linux-x86_64.tar.gz", 100),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .current_dir(project.path())
+        .args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("8.4.10"));
+}
+
+#[test]
+fn download_reports_artifact_size_before_transfer() {
+    let cache_home = tempdir().unwrap();
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+    fs::write(
+        mirror.path().join("bulk/index.json"),
+        listing(&[(name, 3 * 1024 * 1024)]),
+    )
+    .unwrap();
+
+    cmd()
+        .env("XDG_CACHE_HOME", cache_home.path())
+        .args(["download", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", &base_url, "-o"])
+        .arg(out.path().join("php.tar.gz"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Downloading {} (3.0 MB)",
+            name
+        )));
+}
+
+#[test]
+fn list_json_includes_full_metadata() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[("php-8.4.12-fpm-linux-aarch64.tar.gz", 1234)]);
+
+    let output = cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args([
+            "list", "-C", "bulk", "-O", "linux", "-A", "aarch64", "-B", "fpm",
+        ])
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entry = &json[0];
+    assert_eq!(entry["name"], "php-8.4.12-fpm-linux-aarch64.tar.gz");
+    assert_eq!(
+        entry["full_path"],
+        "/static-php-cli/php-8.4.12-fpm-linux-aarch64.tar.gz"
+    );
+    assert_eq!(entry["size"], 1234);
+    assert_eq!(entry["last_modified"], "2025-01-15T10:30:00+00:00");
+    assert_eq!(entry["version"], "8.4.12");
+    assert_eq!(entry["build_type"], "fpm");
+    assert_eq!(entry["os"], "linux");
+    assert_eq!(entry["arch"], "aarch64");
+}
+
+#[test]
+fn list_and_latest_agree_on_windows_and_unix_names() {
+    let cache_home = tempdir().unwrap();
+    let windows = listing(&[
+        ("php-8.3.12-cli-win.zip", 1),
+        ("php-8.4.1-cli-win.zip", 1),
+        ("php-8.4.2-micro-win.zip", 1),
+        ("php-8.4.5-cli-linux-x86_64.tar.gz", 1),
+    ]);
+    let bulk = listing(&[
+        ("php-8.4.10-cli-linux-aarch64.tar.gz", 1),
+        ("php-8.4.11-cli-linux-x86_64.tar.gz", 1),
+        ("php-8.4.12-fpm-linux-aarch64.tar.gz", 1),
+    ]);
+    let listings = [("win-max", windows), ("bulk", bulk)];
+
+    let listed = |args: &[&str]| -> Vec<String> {
+        let output = cmd_with_cache(cache_home.path(), &listings)
+            .arg("list")
+            .args(args)
+            .arg("--json")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let json: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+        json.iter()
+            .map(|entry| entry["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // Windows names carry no arch, so -A doesn't exclude them.
+    let windows_args = [
+        "-C", "win-max", "-B", "cli", "-O", "windows", "-A", "x86_64",
+    ];
+    assert_eq!(
+        listed(&windows_args),
+        ["php-8.3.12-cli-win.zip", "php-8.4.1-cli-win.zip"]
+    );
+    cmd_with_cache(cache_home.path(), &listings)
+        .arg("latest")
+        .args(windows_args)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Latest Version: 8.4.1 ("));
+
+    let unix_args = ["-C", "bulk", "-B", "cli", "-O", "linux", "-A", "aarch64"];
+    assert_eq!(listed(&unix_args), ["php-8.4.10-cli-linux-aarch64.tar.gz"]);
+    cmd_with_cache(cache_home.path(), &listings)
+        .arg("latest")
+        .args(unix_args)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Latest Version: 8.4.10 ("));
+}
+
+#[test]
+fn latest_prints_bare_name_and_url() {
+    let mirror = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+
+    cmd()
+        .args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--base-url", &base_url, "--with-version", "--name", "--url"])
+        .assert()
+        .success()
+        .stdout(format!("8.4.12\n{}\n{}/bulk/{}\n", name, base_url, name));
+}
+
+#[test]
+fn latest_quiet_prints_only_the_version() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[("php-8.4.12-cli-linux-x86_64.tar.gz", 100)]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-q"])
+        .assert()
+        .success()
+        .stdout("8.4.12\n")
+        .stderr(predicate::str::contains("Listing (cached"));
+}
+
+#[test]
+fn url_prints_only_the_resolved_download_url() {
+    let mirror = tempdir().unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &[
+            "php-8.3.9-fpm-linux-x86_64.tar.gz",
+            "php-8.3.12-fpm-linux-x86_64.tar.gz",
+            "php-8.3.12-cli-linux-x86_64.tar.gz",
+            "php-8.4.1-fpm-linux-x86_64.tar.gz",
+        ],
+    );
+    let url = |extra: &[&str]| {
+        let mut cmd = cmd();
+        cmd.args(["url", "-C", "bulk", "-V", "8.3", "-B", "fpm", "-O", "linux"])
+            .args(["-A", "x86_64", "--base-url", &base_url])
+            .args(extra);
+        cmd
+    };
+
+    url(&[]).assert().success().stdout(format!(
+        "{}/bulk/php-8.3.12-fpm-linux-x86_64.tar.gz\n",
+        base_url
+    ));
+
+    let output = url(&["--json"]).output().unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[0]["version"], "8.3.12");
+    assert_eq!(json[0]["name"], "php-8.3.12-fpm-linux-x86_64.tar.gz");
+    assert!(json[0]["target"].is_null());
+}
+
+#[test]
+fn fetch_matrix_lays_out_every_platform_on_a_common_version() {
+    let mirror = tempdir().unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &[
+            "php-8.4.1-cli-linux-x86_64.tar.gz",
+            "php-8.4.1-cli-linux-aarch64.tar.gz",
+            "php-8.4.1-cli-macos-x86_64.tar.gz",
+            "php-8.4.1-cli-macos-aarch64.tar.gz",
+            // Only one platform has 8.4.2 yet, so the matrix stays on 8.4.1.
+            "php-8.4.2-cli-linux-x86_64.tar.gz",
+        ],
+    );
+    let dest = tempdir().unwrap();
+
+    cmd()
+        .args([
+            "fetch-matrix",
+            "-C",
+            "bulk",
+            "-V",
+            "8.4",
+            "--base-url",
+            &base_url,
+        ])
+        .arg("--dest")
+        .arg(dest.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Fetching PHP 8.4.1 for 4 platforms",
+        ));
+
+    let root = dest.path().join("8.4.1");
+    for platform in [
+        "linux-x86_64",
+        "linux-aarch64",
+        "macos-x86_64",
+        "macos-aarch64",
+    ] {
+        let name = format!("php-8.4.1-cli-{}.tar.gz", platform);
+        assert_eq!(
+            fs::read_to_string(root.join(platform).join(&name)).unwrap(),
+            name
+        );
+    }
+    let sums = fs::read_to_string(root.join("SHA256SUMS")).unwrap();
+    assert_eq!(sums.lines().count(), 4);
+    assert!(sums.contains("  linux-aarch64/php-8.4.1-cli-linux-aarch64.tar.gz\n"));
+
+    cmd()
+        .args([
+            "fetch-matrix",
+            "-C",
+            "bulk",
+            "-B",
+            "fpm",
+            "--base-url",
+            &base_url,
+        ])
+        .arg("--dest")
+        .arg(dest.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No matching builds for: fpm linux-x86_64",
+        ));
+
+    cmd()
+        .args([
+            "fetch-matrix",
+            "-C",
+            "bulk",
+            "-O",
+            "windows",
+            "--base-url",
+            &base_url,
+        ])
+        .arg("--dest")
+        .arg(dest.path())
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn base_url_can_come_from_the_environment() {
+    let mirror = tempdir().unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &["php-8.3.12-cli-linux-x86_64.tar.gz"],
+    );
+
+    cmd()
+        .args([
+            "url", "-C", "bulk", "-V", "8.3", "-O", "linux", "-A", "x86_64",
+        ])
+        .env("SPC_UTILS_BASE_URL", &base_url)
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}/bulk/php-8.3.12-cli-linux-x86_64.tar.gz\n",
+            base_url
+        ));
+
+    // The flag overrides the environment.
+    cmd()
+        .args([
+            "url", "-C", "bulk", "-V", "8.3.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--base-url", "https://mirror.example.com/spc/"])
+        .env("SPC_UTILS_BASE_URL", &base_url)
+        .assert()
+        .success()
+        .stdout("https://mirror.example.com/spc/bulk/php-8.3.12-cli-linux-x86_64.tar.gz\n");
+}
+
+#[test]
+fn legacy_artifact_names_are_detected_or_configured() {
+    let mirror = tempdir().unwrap();
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &[
+            "php-cli-8.3.12-linux-x86_64.tar.gz",
+            "php-cli-8.4.12-linux-x86_64.tar.gz",
+            "php-fpm-8.4.12-linux-x86_64.tar.gz",
+        ],
+    );
+    let legacy_url = format!("{}/bulk/php-cli-8.4.12-linux-x86_64.tar.gz", base_url);
+
+    // Names are read in either scheme, and built in the one the listing uses.
+    cmd()
+        .args([
+            "latest", "-C", "bulk", "-O", "linux", "-A", "x86_64", "-B", "cli",
+        ])
+        .args(["--base-url", &base_url, "--with-version", "--url"])
+        .assert()
+        .success()
+        .stdout(format!("8.4.12\n{}\n", legacy_url));
+
+    // An exact version needs no listing, so the scheme comes from the config.
+    let project = tempdir().unwrap();
+    let url = |project: &Path| {
+        let mut cmd = cmd();
+        cmd.current_dir(project)
+            .args(["url", "-C", "bulk", "-V", "8.4.12", "-O", "linux"])
+            .args(["-A", "x86_64", "-B", "cli", "--base-url", &base_url]);
+        cmd
+    };
+    url(project.path()).assert().success().stdout(format!(
+        "{}/bulk/php-8.4.12-cli-linux-x86_64.tar.gz\n",
+        base_url
+    ));
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        "[naming_scheme]\nbulk = \"legacy\"\n",
+    )
+    .unwrap();
+    url(project.path())
+        .assert()
+        .success()
+        .stdout(format!("{}\n", legacy_url));
+}
+
+#[test]
+fn list_stable_order_ignores_upstream_order() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[
+        ("php-8.4.1-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.3.9-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 100),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args(["list", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--stable-order", "--no-pager"])
+        .assert()
+        .success()
+        .stdout("8.3.9\n8.4.1\n8.4.12\n");
+}
+
+#[test]
+fn list_artifact_type_ignores_the_host_platform() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[
+        ("php-8.4.1-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.2-cli-macos-aarch64.tar.gz", 100),
+        ("php-8.3.9-cli-win.zip", 100),
+        ("php-8.3.10-fpm-win.zip", 100),
+    ]);
+    let list = |extra: &[&str]| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())]);
+        cmd.args(["list", "-C", "bulk", "--no-pager"]).args(extra);
+        cmd
+    };
+
+    list(&["--artifact-type", "zip"])
+        .assert()
+        .success()
+        .stdout("8.3.9\n");
+    list(&["--artifact-type", "tar.gz"])
+        .assert()
+        .success()
+        .stdout("8.4.2\n8.4.1\n");
+    list(&["--artifact-type", "all", "-B", "fpm"])
+        .assert()
+        .success()
+        .stdout("8.3.10\n");
+    list(&["--artifact-type", "tar.gz", "-O", "linux"])
+        .assert()
+        .success()
+        .stdout("8.4.1\n");
+}
+
+#[test]
+fn version_keywords_resolve_to_newest_match() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[
+        ("php-8.0.30-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 100),
+        ("php-8.3.9-cli-linux-x86_64.tar.gz", 100),
+    ]);
+
+    for version in ["8", "latest"] {
+        cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())])
+            .args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+            .args(["-V", version])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Latest Version: 8.4.12"));
+    }
+}
+
+#[test]
+fn download_with_partial_version_fetches_newest_patch() {
+    let cache_home = tempdir().unwrap();
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let base_url = local_mirror(
+        mirror.path(),
+        "bulk",
+        &[
+            "php-8.4.10-cli-linux-x86_64.tar.gz",
+            "php-8.4.12-cli-linux-x86_64.tar.gz",
+        ],
+    );
+
+    cmd()
+        .env("XDG_CACHE_HOME", cache_home.path())
+        .args(["download", "-C", "bulk", "-V", "8.4", "-O", "linux"])
+        .args(["-A", "x86_64", "--base-url", &base_url, "-o"])
+        .arg(&output_path)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(&output_path).unwrap(),
+        "php-8.4.12-cli-linux-x86_64.tar.gz"
+    );
+}
+
+#[test]
+fn download_no_progress_writes_nothing_to_stderr() {
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+
+    cmd()
+        .args(["download", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+        .args(["--no-progress", "--base-url", &base_url, "-o"])
+        .arg(&output_path)
+        .assert()
+        .success()
+        .stderr("");
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), name);
+}
+
+#[test]
+fn download_prints_sha256_and_checks_expected_hash() {
+    use sha2::{Digest, Sha256};
+
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let output_path = out.path().join("php.tar.gz");
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+    let digest = hex::encode(Sha256::digest(name));
+
+    let download = |expected: &str| {
+        let mut cmd = cmd();
+        cmd.args([
+            "download", "-C", "bulk", "-O", "linux", "-A", "x86_64", "--force",
+        ])
+        .args(["--base-url", &base_url, "--expect-sha256", expected, "-o"])
+        .arg(&output_path);
+        cmd
+    };
+
+    download(&digest.to_uppercase())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("SHA-256: {}", digest)));
+    assert!(output_path.exists());
+
+    let wrong = "0".repeat(64);
+    download(&wrong)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "expected {}, got {}",
+            wrong, digest
+        )));
+    assert!(!output_path.exists());
+
+    download("abc")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid SHA-256"));
+}
+
+#[test]
+fn check_update_shows_newest_patch_and_newest_overall() {
+    let cache_home = tempdir().unwrap();
+    let listing = unix_listing(&["8.2.20", "8.2.29", "8.4.3"]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", listing.clone())])
+        .args(["check-update", "-C", "bulk", "-V", "8.2.20"])
+        .assert()
+        .stdout(predicate::str::contains(
+            "Update available: 8.2.20 -> 8.2.29 (patch update), 8.4.3 (latest)",
+        ));
+
+    cmd_with_cache(cache_home.path(), &[("bulk", listing.clone())])
+        .args(["check-update", "-C", "bulk", "-V", "8.2.29"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "You have the latest 8.2 patch: 8.2.29; newest is 8.4.3 (minor update)",
+        ));
+
+    let output = cmd_with_cache(cache_home.path(), &[("bulk", listing.clone())])
+        .args(["check-update", "-C", "bulk", "-V", "8.2.20", "--json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["latest"], "8.2.29");
+    assert_eq!(json["newest"], "8.4.3");
+}
+
+#[test]
+fn check_update_writes_prometheus_metrics() {
+    let cache_home = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let metrics = out.path().join("spc_utils.prom");
+    let listing = unix_listing(&["8.2.20", "8.2.29", "8.4.3"]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", listing.clone())])
+        .args([
+            "check-update",
+            "-C",
+            "bulk",
+            "-V",
+            "8.2.20",
+            "--metrics-file",
+        ])
+        .arg(&metrics)
+        .assert()
+        .success();
+
+    let text = fs::read_to_string(&metrics).unwrap();
+    assert!(text.contains("# TYPE spc_utils_update_available gauge"));
+    assert!(text.contains("spc_utils_update_available{category=\"bulk\",current=\"8.2.20\"} 1"));
+    assert!(text.contains("spc_utils_latest_version_info{category=\"bulk\",version=\"8.2.29\"} 1"));
+    assert!(text.contains("spc_utils_cache_age_seconds{category=\"bulk\"} "));
+    assert!(text.contains("spc_utils_last_check_timestamp_seconds{category=\"bulk\"} "));
+    assert!(!out.path().join("spc_utils.prom.tmp").exists());
+
+    cmd_with_cache(cache_home.path(), &[("bulk", listing)])
+        .args([
+            "check-update",
+            "-C",
+            "bulk",
+            "-V",
+            "8.4.3",
+            "--metrics-file",
+        ])
+        .arg(&metrics)
+        .assert()
+        .success();
+    let text = fs::read_to_string(&metrics).unwrap();
+    assert!(text.contains("spc_utils_update_available{category=\"bulk\",current=\"8.4.3\"} 0"));
+}
+
+#[test]
+fn check_update_reads_partial_versions_as_newest_match() {
+    let cache_home = tempdir().unwrap();
+    let listing = unix_listing(&["8.2.20", "8.2.29", "8.4.3"]);
+    let check = |version: &str| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", listing.clone())]);
+        cmd.args(["check-update", "-C", "bulk", "-V", version]);
+        cmd
+    };
+
+    check("8.2")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "You have the latest 8.2 patch: 8.2.29; newest is 8.4.3",
+        ));
+    check("8")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "You have the latest version: 8.4.3",
+        ));
+    check("latest")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "You have the latest version: 8.4.3",
+        ));
+    check("8.3")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No cli build"));
+}
+
+#[test]
+fn check_update_fail_on_gates_exit_status_by_update_kind() {
+    let cache_home = tempdir().unwrap();
+    let listing = unix_listing(&["8.2.29", "8.4.3"]);
+    let check = |version: &str, fail_on: &str| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", listing.clone())]);
+        cmd.args([
+            "check-update",
+            "-C",
+            "bulk",
+            "-V",
+            version,
+            "--fail-on",
+            fail_on,
+        ]);
+        cmd
+    };
+
+    // 8.2.29 is the newest 8.2 patch, but 8.4 is a minor update away.
+    check("8.2.29", "patch").assert().code(5);
+    check("8.2.29", "minor").assert().code(5);
+    check("8.2.29", "major").assert().success();
+    check("8.4.3", "patch").assert().success();
+
+    cmd()
+        .args(["check-update", "-V", "8.4.3", "--fail-on", "build"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn cache_list_json_reports_each_category() {
+    let cache_home = tempdir().unwrap();
+    let bulk = unix_listing(&["8.3.10", "8.4.1"]);
+
+    let output = cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args(["cache", "list", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = json.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["category"], "bulk");
+    assert_eq!(entries[0]["entry_count"], 8);
+    assert_eq!(entries[0]["expired"], false);
+    assert!(entries[0]["size"].as_u64().unwrap() > 0);
+    assert!(entries[0]["path"].as_str().unwrap().ends_with("bulk.json"));
+    assert!(entries[0]["fetched_at"].is_string());
+    assert!(entries[0]["expires_at"].is_string());
+}
+
+#[test]
+fn sync_verify_only_detects_corruption_and_sync_repairs_it() {
+    let mirror = tempdir().unwrap();
+    let dest = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+    fs::write(
+        mirror.path().join("bulk").join(format!("{}.sha256", name)),
+        format!(
+            "820968ef0733c17a73adb6c822bc6a689c399ee2623afe337d73ae39a4b06105  {}\n",
+            name
+        ),
+    )
+    .unwrap();
+
+    let sync = || {
+        let mut cmd = cmd();
+        cmd.args(["sync", "-C", "bulk", "--base-url", &base_url, "--dest"])
+            .arg(dest.path());
+        cmd
+    };
+
+    sync()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bulk: 1 downloaded, 0 up to date"));
+
+    // Same size, different contents: only a checksum catches it.
+    let artifact = dest.path().join("bulk").join(name);
+    fs::write(&artifact, name.chars().rev().collect::<String>()).unwrap();
+
+    sync()
+        .arg("--verify-only")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "bulk: 0 verified, 0 without checksum, 1 failed",
+        ))
+        .stderr(predicate::str::contains(format!(
+            "[checksum mismatch] bulk/{}",
+            name
+        )));
+
+    sync()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bulk: 1 downloaded, 0 up to date"))
+        .stderr(predicate::str::contains(
+            "checksum mismatch, downloading again",
+        ));
+    assert_eq!(fs::read_to_string(&artifact).unwrap(), name);
+
+    sync()
+        .arg("--verify-only")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "bulk: 1 verified, 0 without checksum, 0 failed",
+        ));
+}
+
+#[test]
+fn sync_delete_prunes_artifacts_removed_upstream() {
+    let cache_home = tempdir().unwrap();
+    let dest = tempdir().unwrap();
+    let kept = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let retracted = "php-8.4.11-cli-linux-x86_64.tar.gz";
+
+    let bulk = dest.path().join("bulk");
+    fs::create_dir_all(&bulk).unwrap();
+    fs::write(bulk.join(kept), "abc").unwrap();
+    fs::write(bulk.join(retracted), "abc").unwrap();
+    fs::write(bulk.join(format!("{}.sha256", retracted)), "abc").unwrap();
+    fs::write(bulk.join("index.html"), "").unwrap();
+
+    let sync = |extra: &[&str]| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", listing(&[(kept, 3)]))]);
+        cmd.args(["sync", "-C", "bulk", "--delete"])
+            .args(extra)
+            .arg("--dest")
+            .arg(dest.path());
+        cmd
+    };
+
+    sync(&["--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Would remove bulk/{}",
+            retracted
+        )))
+        .stdout(predicate::str::contains("1 up to date, 2 to remove"));
+    assert!(bulk.join(retracted).exists());
+
+    sync(&[])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 up to date, 2 removed"));
+    assert!(!bulk.join(retracted).exists());
+    assert!(!bulk.join(format!("{}.sha256", retracted)).exists());
+    assert!(bulk.join(kept).exists());
+    assert!(bulk.join("index.html").exists());
+
+    // A listing the server didn't just serve may predate newer uploads.
+    fs::write(bulk.join(retracted), "abc").unwrap();
+    let two_days_ago =
+        std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 86400 + 60);
+    let offline = || {
+        let cmd = sync(&["--base-url", "http://127.0.0.1:9"]);
+        fs::File::options()
+            .write(true)
+            .open(cache_home.path().join("spc-utils/bulk.json"))
+            .unwrap()
+            .set_modified(two_days_ago)
+            .unwrap();
+        cmd
+    };
+    offline()
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not pruning bulk"));
+    assert!(bulk.join(retracted).exists());
+}
+
+#[test]
+fn sync_spaces_out_concurrent_downloads() {
+    let mirror = tempdir().unwrap();
+    let dest = tempdir().unwrap();
+    let names = [
+        "php-8.4.10-cli-linux-x86_64.tar.gz",
+        "php-8.4.11-cli-linux-x86_64.tar.gz",
+        "php-8.4.12-cli-linux-x86_64.tar.gz",
+    ];
+    let base_url = local_mirror(mirror.path(), "bulk", &names);
+
+    let started = std::time::Instant::now();
+    cmd()
+        .args(["sync", "-C", "bulk", "--base-url", &base_url])
+        .args(["--concurrency", "2", "--delay-ms", "150", "--dest"])
+        .arg(dest.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bulk: 3 downloaded, 0 up to date"));
+    assert!(started.elapsed() >= std::time::Duration::from_millis(300));
+
+    for name in names {
+        assert_eq!(
+            fs::read_to_string(dest.path().join("bulk").join(name)).unwrap(),
+            name
+        );
+    }
+
+    cmd()
+        .args(["sync", "--concurrency", "0", "--dest"])
+        .arg(dest.path())
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn latest_stable_channel_skips_recent_releases() {
+    let cache_home = tempdir().unwrap();
+    let released = listing(&[("php-8.4.10-cli-linux-x86_64.tar.gz", 10)]);
+    // Far enough in the future to count as brand new on any test run.
+    let brand_new = listing(&[("php-8.4.12-cli-linux-x86_64.tar.gz", 10)])
+        .replace("2025-01-15 10:30:00", "2999-01-01 00:00:00");
+    let bulk = format!(
+        "[{},{}]",
+        released.trim_matches(['[', ']']),
+        brand_new.trim_matches(['[', ']'])
+    );
+
+    let latest = |channel: &str| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())]);
+        cmd.args(["latest", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+            .args(["--channel", channel]);
+        cmd
+    };
+
+    latest("fresh")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Latest Version: 8.4.12"));
+    latest("stable")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Latest Version: 8.4.10"));
+}
+
+#[test]
+fn as_of_ignores_artifacts_published_after_the_date() {
+    let cache_home = tempdir().unwrap();
+    let published = |name: &str, at: &str| {
+        listing(&[(name, 10)])
+            .replace("2025-01-15 10:30:00", at)
+            .trim_matches(['[', ']'])
+            .to_string()
+    };
+    let bulk = format!(
+        "[{},{},{}]",
+        published("php-8.4.1-cli-linux-x86_64.tar.gz", "2024-11-21 09:00:00"),
+        published("php-8.4.2-cli-linux-x86_64.tar.gz", "2024-12-01 23:00:00"),
+        published("php-8.4.3-cli-linux-x86_64.tar.gz", "2024-12-02 00:30:00"),
+    );
+    let run = |command: &str, as_of: &str| {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[("bulk", bulk.clone())]);
+        cmd.args([command, "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+            .args(["--as-of", as_of]);
+        cmd
+    };
+
+    // The whole of the given day counts.
+    run("latest", "2024-12-01")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Latest Version: 8.4.2"));
+    run("latest", "2024-11-30")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Latest Version: 8.4.1"));
+
+    let output = run("list", "2024-12-01").arg("--json").output().unwrap();
+    assert!(output.status.success());
+    let json: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let versions: Vec<&str> = json
+        .iter()
+        .map(|entry| entry["version"].as_str().unwrap())
+        .collect();
+    assert_eq!(versions, ["8.4.1", "8.4.2"]);
+
+    run("latest", "12/01/2024").assert().code(2);
+}
+
+#[test]
+fn download_extract_unpacks_tarballs_and_zips() {
+    use std::io::Write;
+
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+
+    let unix = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let bulk = mirror.path().join("bulk");
+    fs::create_dir_all(&bulk).unwrap();
+    tarball(
+        &bulk.join(unix),
+        &[("php", "PHP"), ("licenses/LICENSE", "license")],
+    );
+    let size = fs::metadata(bulk.join(unix)).unwrap().len();
+    fs::write(bulk.join("index.json"), listing(&[(unix, size)])).unwrap();
+
+    let windows = "php-8.4.12-cli-win.zip";
+    let win_max = mirror.path().join("windows/spc-max");
+    fs::create_dir_all(&win_max).unwrap();
+    let mut zip = zip::ZipWriter::new(fs::File::create(win_max.join(windows)).unwrap());
+    for (name, contents) in [("php.exe", "PHP.EXE"), ("ext/README", "readme")] {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+    let size = fs::metadata(win_max.join(windows)).unwrap().len();
+    fs::write(win_max.join("index.json"), listing(&[(windows, size)])).unwrap();
+
+    let base_url = format!("file://{}", mirror.path().display());
+    for (category, os, archive, dir, binary, contents) in [
+        ("bulk", "linux", unix, "unix", "php", "PHP"),
+        ("win-max", "windows", windows, "win", "php.exe", "PHP.EXE"),
+    ] {
+        cmd()
+            .args(["download", "-C", category, "-V", "8.4.12", "-O", os])
+            .args(["-A", "x86_64", "-B", "cli", "--no-cache"])
+            .args(["--base-url", &base_url, "-o"])
+            .arg(out.path().join(archive))
+            .arg("--extract")
+            .arg(out.path().join(dir))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Extracted 2 files to"));
+        assert_eq!(
+            fs::read_to_string(out.path().join(dir).join(binary)).unwrap(),
+            contents
+        );
+    }
+    assert!(out.path().join("unix/licenses/LICENSE").is_file());
+    assert!(out.path().join("win/ext/README").is_file());
+}
+
+#[cfg(unix)]
+#[test]
+fn download_extract_makes_binaries_executable_and_renames() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let name = "php-8.4.12-fpm-linux-x86_64.tar.gz";
+
+    // Packed without the executable bit, and not at the top level.
+    let bulk = mirror.path().join("bulk");
+    fs::create_dir_all(&bulk).unwrap();
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(
+        fs::File::create(bulk.join(name)).unwrap(),
+        flate2::Compression::default(),
+    ));
+    for (path, contents) in [("sbin/php-fpm", "FPM"), ("README", "readme")] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, path, contents.as_bytes())
+            .unwrap();
+    }
+    archive.into_inner().unwrap().finish().unwrap();
+    let size = fs::metadata(bulk.join(name)).unwrap().len();
+    fs::write(bulk.join("index.json"), listing(&[(name, size)])).unwrap();
+    let base_url = format!("file://{}", mirror.path().display());
+
+    cmd()
+        .args(["download", "-C", "bulk", "-V", "8.4.12", "-O", "linux"])
+        .args([
+            "-A",
+            "x86_64",
+            "-B",
+            "fpm",
+            "--no-cache",
+            "--base-url",
+            &base_url,
+        ])
+        .arg("-o")
+        .arg(out.path().join(name))
+        .arg("--extract")
+        .arg(out.path().join("php"))
+        .args(["--rename", "php-fpm8.4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Renamed php-fpm to"));
+
+    let binary = out.path().join("php/php-fpm8.4");
+    assert_eq!(fs::read_to_string(&binary).unwrap(), "FPM");
+    assert_eq!(
+        fs::metadata(&binary).unwrap().permissions().mode() & 0o777,
+        0o755
+    );
+    assert!(!out.path().join("php/sbin/php-fpm").exists());
+    assert_eq!(
+        fs::metadata(out.path().join("php/README"))
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o111,
+        0
+    );
+
+    cmd().args(["download", "--rename", "php"]).assert().code(2);
+}
+
+#[test]
+fn install_from_file_verifies_unpacks_and_records_a_receipt() {
+    let incoming = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let archive = incoming.path().join("php-8.3.12-cli-linux-x86_64.tar.gz");
+    tarball(&archive, &[("php", "PHP"), ("LICENSE", "license")]);
+    let install = || {
+        let mut cmd = cmd();
+        cmd.arg("--data-dir")
+            .arg(data.path())
+            .arg("install")
+            .arg("--from-file")
+            .arg(&archive);
+        cmd
+    };
+
+    install()
+        .args(["--expect-sha256", &"0".repeat(64)])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SHA-256 mismatch"));
+    let installed = data.path().join("versions/php-8.3.12-cli-linux-x86_64");
+    assert!(!installed.exists());
+
+    install()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Installed PHP 8.3.12 (cli)"));
+    assert_eq!(fs::read_to_string(installed.join("php")).unwrap(), "PHP");
+
+    cmd()
+        .arg("--data-dir")
+        .arg(data.path())
+        .args(["verify-install", "8.3.12"])
+        .assert()
+        .success();
+
+    // Already installed: replacing it takes --force.
+    install().assert().failure();
+    install().arg("--force").assert().success();
+
+    // A checksum shipped next to the archive must match too.
+    fs::write(
+        incoming
+            .path()
+            .join("php-8.3.12-cli-linux-x86_64.tar.gz.sha256"),
+        format!("{}  php-8.3.12-cli-linux-x86_64.tar.gz\n", "0".repeat(64)),
+    )
+    .unwrap();
+    install()
+        .arg("--force")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("the .sha256 file"));
+}
+
+#[cfg(unix)]
+#[test]
+fn install_runs_post_install_hook() {
+    let incoming = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let archive = incoming.path().join("php-8.3.12-cli-linux-x86_64.tar.gz");
+    tarball(&archive, &[("php", "PHP")]);
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        "[hooks]\npost_install = \"echo $VERSION $PATH_TO_FILE > hooks.log\"\n",
+    )
+    .unwrap();
+
+    cmd()
+        .current_dir(project.path())
+        .arg("--data-dir")
+        .arg(data.path())
+        .arg("install")
+        .arg("--from-file")
+        .arg(&archive)
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(project.path().join("hooks.log")).unwrap(),
+        format!(
+            "8.3.12 {}\n",
+            data.path()
+                .join("versions/php-8.3.12-cli-linux-x86_64")
+                .display()
+        )
+    );
+
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        "[hooks]\npost_install = \"exit 1\"\n",
+    )
+    .unwrap();
+    cmd()
+        .current_dir(project.path())
+        .arg("--data-dir")
+        .arg(data.path())
+        .args(["install", "--force", "--from-file"])
+        .arg(&archive)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("post_install hook failed"));
+}
+
+#[test]
+fn export_manifest_replays_with_download_and_install() {
+    use sha2::{Digest, Sha256};
+
+    let mirror = tempdir().unwrap();
+    let cache_home = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let work = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let bulk = mirror.path().join("bulk");
+    fs::create_dir_all(&bulk).unwrap();
+    tarball(&bulk.join(name), &[("php", "PHP")]);
+    let size = fs::metadata(bulk.join(name)).unwrap().len();
+    fs::write(bulk.join("index.json"), listing(&[(name, size)])).unwrap();
+    let base_url = format!("file://{}", mirror.path().display());
+    let sha256 = hex::encode(Sha256::digest(fs::read(bulk.join(name)).unwrap()));
+    let run = |args: &[&str]| {
+        let mut cmd = cmd();
+        cmd.current_dir(work.path())
+            .env("XDG_CACHE_HOME", cache_home.path())
+            .arg("--data-dir")
+            .arg(data.path())
+            .args(args);
+        cmd
+    };
+
+    run(&["export-manifest", "-C", "bulk", "-V", "8.4", "-O", "linux"])
+        .args(["-A", "x86_64", "-B", "cli", "--base-url", &base_url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Wrote spc-manifest.json (1 artifacts)",
+        ));
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(work.path().join("spc-manifest.json")).unwrap())
+            .unwrap();
+    assert_eq!(manifest["base_url"], base_url.as_str());
+    let artifact = &manifest["artifacts"][0];
+    assert_eq!(artifact["requested"], "8.4");
+    assert_eq!(artifact["version"], "8.4.12");
+    assert_eq!(artifact["name"], name);
+    assert_eq!(artifact["sha256"], sha256.as_str());
+
+    run(&["download", "--from-manifest", "spc-manifest.json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("matches the manifest"));
+    assert!(work.path().join(name).is_file());
+
+    run(&["install", "--from-manifest", "spc-manifest.json"])
+        .assert()
+        .success();
+    let installed = data.path().join("versions/php-8.4.12-cli-linux-x86_64/php");
+    assert_eq!(fs::read_to_string(installed).unwrap(), "PHP");
+
+    // Anything but the recorded bytes is refused.
+    tarball(&bulk.join(name), &[("php", "EVIL")]);
+    run(&[
+        "download",
+        "--from-manifest",
+        "spc-manifest.json",
+        "--force",
+    ])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("SHA-256 mismatch"));
+    assert!(!work.path().join(name).exists());
+}
+
+#[test]
+fn install_web_profile_keeps_cli_and_fpm_on_one_version() {
+    let mirror = tempdir().unwrap();
+    let cache_home = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let bulk = mirror.path().join("bulk");
+    fs::create_dir_all(&bulk).unwrap();
+    let mut entries = Vec::new();
+    // fpm 8.3.12 isn't out yet, so the pair stays on 8.3.11.
+    for (name, binary) in [
+        ("php-8.3.11-cli-linux-x86_64.tar.gz", "php"),
+        ("php-8.3.11-fpm-linux-x86_64.tar.gz", "php-fpm"),
+        ("php-8.3.12-cli-linux-x86_64.tar.gz", "php"),
+    ] {
+        tarball(&bulk.join(name), &[(binary, name)]);
+        entries.push((name, fs::metadata(bulk.join(name)).unwrap().len()));
+    }
+    fs::write(bulk.join("index.json"), listing(&entries)).unwrap();
+    let base_url = format!("file://{}", mirror.path().display());
+    let install = |version: &str| {
+        let mut cmd = cmd();
+        cmd.env("XDG_CACHE_HOME", cache_home.path())
+            .arg("--data-dir")
+            .arg(data.path())
+            .args(["install", "--profile", "web", "-C", "bulk", "-V", version])
+            .args(["-O", "linux", "-A", "x86_64", "--base-url", &base_url]);
+        cmd
+    };
+
+    install("8.3")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Installing PHP 8.3.11 (cli + fpm)",
+        ));
+    let versions = data.path().join("versions");
+    assert!(versions.join("php-8.3.11-cli-linux-x86_64/php").is_file());
+    assert!(
+        versions
+            .join("php-8.3.11-fpm-linux-x86_64/php-fpm")
+            .is_file()
+    );
+    assert!(!versions.join("php-8.3.12-cli-linux-x86_64").exists());
+
+    // Asking for a version only one of them has installs neither.
+    install("8.3.12")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to download the fpm build"));
+    assert!(!versions.join("php-8.3.12-cli-linux-x86_64").exists());
+
+    // A reinstall whose fpm build turns out broken leaves both old installs.
+    let fpm = bulk.join("php-8.3.11-fpm-linux-x86_64.tar.gz");
+    let len = fs::metadata(&fpm).unwrap().len() as usize;
+    fs::write(&fpm, vec![b'x'; len]).unwrap();
+    install("8.3.11")
+        .args(["--force", "--no-cache"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Failed to install the web profile",
+        ));
+    assert_eq!(
+        fs::read_to_string(versions.join("php-8.3.11-cli-linux-x86_64/php")).unwrap(),
+        "php-8.3.11-cli-linux-x86_64.tar.gz"
+    );
+    assert!(
+        versions
+            .join("php-8.3.11-fpm-linux-x86_64/php-fpm")
+            .is_file()
+    );
+    let leftovers: Vec<_> = fs::read_dir(&versions)
+        .unwrap()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(".staging-"))
+        .collect();
+    assert!(leftovers.is_empty());
+
+    cmd()
+        .args(["install", "--profile", "web", "-C", "win-max"])
+        .arg("--data-dir")
+        .arg(data.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("has no fpm builds"));
+    // Refused before any listing is fetched from the unreachable server.
+    cmd()
+        .args(["install", "--profile", "web", "-C", "frankenphp"])
+        .args(["--base-url", "http://127.0.0.1:9", "--data-dir"])
+        .arg(data.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "The frankenphp category has no cli or fpm builds",
+        ));
+}
+
+#[test]
+fn install_installs_the_configured_targets() {
+    let mirror = tempdir().unwrap();
+    let cache_home = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    let bulk = mirror.path().join("bulk");
+    fs::create_dir_all(&bulk).unwrap();
+    let mut entries = Vec::new();
+    for (name, binary) in [
+        ("php-8.3.11-cli-linux-x86_64.tar.gz", "php"),
+        ("php-8.3.12-cli-linux-x86_64.tar.gz", "php"),
+        ("php-8.3.11-fpm-linux-x86_64.tar.gz", "php-fpm"),
+    ] {
+        tarball(&bulk.join(name), &[(binary, name)]);
+        entries.push((name, fs::metadata(bulk.join(name)).unwrap().len()));
+    }
+    fs::write(bulk.join("index.json"), listing(&entries)).unwrap();
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        r#"
+[targets.cli]
+category = "bulk"
+version = "8.3"
+os = "linux"
+arch = "x86_64"
+
+[targets.fpm]
+category = "bulk"
+version = "8.3.11"
+os = "linux"
+arch = "x86_64"
+build_type = "fpm"
+"#,
+    )
+    .unwrap();
+    let base_url = format!("file://{}", mirror.path().display());
+    let install = || {
+        let mut cmd = cmd();
+        cmd.env("XDG_CACHE_HOME", cache_home.path())
+            .current_dir(project.path())
+            .arg("--data-dir")
+            .arg(data.path())
+            .args(["install", "--base-url", &base_url]);
+        cmd
+    };
+
+    let versions = data.path().join("versions");
+    install().args(["--target", "fpm"]).assert().success();
+    assert!(
+        versions
+            .join("php-8.3.11-fpm-linux-x86_64/php-fpm")
+            .is_file()
+    );
+    assert!(!versions.join("php-8.3.12-cli-linux-x86_64").exists());
+
+    install()
+        .arg("--force")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[cli]"))
+        .stdout(predicate::str::contains("[fpm]"));
+    assert!(versions.join("php-8.3.12-cli-linux-x86_64/php").is_file());
+
+    install()
+        .args(["--target", "nonsense"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("nonsense"));
+
+    // Without targets there is nothing to install.
+    cmd()
+        .arg("--data-dir")
+        .arg(data.path())
+        .current_dir(mirror.path())
+        .arg("install")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("unless targets are defined"));
+}
+
+#[test]
+fn builds_for_another_platform_are_flagged_and_need_target_confirm_to_install() {
+    let foreign = if cfg!(target_os = "linux") {
+        "macos"
+    } else {
+        "linux"
+    };
+    let name = format!("php-8.3.12-cli-{}-x86_64.tar.gz", foreign);
+    let mirror = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let base_url = local_mirror(mirror.path(), "bulk", &[&name]);
+
+    cmd()
+        .args([
+            "download", "-C", "bulk", "-V", "8.3.12", "-O", foreign, "-A", "x86_64",
+        ])
+        .args(["--no-cache", "--base-url", &base_url, "-o"])
+        .arg(out.path().join(&name))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "Note: this is a {}-x86_64 build",
+            foreign
+        )));
+
+    let install = || {
+        let mut cmd = cmd();
+        cmd.arg("--data-dir")
+            .arg(data.path())
+            .arg("install")
+            .arg("--from-file")
+            .arg(out.path().join(&name));
+        cmd
+    };
+    fs::remove_file(out.path().join(&name)).unwrap();
+    tarball(&out.path().join(&name), &[("php", "PHP")]);
+
+    install()
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--target-confirm"));
+    assert!(!data.path().join("versions").exists());
+
+    install().arg("--target-confirm").assert().success();
+}
+
+#[test]
+fn downloads_are_verified_against_published_checksums() {
+    use sha2::{Digest, Sha256};
+
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let name = "php-8.3.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+    let sidecar = mirror.path().join("bulk").join(format!("{}.sha256", name));
+    let output = out.path().join(name);
+    let download = || {
+        let mut cmd = cmd();
+        cmd.args([
+            "download", "-C", "bulk", "-V", "8.3.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--force", "--base-url", &base_url, "-o"])
+        .arg(&output);
+        cmd
+    };
+
+    fs::write(
+        &sidecar,
+        format!("{}  {}\n", hex::encode(Sha256::digest(name)), name),
+    )
+    .unwrap();
+    download()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Verified against the published SHA-256",
+        ));
+
+    fs::write(&sidecar, "0".repeat(64)).unwrap();
+    download()
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("the server publishes 0000"));
+    assert!(!output.exists());
+
+    download().arg("--no-verify").assert().success();
+    assert!(output.is_file());
+}
+
+/// A minisign key pair as `(public key line, signer)`, where the signer turns
+/// file contents into a prehashed `.minisig` document.
+fn minisign_key(seed: u8) -> (String, impl Fn(&[u8]) -> String) {
+    use blake2::{Blake2b512, Digest};
+    use ct_codecs::{Base64, Encoder};
+
+    let pair = ed25519_compact::KeyPair::from_seed(ed25519_compact::Seed::new([seed; 32]));
+    let key_id = [seed; 8];
+    let encode = |bytes: &[u8]| Base64::encode_to_string(bytes).unwrap();
+    let public = encode(&[b"Ed".as_slice(), &key_id, pair.pk.as_ref()].concat());
+
+    let sign = move |contents: &[u8]| {
+        let signature = pair.sk.sign(Blake2b512::digest(contents), None);
+        let comment = "timestamp:1736937000";
+        let global = pair
+            .sk
+            .sign([signature.as_ref(), comment.as_bytes()].concat(), None);
+        format!(
+            "untrusted comment: test\n{}\ntrusted comment: {}\n{}\n",
+            encode(&[b"ED".as_slice(), &key_id, signature.as_ref()].concat()),
+            comment,
+            encode(global.as_ref())
+        )
+    };
+    (public, sign)
+}
+
+#[test]
+fn verify_signature_checks_the_published_minisign_signature() {
+    let mirror = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let name = "php-8.3.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "bulk", &[name]);
+    let signature = mirror.path().join("bulk").join(format!("{}.minisig", name));
+    let output = out.path().join(name);
+    let (trusted, sign) = minisign_key(1);
+    let (_, sign_with_other_key) = minisign_key(2);
+    let download = |key: &str| {
+        let mut cmd = cmd();
+        cmd.args([
+            "download", "-C", "bulk", "-V", "8.3.12", "-O", "linux", "-A", "x86_64",
+        ])
+        .args(["--force", "--base-url", &base_url, "--verify-signature"])
+        .args(["--public-key", key, "-o"])
+        .arg(&output);
+        cmd
+    };
+
+    download(&trusted)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no signature is published"));
+    assert!(!output.exists());
+
+    fs::write(&signature, sign(name.as_bytes())).unwrap();
+    download(&trusted)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Signature verified (trusted comment: timestamp:1736937000)",
+        ));
+
+    fs::write(&signature, sign(b"something else")).unwrap();
+    download(&trusted)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("the signature doesn't match"));
+    assert!(!output.exists());
+
+    fs::write(&signature, sign_with_other_key(name.as_bytes())).unwrap();
+    download(&trusted).assert().failure();
+
+    // Without a key there is nothing to trust.
+    cmd()
+        .current_dir(out.path())
+        .args([
+            "download",
+            "-C",
+            "bulk",
+            "-V",
+            "8.3.12",
+            "--verify-signature",
+        ])
+        .args(["--base-url", &base_url, "-o", "php.tar.gz"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs a trusted key"));
+}
+
+#[test]
+fn micro_fetch_extracts_and_caches_micro_sfx() {
+    let mirror = tempdir().unwrap();
+    let cache_home = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let name = "php-8.3.12-micro-linux-x86_64.tar.gz";
+
+    // A micro artifact: micro.sfx next to other files in a gzipped tarball.
+    let bulk = mirror.path().join("bulk");
+    fs::create_dir_all(&bulk).unwrap();
+    tarball(
+        &bulk.join(name),
+        &[("LICENSE", "license"), ("micro.sfx", "MICRO")],
+    );
+    let size = fs::metadata(bulk.join(name)).unwrap().len();
+    fs::write(bulk.join("index.json"), listing(&[(name, size)])).unwrap();
+    let base_url = format!("file://{}", mirror.path().display());
+
+    let fetch = || {
+        let mut cmd = cmd();
+        cmd.env("XDG_CACHE_HOME", cache_home.path())
+            .args(["micro", "fetch", "-C", "bulk", "-V", "8.3", "-O", "linux"])
+            .args(["-A", "x86_64", "--base-url", &base_url, "-o"])
+            .arg(out.path().join("micro.sfx"));
+        cmd
+    };
+
+    fetch()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Saved micro.sfx from {}",
+            name
+        )));
+    assert_eq!(
+        fs::read_to_string(out.path().join("micro.sfx")).unwrap(),
+        "MICRO"
+    );
+
+    // Served from the cache once extracted, even without the archive.
+    fs::remove_file(bulk.join(name)).unwrap();
+    fs::remove_file(out.path().join("micro.sfx")).unwrap();
+    fetch()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using cached micro.sfx"));
+    assert_eq!(
+        fs::read_to_string(out.path().join("micro.sfx")).unwrap(),
+        "MICRO"
+    );
+}
+
+#[test]
+fn categories_finds_smallest_category_with_extensions() {
+    cmd()
+        .args(["categories", "--with", "gd,redis", "-O", "linux"])
+        .assert()
+        .success()
+        .stdout("common\n");
+
+    cmd()
+        .args(["categories", "--with", "imagick,ffi", "-O", "linux"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("bulk lacks ffi"));
+
+    cmd()
+        .args(["categories", "minimal", "--compare", "common"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Only in minimal: -"));
+}
+
+#[test]
+fn resolve_picks_category_and_version_from_requirements() {
+    let cache_home = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("requirements.toml"),
+        "php = \"8.3\"\nextensions = [\"gd\", \"redis\"]\n",
+    )
+    .unwrap();
+    let common = listing(&[
+        ("php-8.3.10-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.3.12-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.4.1-cli-linux-x86_64.tar.gz", 10),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("common", common.clone())])
+        .current_dir(project.path())
+        .args(["resolve", "--requirements", "requirements.toml"])
+        .args(["-O", "linux", "-A", "x86_64"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Category: common"))
+        .stdout(predicate::str::contains("Version:  8.3.12"))
+        .stdout(predicate::str::contains(
+            "Artifact: php-8.3.12-cli-linux-x86_64.tar.gz",
+        ));
+
+    cmd_with_cache(cache_home.path(), &[("common", common)])
+        .current_dir(project.path())
+        .args(["resolve", "--requirements", "requirements.toml"])
+        .args(["--require-ext", "ffi", "-O", "linux"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("bulk lacks ffi"));
+}
+
+#[test]
+fn resolution_reports_candidates_defaults_and_missing_builds() {
+    let cache_home = tempdir().unwrap();
+    let common = listing(&[
+        ("php-8.3.10-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.3.12-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.3.12-fpm-linux-x86_64.tar.gz", 10),
+    ]);
+
+    let output = cmd_with_cache(cache_home.path(), &[("common", common.clone())])
+        .args(["resolve", "--require-ext", "gd", "-V", "8.3"])
+        .args(["-O", "linux", "-A", "x86_64", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let resolved: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(resolved["name"], "php-8.3.12-cli-linux-x86_64.tar.gz");
+    assert_eq!(resolved["candidates"], 2);
+    assert_eq!(
+        resolved["defaults"],
+        serde_json::json!({"build_type": "cli"})
+    );
+
+    // No matching build is an error, not a crash.
+    cmd_with_cache(cache_home.path(), &[("common", common)])
+        .args(["latest", "-C", "common", "-V", "8.2"])
+        .args(["-O", "linux", "-A", "x86_64"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "No cli build for linux-x86_64 8.2.x in the common listing",
+        ));
+}
+
+#[test]
+fn download_checks_required_extensions_against_category() {
+    let out = tempdir().unwrap();
+
+    cmd()
+        .current_dir(out.path())
+        .args([
+            "download",
+            "-C",
+            "minimal",
+            "--require-ext",
+            "imagick",
+            "-o",
+            "php",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "The minimal category doesn't include imagick",
+        ));
+    assert!(!out.path().join("php").exists());
+
+    // Without -C the smallest category with the extensions is used.
+    let mirror = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let base_url = local_mirror(mirror.path(), "common", &[name]);
+    cmd()
+        .current_dir(out.path())
+        .args(["download", "--require-ext", "gd,redis", "-O", "linux"])
+        .args(["-A", "x86_64", "--base-url", &base_url, "-o", "php"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using the common category"));
+    assert_eq!(fs::read_to_string(out.path().join("php")).unwrap(), name);
+}
+
+#[test]
+fn latest_matrix_marks_lagging_platforms() {
+    let cache_home = tempdir().unwrap();
+    let bulk = listing(&[
+        ("php-8.4.12-cli-linux-x86_64.tar.gz", 10),
+        ("php-8.4.12-cli-linux-aarch64.tar.gz", 10),
+        ("php-8.4.12-fpm-linux-x86_64.tar.gz", 10),
+        ("php-8.4.10-fpm-linux-aarch64.tar.gz", 10),
+    ]);
+
+    cmd_with_cache(cache_home.path(), &[("bulk", bulk)])
+        .args(["latest", "-C", "bulk", "--matrix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("linux-aarch64"))
+        .stdout(predicate::str::contains("8.4.10*"))
+        .stdout(predicate::str::contains("* behind 8.4.12"));
+}
+
+#[test]
+fn version_reports_build_details() {
+    cmd()
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(concat!(
+            "spc-utils ",
+            env!("CARGO_PKG_VERSION"),
+            "\ncommit: "
+        )))
+        .stdout(predicate::str::contains("\ntarget: "))
+        .stdout(predicate::str::contains(
+            "base URL: https://dl.static-php.dev/static-php-cli",
+        ));
+
+    let output = cmd().args(["version", "--json"]).output().unwrap();
+    assert!(output.status.success());
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert!(!info["commit"].as_str().unwrap().is_empty());
+    assert!(!info["target"].as_str().unwrap().is_empty());
+    assert!(info["features"].is_array());
+}
+
+#[test]
+fn self_update_check_hints_at_newer_release_unless_opted_out() {
+    let cache_home = tempdir().unwrap();
+    let project = tempdir().unwrap();
+    let cache_dir = cache_home.path().join("spc-utils");
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join(".version"), env!("CARGO_PKG_VERSION")).unwrap();
+    // Checked just now, so nothing is fetched.
+    fs::write(
+        cache_dir.join("self-check.json"),
+        format!(
+            r#"{{"checked_at":"{}","latest":"99.0.0"}}"#,
+            chrono::Utc::now().to_rfc3339()
+        ),
+    )
+    .unwrap();
+
+    let parse = || {
+        let mut cmd = cmd();
+        cmd.current_dir(project.path())
+            .env("XDG_CACHE_HOME", cache_home.path())
+            .env_remove("SPC_UTILS_NO_SELF_CHECK")
+            .args(["parse", "php-8.3.12-cli-win.zip"]);
+        cmd
+    };
+
+    // Off unless enabled in the project config.
+    parse().assert().success().stderr("");
+
+    fs::write(
+        project.path().join(".spc-utils.toml"),
+        "self_update_check = true\n",
+    )
+    .unwrap();
+    parse()
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "spc-utils 99.0.0 is available (you have {})",
+            env!("CARGO_PKG_VERSION")
+        )));
+
+    parse().arg("--no-self-check").assert().success().stderr("");
+    parse()
+        .env("SPC_UTILS_NO_SELF_CHECK", "1")
+        .assert()
+        .success()
+        .stderr("");
+}
+
+#[test]
+fn examples_filter_by_command_and_render_as_json() {
+    cmd()
+        .args(["examples", "download"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("spc-utils download -o php"))
+        .stdout(predicate::str::contains("spc-utils latest").not());
+
+    // --examples skips the command's own required arguments.
+    cmd()
+        .args(["sync", "--examples"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "spc-utils sync --dest /srv/spc-mirror",
+        ))
+        .stdout(predicate::str::contains("spc-utils download").not());
+    cmd()
+        .args(["cache", "--examples"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("spc-utils cache clear -C bulk"));
+
+    let output = cmd().args(["examples", "--json"]).output().unwrap();
+    assert!(output.status.success());
+    let examples: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let parse: Vec<_> = examples
+        .iter()
+        .filter(|example| example["command"] == "parse")
+        .collect();
+    assert_eq!(parse.len(), 2);
+    assert!(parse.iter().all(|example| {
+        example["invocation"]
+            .as_str()
+            .unwrap()
+            .starts_with("spc-utils parse ")
+    }));
+
+    cmd().args(["examples", "nonsense"]).assert().code(2);
+}
+
+#[cfg(unix)]
+#[test]
+fn check_update_install_replaces_binary_after_verifying_it() {
+    use sha2::{Digest, Sha256};
+    use std::os::unix::fs::PermissionsExt;
+
+    let mirror = tempdir().unwrap();
+    let data_dir = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let bin = out.path().join("php");
+    let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+    let php = |version: &str| format!("#!/bin/sh\necho {}\n", version);
+
+    // Stand-ins for PHP that only report their version.
+    let bulk = mirror.path().join("bulk");
+    fs::create_dir_all(&bulk).unwrap();
+    let mut names = Vec::new();
+    for version in ["8.4.10", "8.4.12"] {
+        let name = format!("php-{}-cli-{}.tar.gz", version, platform);
+        tarball(&bulk.join(&name), &[("php", &php(version))]);
+        names.push(name);
+    }
+    let entries: Vec<(&str, u64)> = names.iter().map(|name| (name.as_str(), 1)).collect();
+    fs::write(bulk.join("index.json"), listing(&entries)).unwrap();
+    let base_url = format!("file://{}", mirror.path().display());
+
+    fs::write(&bin, php("8.4.10")).unwrap();
+    fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let install = || {
+        let mut cmd = cmd();
+        cmd.args(["check-update", "-C", "bulk", "--install", "--bin"])
+            .arg(&bin)
+            .args(["--base-url", &base_url, "--data-dir"])
+            .arg(data_dir.path());
+        cmd
+    };
+
+    let sha256 = bulk.join(format!("{}.sha256", names[1]));
+    fs::write(&sha256, format!("{}  {}\n", "0".repeat(64), names[1])).unwrap();
+    install().assert().code(1).stderr(predicate::str::contains(
+        "does not match its published SHA-256",
+    ));
+    assert_eq!(fs::read_to_string(&bin).unwrap(), php("8.4.10"));
+
+    let archive = fs::read(bulk.join(&names[1])).unwrap();
+    fs::write(&sha256, hex::encode(Sha256::digest(&archive))).unwrap();
+    install()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Update available: 8.4.10 -> 8.4.12",
+        ))
+        .stdout(predicate::str::contains(format!(
+            "Installed PHP 8.4.12 to {}",
+            bin.display()
+        )));
+    assert_eq!(fs::read_to_string(&bin).unwrap(), php("8.4.12"));
+
+    // Nothing to do once the binary reports the newest version.
+    install()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is up to date"));
+    assert!(!out.path().join(&names[1]).exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn check_update_install_takes_the_frankenphp_binary() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mirror = tempdir().unwrap();
+    let data_dir = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let bin = out.path().join("frankenphp");
+    let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+    let frankenphp = |version: &str| format!("#!/bin/sh\necho {}\n", version);
+
+    let dir = mirror.path().join("frankenphp");
+    fs::create_dir_all(&dir).unwrap();
+    let name = format!("php-8.4.12-frankenphp-{}.tar.gz", platform);
+    tarball(&dir.join(&name), &[("frankenphp", &frankenphp("8.4.12"))]);
+    fs::write(dir.join("index.json"), listing(&[(name.as_str(), 1)])).unwrap();
+
+    fs::write(&bin, frankenphp("8.4.10")).unwrap();
+    fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+
+    cmd()
+        .args(["check-update", "-C", "frankenphp", "--install", "--bin"])
+        .arg(&bin)
+        .args(["--base-url", &format!("file://{}", mirror.path().display())])
+        .arg("--data-dir")
+        .arg(data_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Installed PHP 8.4.12"));
+    assert_eq!(fs::read_to_string(&bin).unwrap(), frankenphp("8.4.12"));
+}
+
+#[test]
+fn refreshed_listings_report_what_changed() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let kept = "php-8.3.12-cli-linux-x86_64.tar.gz";
+    let added = "php-8.3.13-cli-linux-x86_64.tar.gz";
+    let updated = [
+        "php-8.3.12-fpm-linux-x86_64.tar.gz",
+        "php-8.3.12-micro-linux-x86_64.tar.gz",
+    ];
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let body = listing(&[
+        (kept, 100),
+        (added, 100),
+        (updated[0], 250),
+        (updated[1], 250),
+    ]);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    let previous = listing(&[(kept, 100), (updated[0], 200), (updated[1], 200)]);
+
+    // Any command that fetches the listing again says what changed.
+    let cache_home = tempdir().unwrap();
+    cmd_with_cache(cache_home.path(), &[("bulk", previous.clone())])
+        .args([
+            "latest",
+            "-C",
+            "bulk",
+            "--no-cache",
+            "--base-url",
+            &base_url,
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "Refreshed the bulk listing: + {}, 2 artifacts updated",
+            added
+        )));
+
+    let cache_home = tempdir().unwrap();
+    let output = cmd_with_cache(cache_home.path(), &[("bulk", previous)])
+        .args(["cache", "refresh", "--json", "--base-url", &base_url])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let refreshed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(refreshed[0]["category"], "bulk");
+    assert_eq!(refreshed[0]["entry_count"], 4);
+    let changes = refreshed[0]["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[0]["name"], added);
+    assert_eq!(changes[0]["kind"], "appeared");
+    assert_eq!(changes[1]["kind"], "changed");
+
+    // Nothing changed since the last refresh.
+    cmd_with_cache(cache_home.path(), &[])
+        .args(["cache", "refresh", "-C", "bulk", "--base-url", &base_url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bulk: 4 entries, no changes"));
+}
+
+#[test]
+fn history_remote_records_changes_between_listings() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let kept = "php-8.4.11-cli-linux-x86_64.tar.gz";
+    let republished = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let retracted = "php-8.4.10-cli-linux-x86_64.tar.gz";
+    let added = "php-8.4.13-cli-linux-x86_64.tar.gz";
+
+    // Upstream now serves a listing that differs from the cached one.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let body = listing(&[(kept, 100), (republished, 250), (added, 300)]);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+
+    let cache_home = tempdir().unwrap();
+    let previous = listing(&[(retracted, 100), (kept, 100), (republished, 200)]);
+    let output = cmd_with_cache(cache_home.path(), &[("bulk", previous)])
+        .args([
+            "history",
+            "remote",
+            "-C",
+            "bulk",
+            "--no-cache",
+            "--base-url",
+        ])
+        .arg(&base_url)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.starts_with("History of bulk since "));
+    let line = |name: &str| {
+        stdout
+            .lines()
+            .find(|l| l.contains(name))
+            .map(str::to_string)
+    };
+    assert!(line(kept).is_none());
+    assert!(line(added).unwrap().contains("appeared"));
+    assert!(line(republished).unwrap().contains("changed"));
+    assert!(line(retracted).unwrap().contains("removed"));
+
+    // The recorded history is shown again without another change upstream.
+    let output = cmd_with_cache(cache_home.path(), &[])
+        .args(["history", "remote", "-C", "bulk", "--json", "--base-url"])
+        .arg(&base_url)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let history: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let events = history["events"].as_array().unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0]["name"], republished);
+    assert_eq!(events[0]["kind"], "changed");
+    assert_eq!(events[0]["previous_size"], "200");
+    assert_eq!(events[1]["kind"], "appeared");
+    assert_eq!(events[2]["kind"], "removed");
+}
+
+#[test]
+fn list_warns_once_about_republished_artifacts() {
+    use sha2::{Digest, Sha256};
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let body = listing(&[(name, 250)]);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+
+    // The cached listing and artifact are of the earlier publication, and the
+    // listing has expired.
+    let cache_home = tempdir().unwrap();
+    cmd_with_cache(cache_home.path(), &[("bulk", listing(&[(name, 200)]))]);
+    let cache_dir = cache_home.path().join("spc-utils");
+    fs::File::options()
+        .write(true)
+        .open(cache_dir.join("bulk.json"))
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 86400))
+        .unwrap();
+    fs::create_dir_all(cache_dir.join("artifacts/bulk")).unwrap();
+    fs::write(cache_dir.join("artifacts/bulk").join(name), "old").unwrap();
+
+    let list = || {
+        let mut cmd = cmd_with_cache(cache_home.path(), &[]);
+        cmd.args(["list", "-C", "bulk", "-O", "linux", "-A", "x86_64"])
+            .args(["--base-url", &base_url]);
+        cmd
+    };
+    list()
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "WARNING: {} was re-published upstream",
+            name
+        )))
+        .stderr(predicate::str::contains(format!(
+            "the copy cached before had SHA-256 {}",
+            hex::encode(Sha256::digest("old"))
+        )));
+
+    // Recorded once, and warned about once.
+    list()
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("re-published").not());
+    let records: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(cache_dir.join("bulk.republished.json")).unwrap())
+            .unwrap();
+    assert_eq!(records.as_array().unwrap().len(), 1);
+
+    cmd_with_cache(cache_home.path(), &[])
+        .args(["cache", "clear", "-C", "bulk"])
+        .assert()
+        .success();
+    assert!(!cache_dir.join("bulk.republished.json").exists());
+}
+
+#[test]
+fn github_source_uses_asset_urls_from_its_own_cache() {
+    let cache_home = tempdir().unwrap();
+    let assets = tempdir().unwrap();
+    let out = tempdir().unwrap();
+    let name = "php-8.4.12-cli-linux-x86_64.tar.gz";
+    let asset_path = assets.path().join("release-asset");
+    fs::write(&asset_path, "from github").unwrap();
+
+    let github_cache = cache_home
+        .path()
+        .join("spc-utils")
+        .join("sources")
+        .join("github");
+    fs::create_dir_all(&github_cache).unwrap();
+    fs::write(
+        github_cache.join("bulk.json"),
+        listing(&[(name, 11)]).replace(
+            &format!("/static-php-cli/{}", name),
+            &format!("file://{}", asset_path.display()),
+        ),
+    )
+    .unwrap();
+
+    cmd_with_cache(cache_home.path(), &[])
+        .args([
+            "download",
+            "--source",
+            "github",
+            "--github-repo",
+            "example/php-builds",
+        ])
+        .args(["-C", "bulk", "-O", "linux", "-A", "x86_64", "-o"])
+        .arg(out.path().join("php"))
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(out.path().join("php")).unwrap(),
+        "from github"
+    );
+}
+
+#[test]
+fn parse_decomposes_unix_artifact() {
+    cmd()
+        .args(["parse", "php-8.3.12-fpm-linux-aarch64.tar.gz"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("version:    8.3.12"))
+        .stdout(predicate::str::contains("build type: fpm"))
+        .stdout(predicate::str::contains("os:         linux"))
+        .stdout(predicate::str::contains("arch:       aarch64"))
+        .stdout(predicate::str::contains(
+            "categories: bulk, common, minimal",
+        ));
+}
+
+#[test]
+fn parse_windows_artifact_as_json() {
+    cmd()
+        .args(["parse", "php-8.3.12-cli-win.zip", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""extension": "zip""#))
+        .stdout(predicate::str::contains(r#""arch": null"#))
+        .stdout(predicate::str::contains(r#""win-min""#));
+}
+
+#[test]
+fn parse_rejects_unknown_names() {
+    cmd().args(["parse", "README.md"]).assert().failure();
+}